@@ -7,48 +7,17 @@ path_to_data = "/Users/krq770/CLionProjects/timelymon/mfodl_monitor/data/"
 path_to_monpoly = "/Users/krq770/Desktop/Experiments_Stream_Monitor/monpoly"
 
 
-def parse_tp(line):
-  line.strip()
-  lines = line.split(",")
-  tp = lines[1].split("=")
-  return int(tp[1])
-
-def write_file(data, name, file_name):
-	with open(name + file_name, "w") as f:
-		for d in data:
-			f.write(d)
-
-def reverse(file, file_name):
-	file.reverse()
-	write_file(file, "reverse_", file_name)
-	
-
-def reorder_epoch(file, file_name):
-	segment_tp = parse_tp(file[0])
-	segements = []
-
-	current_segments = []
-	for line in file:
-		if segment_tp == parse_tp(line):
-			current_segments.append(line)
-		else:
-			segements.append(current_segments)
-			current_segments = []
-			segment_tp = parse_tp(line)
-			current_segments.append(line)
-	segements.append(current_segments)
-	random.shuffle(segements)
-
-	res = []
-	for seg in segements:
-		for s in seg:
-			res.append(s)
-
-	write_file(res, "reorder_epoch_", file_name)
-
-def reorder_tuple(file, file_name):
-	random.shuffle(file)
-	write_file(file, "reorder_tuple_", file_name)
+def reorder(command, input_name, output_name):
+	os.system(path_to_mfodl + "/target/release/timelymon-trace " + command + " " + input_name + " -o " + output_name)
+
+def reverse(file_name):
+	reorder("reverse", file_name, "reverse_" + file_name)
+
+def reorder_epoch(file_name):
+	reorder("shuffle --seed " + str(random.randrange(2**32)), file_name, "reorder_epoch_" + file_name)
+
+def reorder_tuple(file_name):
+	reorder("shuffle --events --seed " + str(random.randrange(2**32)), file_name, "reorder_tuple_" + file_name)
 
 
 # create folder structure
@@ -106,39 +75,20 @@ for (formula, directory, form) in [("T", "Triangle", "((Once[0,7] A(a,b)) && B(b
 		if not os.path.exists(file_name):
 			os.system(base_com + rest)
 
-		new_file_content = []
-		for line in open(file_name):
-  			new_file_content.append(line)
-
 		if not os.path.exists("verimon_" + set_size + "K"):
-  			os.system(docker + ":/work infsec/benchmark replayer -f verimon -a 0 < " + file_name + " > verimon_" + set_size + "K")
+  			os.system(path_to_mfodl + "/target/release/replayer " + file_name + " --to monpoly -o verimon_" + set_size + "K")
 		if not os.path.exists("result_" + set_size + "K"):
 			os.chdir(path_to_monpoly)
 			os.system("./monpoly -sig Benchmark.sig -formula " + directory + "_formula.txt -ignore_parse_errors -log " + path_to_data + directory + "/verimon_" + set_size + "K > " + path_to_data + directory + "/result_" + set_size + "K")
 			os.chdir(path_to_data + directory)
 		if not os.path.exists("reverse_" + file_name):
-			reverse(new_file_content, file_name)
+			reverse(file_name)
 		if not os.path.exists("reorder_epoch_" + file_name):
-			reorder_epoch(new_file_content, file_name)
+			reorder_epoch(file_name)
 		if not os.path.exists("reorder_tuple_" + file_name):
-			reorder_tuple(new_file_content, file_name)
-		if not os.path.exists(file_name):
-			new_file_content = []
-			for line in open(file_name):
-  				new_file_content.append(line)
-
-			segment_tp = parse_tp(new_file_content[0])
-			segements = []
-			for line in new_file_content:
-  				if segment_tp == parse_tp(line):
-  					segements.append(line)
-  				else:
-  					segements.append(">WATERMARK " + str(segment_tp) + "<\n")
-  					segment_tp = parse_tp(line)
-  					segements.append(line)
-  
-			out = open(file_name, "w")
-			out.writelines(segements)
+			reorder_tuple(file_name)
+		if not os.path.exists("wm_" + file_name):
+			reorder("watermarks", file_name, "wm_" + file_name)
 print("-----------Base data sets created")
 
 
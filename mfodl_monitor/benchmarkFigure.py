@@ -1,39 +1,40 @@
-import os
+import csv
+import sys
 import numpy as np
 import matplotlib.pyplot as plt
 
+# CSV written by the benchmark binary, e.g.
+# cargo run --release --bin benchmark -- -p "..." -d data/cleaned_data1800.csv -w 1 4 -s 1 100 -o benchmark_data/benchmark.csv
+results_file = sys.argv[1] if len(sys.argv) > 1 else 'benchmark_data/benchmark.csv'
+column = sys.argv[2] if len(sys.argv) > 2 else 'seconds'
+
 def read_results(file_path):
-    with open(file_path, 'r') as file:
-        lines = file.readlines()
-    return np.array([float(line.strip()) for line in lines])
+    groups = {}
+    with open(file_path, 'r', newline='') as file:
+        for row in csv.DictReader(file):
+            if row[column] == '':
+                continue
+            key = (row['policy'], row['dataset'], int(row['step']), int(row['workers']))
+            groups.setdefault(key, []).append(float(row[column]))
+    return groups
 
 def plot_results(data, labels, title):
     fig, ax = plt.subplots()
     ax.boxplot(data, labels=labels)
     ax.set_title(title)
-    ax.set_ylabel('Time (seconds)')
+    ax.set_ylabel(column)
     plt.xticks(rotation=45)
     plt.tight_layout()
     plt.show()
 
 
-base_path = 'benchmark_data'
-datasets = ['csv', 'json', 'pipe']
-step_sizes = ['step1', 'step100']
-workers = [1, 4]
-sizes = [1800]
+groups = read_results(results_file)
 
 data = []
 labels = []
 
-for dataset in datasets:
-    for step_size in step_sizes:
-        for worker in workers:
-            file_name = f'{dataset}_data{sizes[0]}_{worker}w.txt'
-            file_path = os.path.join(base_path, dataset, step_size, file_name)
-            if os.path.exists(file_path):
-                results = read_results(file_path)
-                data.append(results)
-                labels.append(f'{dataset} {step_size} {worker}w')
-
-plot_results(data, labels, 'Benchmark Results: Largest Dataset with Various Configurations')
+for (policy, dataset, step, workers) in sorted(groups):
+    data.append(np.array(groups[(policy, dataset, step, workers)]))
+    labels.append(f'{dataset} step{step} {workers}w')
+
+plot_results(data, labels, 'Benchmark Results: ' + ', '.join(sorted({key[0] for key in groups})))
@@ -3,6 +3,14 @@
 # Number of iterations
 N=20
 
+# Results in the CSV schema of the benchmark binary, read by benchmarkFigure.py and boxplot.py.
+# Verdicts, latencies and peak memory are only measured by the benchmark binary and left empty
+results_file="benchmark_data/pipe.csv"
+mkdir -p benchmark_data
+if [ ! -s "$results_file" ]; then
+    echo "policy,dataset,workers,step,run,events,verdicts,seconds,events_per_second,latency_p50_ms,latency_p90_ms,latency_p99_ms,latency_max_ms,peak_rss_kb" > "$results_file"
+fi
+
 # Array of JSON data files
 json_files=("cleaned_data300.json" "cleaned_data600.json" "cleaned_data900.json" "cleaned_data1800.json")
 
@@ -18,12 +26,9 @@ for json_file in "${json_files[@]}"; do
     for workers in "${worker_counts[@]}"; do
         # Loop through all step sizes
         for step_size in "${step_sizes[@]}"; do
-            # Set the output file name based on the JSON file, worker count, and step size
-            json_size=$(echo "$json_file" | cut -d'_' -f2 | cut -d'.' -f1)
-            timelymon_times_file="pipe_${json_size}_${workers}w_s${step_size}.txt"
-
-            # Clear the file or create it if it doesn't exist
-            > "$timelymon_times_file"
+            # Dataset name and number of events of the file
+            dataset="${json_file%.*}"
+            events=$(grep -c . "data/$json_file")
 
             # Execute the command chain N times
             for i in $(seq 1 $N); do
@@ -33,8 +38,8 @@ for json_file in "${json_files[@]}"; do
                 # Extract the total time from the output for the entire chain, convert it to seconds, and format it to three decimal places
                 timelymon_time=$(echo "$output" | awk '/real/ { split($2, time, /[ms]/); printf "%.3f\n", time[1] * 60 + time[2] }')
 
-                # Append time to the file
-                echo "$timelymon_time" >> "$timelymon_times_file"
+                # Append a row with the time and the events per second
+                echo "edit_once,$dataset,$workers,$step_size,$((i - 1)),$events,,$timelymon_time,$(awk -v e="$events" -v t="$timelymon_time" 'BEGIN { if (t > 0) printf "%.1f", e / t }'),,,,," >> "$results_file"
             done
         done
     done
@@ -1,50 +1,47 @@
+import csv
+import sys
 import numpy as np
 import matplotlib.pyplot as plt
-import os
-
-sizes = [300, 600, 900, 1800]
-worker_counts = [1, 2, 4, 8]
-iterations = 20
-
-data_dir = './benchmark_data/pipe/step1000/'
-
-plot_data = {size: [] for size in sizes}
-
-for size in sizes:
-    for workers in worker_counts:
-        # filename = f"json_data{size}_{workers}w.txt"
-        # filename = f"csv_data{size}_{workers}w.txt"
-        filename = f"pipe_data{size}_{workers}w.txt"
-        filepath = os.path.join(data_dir, filename)
-        
-        try:
-            with open(filepath, 'r') as file:
-                times = np.array([float(line.strip()) for line in file])
-                
-                mean = np.mean(times)
-                std = np.std(times)
-                median = np.median(times)
-                minimum = np.min(times)
-                maximum = np.max(times)
-                percentiles = np.percentile(times, [25, 50, 75])
-                
-                print(f"Statistics for {filename}:")
-                print(f"Mean: {mean:.3f}, Std: {std:.3f}, Median: {median:.3f}, Min: {minimum:.3f}, Max: {maximum:.3f}")
-                print(f"25th percentile: {percentiles[0]:.3f}, 50th percentile: {percentiles[1]:.3f}, 75th percentile: {percentiles[2]:.3f}\n")
-                
-                plot_data[size].append(times)
-        
-        except FileNotFoundError:
-            print(f"File {filename} not found.")
-
-fig, axs = plt.subplots(1, len(sizes), figsize=(12, 6), sharey=True)
-fig.suptitle('Box Plot of Execution Times by Raw file Size and Worker Count')
-
-for i, size in enumerate(sizes):
-    axs[i].boxplot(plot_data[size], labels=[f"{wc}w" for wc in worker_counts])
-    axs[i].set_title(f"Raw file size: {size}")
-    axs[i].set_xlabel('Number of Workers')
-    axs[i].set_ylabel('Time (seconds)')
+
+# CSV written by the benchmark binary
+results_file = sys.argv[1] if len(sys.argv) > 1 else 'benchmark_data/benchmark.csv'
+step = int(sys.argv[2]) if len(sys.argv) > 2 else None
+
+times = {}
+with open(results_file, 'r', newline='') as file:
+    for row in csv.DictReader(file):
+        if step is not None and int(row['step']) != step:
+            continue
+        times.setdefault(row['dataset'], {}).setdefault(int(row['workers']), []).append(float(row['seconds']))
+
+datasets = sorted(times)
+plot_data = {dataset: [] for dataset in datasets}
+
+for dataset in datasets:
+    for workers in sorted(times[dataset]):
+        runs = np.array(times[dataset][workers])
+
+        mean = np.mean(runs)
+        std = np.std(runs)
+        median = np.median(runs)
+        minimum = np.min(runs)
+        maximum = np.max(runs)
+        percentiles = np.percentile(runs, [25, 50, 75])
+
+        print(f"Statistics for {dataset} with {workers} workers:")
+        print(f"Mean: {mean:.3f}, Std: {std:.3f}, Median: {median:.3f}, Min: {minimum:.3f}, Max: {maximum:.3f}")
+        print(f"25th percentile: {percentiles[0]:.3f}, 50th percentile: {percentiles[1]:.3f}, 75th percentile: {percentiles[2]:.3f}\n")
+
+        plot_data[dataset].append(runs)
+
+fig, axs = plt.subplots(1, len(datasets), figsize=(12, 6), sharey=True, squeeze=False)
+fig.suptitle('Box Plot of Execution Times by Data Set and Worker Count')
+
+for i, dataset in enumerate(datasets):
+    axs[0][i].boxplot(plot_data[dataset], labels=[f"{wc}w" for wc in sorted(times[dataset])])
+    axs[0][i].set_title(f"Data set: {dataset}")
+    axs[0][i].set_xlabel('Number of Workers')
+    axs[0][i].set_ylabel('Time (seconds)')
 
 plt.tight_layout()
 plt.show()
@@ -0,0 +1,243 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use timely;
+use timely::dataflow::operators::{Inspect, Probe, UnorderedInput};
+
+use dataflow_constructor::types::FlowValues::Data;
+use dataflow_constructor::types::TimeFlowValues::{Timestamp, EOS};
+use dataflow_constructor::types::{default_options, TimeFlowValues};
+use parser::binary_log::read_csv_log;
+use parser::csv_parser::{parse_lines_to_segments, Segment};
+use stream_io::latency::{LatencyReport, LatencyTracker};
+use stream_io::reorder::{log_events, with_watermarks};
+use {create_dataflow, parse_formula};
+
+/// Columns of the CSV written by `csv_row`, one row per run.
+pub const CSV_HEADER: &str = "policy,dataset,workers,step,run,events,verdicts,seconds,events_per_second,latency_p50_ms,latency_p90_ms,latency_p99_ms,latency_max_ms,peak_rss_kb";
+
+/// A trace in segments, with a watermark after every `watermark_period` complete time points.
+#[derive(Clone, Debug)]
+pub struct Dataset {
+    pub name: String,
+    pub segments: Vec<Segment>,
+    pub events: usize,
+    /// Time point of the end of the stream
+    pub end: usize,
+}
+
+/// Outcome of one run of a policy over a dataset.
+#[derive(Clone, Debug)]
+pub struct Measurement {
+    pub events: usize,
+    /// Satisfying valuations, duplicates included
+    pub verdicts: usize,
+    /// From the first event until every verdict is final
+    pub duration: Duration,
+    /// Per time point, from its last event until the output probe passed it, i.e. until its
+    /// verdicts are final
+    pub latency: LatencyReport,
+    /// Peak resident memory of the process since the start of the run, if the platform reports it
+    pub peak_rss_kb: Option<u64>,
+}
+
+impl Dataset {
+    /// Reads a trace in the CSV event format. Its watermarks are replaced by ones that follow the
+    /// order of the file.
+    pub fn load(path: &Path, watermark_period: usize) -> io::Result<Dataset> {
+        let events = log_events(read_csv_log(path)?)?;
+        if events.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Empty trace {}", path.display())));
+        }
+        let end = events.iter().map(|e| e.tp).max().unwrap() + 1;
+        let segments = parse_lines_to_segments(with_watermarks(&events, Some(watermark_period))?.into_iter());
+        let name = path.file_stem().map_or_else(|| path.display().to_string(), |s| s.to_string_lossy().to_string());
+        Ok(Dataset { name, segments, events: events.len(), end })
+    }
+}
+
+impl Measurement {
+    pub fn events_per_second(&self) -> f64 {
+        self.events as f64 / duration_secs(self.duration).max(f64::MIN_POSITIVE)
+    }
+}
+
+fn duration_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+/// Runs the policy over the dataset with `workers` workers that step after every `step` events
+/// and at every watermark.
+pub fn measure(policy: &str, dataset: &Dataset, workers: usize, step: usize) -> Measurement {
+    let (send, recv) = mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+    let verdicts = Arc::new(AtomicUsize::new(0));
+    let segments = Arc::new(Mutex::new(Some(dataset.segments.clone())));
+    let end = dataset.end;
+    let policy = policy.to_string();
+    let mut options = default_options();
+    options.set_workers(workers);
+    options.set_step(step);
+    let step = step.max(1);
+    let reset = reset_peak_rss();
+    let counter = verdicts.clone();
+
+    timely::execute(timely::Config::process(workers), move |worker| {
+        let send = send.lock().unwrap().clone();
+        let counter = counter.clone();
+        let (mut input, cap, mut time_input, time_cap, probe) = worker.dataflow::<usize, _, _>(|scope| {
+            let ((time_input, time_cap), time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+            let ((input, input_cap), stream) = scope.new_unordered_input::<String>();
+            let (_attrs, output) = create_dataflow(parse_formula(&policy), stream, time_stream, options.clone());
+            let probe = output
+                .inspect(move |record| if let Data(true, _) = record {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                })
+                .probe();
+            (input, input_cap, time_input, time_cap, probe)
+        });
+
+        if worker.index() == 0 {
+            let (mut cap, mut time_cap) = (cap, time_cap);
+            let mut latency = LatencyTracker::new();
+            let mut threshold = 0;
+            let start = Instant::now();
+            for segment in segments.lock().unwrap().take().unwrap() {
+                match segment {
+                    Segment::Epoch(wm) => {
+                        let t = wm.max(0) as usize;
+                        time_cap.downgrade(&t);
+                        cap.downgrade(&t);
+                        threshold = step;
+                    }
+                    Segment::Seg(tp, ts, events) => {
+                        latency.ingest(tp);
+                        threshold += events.len();
+                        time_input.session(time_cap.delayed(&tp)).give(Timestamp(ts));
+                        input.session(cap.delayed(&tp)).give_iterator(events.into_iter());
+                    }
+                }
+                if threshold >= step {
+                    worker.step();
+                    threshold = 0;
+                    latency.complete(&probe);
+                }
+            }
+            time_input.session(time_cap.delayed(&end)).give(EOS);
+            input.session(cap.delayed(&end)).give("<eos>".to_string());
+            drop((cap, time_cap));
+            while !probe.done() {
+                worker.step();
+                latency.complete(&probe);
+            }
+            latency.complete(&probe);
+            let _ = send.send((start.elapsed(), latency.report()));
+        }
+    })
+    .unwrap();
+
+    let (duration, latency) = recv.recv().expect("The first worker reported no measurement");
+    Measurement {
+        events: dataset.events,
+        verdicts: verdicts.load(Ordering::Relaxed),
+        duration,
+        latency,
+        peak_rss_kb: if reset { peak_rss_kb() } else { None },
+    }
+}
+
+/// Peak resident memory of the process in kB (`VmHWM` of `/proc/self/status`).
+pub fn peak_rss_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status.lines()
+        .find(|line| line.starts_with("VmHWM:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse().ok())
+}
+
+/// Resets the peak resident memory to the current one, which Linux supports since 4.0.
+pub fn reset_peak_rss() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(String::new, |v| v.to_string())
+}
+
+/// A row of the CSV with the columns of `CSV_HEADER`, missing values are left empty.
+pub fn csv_row(policy: &str, dataset: &str, workers: usize, step: usize, run: usize, measurement: &Measurement) -> String {
+    let latency = |p| optional(measurement.latency.percentile(p).map(|l| format!("{:.3}", duration_secs(l) * 1000.0)));
+    format!(
+        "{},{},{},{},{},{},{},{:.6},{:.1},{},{},{},{},{}",
+        csv_field(policy),
+        csv_field(dataset),
+        workers,
+        step,
+        run,
+        measurement.events,
+        measurement.verdicts,
+        duration_secs(measurement.duration),
+        measurement.events_per_second(),
+        latency(50.0),
+        latency(90.0),
+        latency(99.0),
+        latency(100.0),
+        optional(measurement.peak_rss_kb),
+    )
+}
+
+// Quotes a field that contains a delimiter or a quote
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Dataset {
+        Dataset::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data/regression/triangle.csv"), 1).unwrap()
+    }
+
+    #[test]
+    fn measures_every_time_point() {
+        let dataset = triangle();
+        assert_eq!(dataset.name, "triangle");
+        assert_eq!(dataset.events, 60);
+        for &(workers, step) in &[(1, 1), (2, 1000)] {
+            let measurement = measure("(ONCE[0,2] A(a,b)) AND B(b,c)", &dataset, workers, step);
+            assert_eq!(measurement.events, 60);
+            assert!(measurement.verdicts > 0);
+            // the time points 0 to 15
+            assert_eq!(measurement.latency.len(), 16);
+        }
+    }
+
+    #[test]
+    fn percentiles_and_rows() {
+        let measurement = Measurement {
+            events: 100,
+            verdicts: 3,
+            duration: Duration::from_millis(500),
+            latency: LatencyReport::new((1..=10).map(Duration::from_millis).collect()),
+            peak_rss_kb: None,
+        };
+        assert_eq!(measurement.events_per_second(), 200.0);
+        assert_eq!(
+            csv_row("A(x), B(x)", "trace", 2, 10, 0, &measurement),
+            "\"A(x), B(x)\",trace,2,10,0,100,3,0.500000,200.0,5.000,9.000,10.000,10.000,"
+        );
+        assert_eq!(CSV_HEADER.split(',').count(), 14);
+        let empty = Measurement { latency: LatencyReport::new(vec![]), ..measurement };
+        assert!(csv_row("A(x)", "trace", 1, 1, 0, &empty).ends_with(",,,,,"));
+    }
+}
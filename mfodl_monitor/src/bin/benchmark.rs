@@ -0,0 +1,110 @@
+extern crate mfodl_monitor;
+extern crate structopt;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+
+use mfodl_monitor::benchmark::{csv_row, measure, Dataset, CSV_HEADER};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "benchmark",
+    about = "Sweeps policies, datasets, worker counts and step sizes and reports throughput, verdict latency and peak memory in CSV."
+)]
+pub struct BenchmarkArgs {
+    /// Policies, optionally named as NAME=POLICY. Example: "Triangle=(ONCE[0,7] A(a,b)) AND B(b,c)"
+    #[structopt(short, long = "policy", required = true)]
+    policies: Vec<String>,
+
+    /// Datasets in the CSV event format
+    #[structopt(short, long = "dataset", parse(from_os_str), required = true)]
+    datasets: Vec<PathBuf>,
+
+    /// Numbers of workers
+    #[structopt(short, long, default_value = "1")]
+    workers: Vec<usize>,
+
+    /// Step sizes of workers
+    #[structopt(short, long, default_value = "1000")]
+    step: Vec<usize>,
+
+    /// Runs of each configuration
+    #[structopt(short, long, default_value = "5")]
+    runs: usize,
+
+    /// Watermark at the latest every N time points, replacing the watermarks of the datasets
+    #[structopt(long = "watermark-period", default_value = "1")]
+    watermark_period: usize,
+
+    /// Output file, stdout otherwise
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+// Splits NAME=POLICY, a policy without a name is named after itself
+fn named_policy(policy: &str) -> (String, String) {
+    match policy.find('=') {
+        Some(i) if i > 0 && policy[..i].chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') => {
+            (policy[..i].to_string(), policy[i + 1..].trim().to_string())
+        }
+        _ => (policy.to_string(), policy.to_string()),
+    }
+}
+
+fn run<W: Write>(args: &BenchmarkArgs, datasets: &[Dataset], out: &mut W) -> io::Result<()> {
+    writeln!(out, "{}", CSV_HEADER)?;
+    for (name, policy) in args.policies.iter().map(|p| named_policy(p)) {
+        for dataset in datasets {
+            for &workers in args.workers.iter() {
+                for &step in args.step.iter() {
+                    for run in 0..args.runs {
+                        let measurement = measure(&policy, dataset, workers, step);
+                        eprintln!(
+                            "{} on {} with {} workers and step {}, run {}: {:.3}s",
+                            name, dataset.name, workers, step, run, measurement.duration.as_secs() as f64 + f64::from(measurement.duration.subsec_millis()) / 1000.0
+                        );
+                        writeln!(out, "{}", csv_row(&name, &dataset.name, workers, step, run, &measurement))?;
+                        out.flush()?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = BenchmarkArgs::from_args();
+    if args.workers.contains(&0) || args.watermark_period == 0 {
+        println!("Expected at least one worker and a positive --watermark-period");
+        process::exit(2);
+    }
+
+    let mut datasets = Vec::with_capacity(args.datasets.len());
+    for path in args.datasets.iter() {
+        match Dataset::load(path, args.watermark_period) {
+            Ok(dataset) => datasets.push(dataset),
+            Err(e) => {
+                println!("Unable to read {}: {}", path.display(), e);
+                process::exit(2);
+            }
+        }
+    }
+
+    let result = match &args.output {
+        Some(path) => File::create(path).and_then(|f| run(&args, &datasets, &mut BufWriter::new(f))),
+        None => {
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            run(&args, &datasets, &mut out)
+        }
+    };
+
+    if let Err(e) = result {
+        println!("Unable to write the results: {}", e);
+        process::exit(2);
+    }
+}
@@ -0,0 +1,36 @@
+extern crate mfodl_monitor;
+extern crate structopt;
+
+use std::path::PathBuf;
+use std::process;
+
+use mfodl_monitor::parser::binary_log::convert_to_binary_log;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "convert",
+    about = "Converts a CSV or JSON event log into the binary event log read by timelymon -f binary."
+)]
+pub struct ConvertArgs {
+    /// Event log to convert. Example: data/50K50TS.csv
+    input: PathBuf,
+
+    /// Binary event log to write. Example: data/50K50TS.tlog
+    output: PathBuf,
+
+    /// Input file type: csv or json
+    #[structopt(short = "f", long = "filetype", default_value = "csv", possible_values = &["csv", "json"])]
+    file_type: String,
+}
+
+fn main() {
+    let args = ConvertArgs::from_args();
+    match convert_to_binary_log(&args.input, &args.output, args.file_type == "json") {
+        Ok(records) => println!("Converted {} records to {}", records, args.output.display()),
+        Err(e) => {
+            println!("Unable to convert {}: {}", args.input.display(), e);
+            process::exit(2);
+        }
+    }
+}
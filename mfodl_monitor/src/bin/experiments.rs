@@ -13,7 +13,7 @@ use std::time::Instant;
 use timely::dataflow::operators::capture::Extract;
 use timely::dataflow::operators::{Capture, Probe, UnorderedInput};
 
-use mfodl_monitor::dataflow_constructor::types::FlowValues::{Data, MetaData};
+use mfodl_monitor::dataflow_constructor::types::FlowValues::{Data, MetaData, Unknown};
 use mfodl_monitor::dataflow_constructor::types::{default_options, TimeFlowValues};
 use mfodl_monitor::dataflow_constructor::types::TimeFlowValues::Timestamp;
 use mfodl_monitor::parser::csv_parser::{parse_csv_file_to_vec, parse_delay_csv_file_to_vec, parse_verimon_results, };
@@ -194,7 +194,7 @@ fn delay_input(policy: String, path_data: String, path_result: String, workers:
         let mut tmp_vec = Vec::new();
         data.iter().for_each(|rec| match rec {
             Data(_, rec) => tmp_vec.push(rec.clone()),
-            MetaData(_, _) => {}
+            MetaData(_, _) | Unknown(_) => {}
         });
 
         btree_res.insert(tp.clone(), tmp_vec);
@@ -305,7 +305,7 @@ fn out_order_tuple_input(policy: String, path_data: String, path_result: String,
         let mut tmp_vec = Vec::new();
         data.iter().for_each(|rec| match rec {
             Data(_, rec) => tmp_vec.push(rec.clone()),
-            MetaData(_, _) => {}
+            MetaData(_, _) | Unknown(_) => {}
         });
 
         btree_res.insert(*tp, tmp_vec);
@@ -411,7 +411,7 @@ fn out_order_input(policy: String, path_data: String, path_result: String, worke
         let mut tmp_vec = Vec::new();
         data.iter().for_each(|rec| match rec {
             Data(_, rec) => tmp_vec.push(rec.clone()),
-            MetaData(_, _) => {}
+            MetaData(_, _) | Unknown(_) => {}
         });
 
         btree_res.insert(*tp, tmp_vec);
@@ -517,7 +517,7 @@ fn reverse_order_input(policy: String, path_data: String, path_result: String, w
         let mut tmp_vec = Vec::new();
         data.iter().for_each(|rec| match rec {
             Data(_, rec) => tmp_vec.push(rec.clone()),
-            MetaData(_, _) => {}
+            MetaData(_, _) | Unknown(_) => {}
         });
 
         btree_res.insert(*tp, tmp_vec);
@@ -623,7 +623,7 @@ fn in_order_input(policy: String, path_data: String, path_result: String, worker
         let mut tmp_vec = Vec::new();
         data.iter().for_each(|rec| match rec {
             Data(_, rec) => tmp_vec.push(rec.clone()),
-            MetaData(_, _) => {}
+            MetaData(_, _) | Unknown(_) => {}
         });
 
         btree_res.insert(*tp, tmp_vec);
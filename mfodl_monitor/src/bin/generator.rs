@@ -0,0 +1,155 @@
+extern crate mfodl_monitor;
+extern crate structopt;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+
+use mfodl_monitor::parser::signature::Signatures;
+use mfodl_monitor::trace_generator::{parse_distributions, write_trace, EventSource, OutOfOrder, Pattern, TraceConfig};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "generator",
+    about = "Generates random CSV or JSON event logs for a join pattern or a signature."
+)]
+pub struct GeneratorArgs {
+    /// Number of time points
+    length: usize,
+
+    /// Star pattern A(w,x) B(w,y) C(w,z)
+    #[structopt(short = "S", long = "star")]
+    star: bool,
+
+    /// Linear pattern A(w,x) B(x,y) C(y,z)
+    #[structopt(short = "L", long = "linear")]
+    linear: bool,
+
+    /// Triangle pattern A(x,y) B(y,z) C(z,x)
+    #[structopt(short = "T", long = "triangle")]
+    triangle: bool,
+
+    /// Signature file, random events of the declared predicates instead of a pattern
+    #[structopt(long = "sig", parse(from_os_str))]
+    signature: Option<PathBuf>,
+
+    /// Events per time point
+    #[structopt(short = "e", long = "event-rate", default_value = "10")]
+    event_rate: usize,
+
+    /// Timestamp difference of consecutive time points
+    #[structopt(short = "t", long = "ts-spacing", default_value = "1")]
+    ts_spacing: usize,
+
+    /// Values are drawn from 0 to domain - 1
+    #[structopt(long = "domain", default_value = "1000000")]
+    domain: i32,
+
+    /// Zipf distributed variables with exponent and offset, e.g. x=1.5+3,y=2. Others are uniform
+    #[structopt(short = "z", long = "zipf", default_value = "")]
+    zipf: String,
+
+    /// Relative frequency of A events
+    #[structopt(long = "pA", default_value = "0.3333")]
+    ratio_a: f64,
+
+    /// Relative frequency of B events, C events take the rest
+    #[structopt(long = "pB", default_value = "0.3333")]
+    ratio_b: f64,
+
+    /// Probability that a B or C event joins with an earlier event of the pattern
+    #[structopt(short = "x", long = "match-ratio", default_value = "0.5")]
+    match_ratio: f64,
+
+    /// Largest timestamp distance of joining events
+    #[structopt(short = "w", long = "window", default_value = "10")]
+    window: usize,
+
+    /// Largest delay of an event, enables out-of-order emission with watermarks
+    #[structopt(long = "max-delay", alias = "md")]
+    max_delay: Option<usize>,
+
+    /// Standard deviation of the delay
+    #[structopt(short = "s", long = "std-dev", default_value = "1.0")]
+    std_dev: f64,
+
+    /// Emission time between two watermarks
+    #[structopt(long = "watermark-period", alias = "wp", default_value = "1")]
+    watermark_period: usize,
+
+    /// Output format: csv or json
+    #[structopt(short = "f", long = "format", default_value = "csv", possible_values = &["csv", "json"])]
+    format: String,
+
+    /// Seed of the random generator
+    #[structopt(long = "seed", default_value = "0")]
+    seed: usize,
+
+    /// Output file, stdout otherwise
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    let args = GeneratorArgs::from_args();
+
+    let source = match (&args.signature, args.star, args.linear, args.triangle) {
+        (Some(path), false, false, false) => match Signatures::from_file(path) {
+            Ok(signatures) => EventSource::Signature(signatures),
+            Err(e) => {
+                println!("Invalid signature {}: {}", path.display(), e);
+                process::exit(2);
+            }
+        },
+        (None, true, false, false) => EventSource::Pattern(Pattern::Star),
+        (None, false, true, false) => EventSource::Pattern(Pattern::Linear),
+        (None, false, false, true) => EventSource::Pattern(Pattern::Triangle),
+        _ => {
+            println!("Expected exactly one of -S, -L, -T and --sig");
+            process::exit(2);
+        }
+    };
+
+    let mut config = TraceConfig::new(source, args.length);
+    config.event_rate = args.event_rate;
+    config.ts_spacing = args.ts_spacing;
+    config.domain = args.domain;
+    config.ratio_a = args.ratio_a;
+    config.ratio_b = args.ratio_b;
+    config.match_ratio = args.match_ratio;
+    config.window = args.window;
+    config.seed = args.seed;
+    config.distributions = match parse_distributions(&args.zipf) {
+        Ok(distributions) => distributions,
+        Err(e) => {
+            println!("Invalid distribution: {}", e);
+            process::exit(2);
+        }
+    };
+    config.out_of_order = args.max_delay.map(|max_delay| OutOfOrder {
+        max_delay,
+        std_dev: args.std_dev,
+        watermark_period: args.watermark_period,
+    });
+
+    let json = args.format == "json";
+    let result = match &args.output {
+        Some(path) => File::create(path).and_then(|f| {
+            let mut out = BufWriter::new(f);
+            write_trace(&config, json, &mut out)?;
+            out.flush()
+        }),
+        None => {
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            write_trace(&config, json, &mut out).and_then(|_| out.flush())
+        }
+    };
+
+    if let Err(e) = result {
+        println!("Unable to write the trace: {}", e);
+        process::exit(2);
+    }
+}
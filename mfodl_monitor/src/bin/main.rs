@@ -12,25 +12,57 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, BufReader, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::{println, writeln};
 
-use mfodl_monitor::dataflow_constructor::types::FlowValues::Data;
-use mfodl_monitor::parser::formula_syntax_tree::Constant;
-use mfodl_monitor::{create_dataflow, parse_formula};
+use mfodl_monitor::dataflow_constructor::operators::format_record;
+use mfodl_monitor::dataflow_constructor::types::FlowValues::{Data, Unknown};
+use mfodl_monitor::parser::formula_parser::parse_formula_with_resolution;
+use mfodl_monitor::parser::formula_syntax_tree::{formula_error, Constant, Formula};
+use mfodl_monitor::{create_dataflow, unknown_verdicts_error};
 use std::path::PathBuf;
 
 use mfodl_monitor::dataflow_constructor::types::TimeFlowValues::Timestamp;
-use mfodl_monitor::dataflow_constructor::types::{OperatorOptions, TimeFlowValues};
+use mfodl_monitor::dataflow_constructor::types::{InputEvent, OperatorOptions, TimeFlowValues, VerdictLimit};
 use structopt::StructOpt;
+use timely::communication::Allocate;
 use timely::dataflow::operators::capture::Capture;
 use timely::dataflow::operators::capture::Extract;
 use timely::dataflow::operators::{Probe, UnorderedInput};
+use timely::dataflow::ProbeHandle;
+use timely::worker::Worker;
 
 use mfodl_monitor::parser::csv_parser::{
-    parse_file_to_segments, parser_extended_wrapper, ParserReturn, Segment,
+    parse_lines_to_segments, parser_extended_wrapper, ParserReturn, Segment,
 };
 
+use mfodl_monitor::parser::binary_log::binary_log_to_segments;
 use mfodl_monitor::parser::json_parser::find_timestamp;
+use mfodl_monitor::parser::monpoly::{parse_monpoly_formula, to_native_policy, MonpolySegments};
+use mfodl_monitor::parser::validation::{
+    validate_lines, ErrorPolicy, InputValidator, ValidatedLines,
+};
+use mfodl_monitor::parser::signature::{Signatures, TypedLines};
+use mfodl_monitor::repl;
+use mfodl_monitor::parser::table_csv::{
+    table_to_segments, ColumnMapping, PredicateSource, TableFormat,
+};
+use mfodl_monitor::stream_io::backpressure::Backpressure;
+use mfodl_monitor::stream_io::follow_source::FollowLines;
+use mfodl_monitor::stream_io::latency::{LatencyReport, LatencyTracker};
+use mfodl_monitor::stream_io::polled_source::{Poll, PolledSource};
+use mfodl_monitor::timeunits::TimeUnit;
+use mfodl_monitor::stream_io::socket_sink::{SinkAddress, SocketSink};
+use mfodl_monitor::stream_io::tcp_source::TcpLines;
+use std::time::Duration;
+
+const SINK_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+// how long worker 0 waits for the next input item before it steps again and checks the verdict limit
+const IDLE: Duration = Duration::from_millis(1);
+// input items read ahead of worker 0
+const INPUT_BUFFER: usize = 1024;
 
 // const MODE_VALS: &[&str] = &["order", "out_of_order"];
 
@@ -54,7 +86,9 @@ pub struct ProgArgs {
     #[structopt(short, long)]
     output_file: Option<String>,
 
-    /// write to file (0), print to stdin (1) or disregard input (2)
+    /// write to file (0), print to stdout (1), print batched to stdout (2), print the
+    /// three-valued verdict (true/false/unknown) of each time point in order (3) or print to stdout
+    /// in (time point, tuple) order as soon as a time point is complete (4)
     #[structopt(short, long, default_value = "0")]
     mode_out_put: usize,
 
@@ -70,13 +104,134 @@ pub struct ProgArgs {
     #[structopt(short, long, default_value = "1")]
     batch_output: usize,
 
-    /// File type
+    /// File type: json for one JSON event per line, binary for a data file in the binary event
+    /// log format (see the convert binary), table for a data file in CSV with a header row, monpoly
+    /// for events in the MonPoly log format (@ts p(a,b)(c,-1)) from a file, stdin or a socket, CSV
+    /// events otherwise
     #[structopt(short = "f", long = "filetype")]
     file_type: Option<String>,
+
+    /// Column of a table holding the timestamp
+    #[structopt(long = "ts-column", default_value = "ts")]
+    ts_column: String,
+
+    /// Column of a table holding the predicate name
+    #[structopt(long = "pred-column", conflicts_with = "predicate")]
+    pred_column: Option<String>,
+
+    /// Predicate of all rows of a table
+    #[structopt(long = "predicate")]
+    predicate: Option<String>,
+
+    /// Columns of a table mapped to the predicate arguments, e.g. user:string,amount:int.
+    /// Defaults to all other columns
+    #[structopt(long = "columns", use_delimiter = true)]
+    columns: Option<Vec<ColumnMapping>>,
+
+    /// Column delimiter of a table
+    #[structopt(long = "delimiter", default_value = ",")]
+    delimiter: char,
+
+    /// Unit of the timestamps of the log (ms, s, m, h or d). Interval bounds with a unit, e.g.
+    /// ONCE[0, 5m], are converted to it, bounds without a unit are timestamps
+    #[structopt(long = "ts-unit", default_value = "s")]
+    ts_unit: TimeUnit,
+
+    /// Syntax of the policy: native, or monpoly for MonPoly formulas (operator precedence of MonPoly,
+    /// optional intervals with time units, string constants in double quotes)
+    #[structopt(long = "syntax", default_value = "native", possible_values = &["native", "monpoly"])]
+    syntax: String,
+
+    /// Signature file declaring the predicates and argument types, e.g. A(int,int) B(string).
+    /// The policy is type-checked against it and events are parsed with the declared types. Events
+    /// of undeclared predicates or with ill-typed arguments are dropped and reported on stderr, a
+    /// MonPoly log stops at such an event with exit code 2. Not supported for JSON events
+    #[structopt(long = "signature", parse(from_os_str))]
+    signature: Option<PathBuf>,
+
+    /// Check the CSV events against the policy and handle invalid lines: drop them (skip), drop
+    /// and report them on stderr (warn), or report the first one and stop reading with exit code 2 (fail).
+    /// Not supported for other formats
+    #[structopt(long = "on-error", possible_values = &["skip", "warn", "fail"])]
+    on_error: Option<ErrorPolicy>,
+
+    /// Only check the CSV events against the policy and report every invalid line: malformed events,
+    /// arities that differ from the policy, time points below a watermark and decreasing timestamps.
+    /// Exits with 1 if a line is invalid and 0 otherwise. Not supported for other formats
+    #[structopt(long = "validate")]
+    validate: bool,
+
+    /// Listen for TCP connections streaming events on the given address, e.g. 127.0.0.1:4000.
+    /// The input ends once all connections are closed or a connection sends >SHUTDOWN<
+    #[structopt(short, long, conflicts_with = "file")]
+    listen: Option<String>,
+
+    /// Keep reading the data file as it grows, following truncation and rotation. The input ends
+    /// at a >SHUTDOWN< line or once no line arrived within the idle timeout
+    #[structopt(long = "follow", requires = "file")]
+    follow: bool,
+
+    /// Seconds without a new line after which the followed file is considered complete
+    #[structopt(long = "idle-timeout", requires = "follow")]
+    idle_timeout: Option<u64>,
+
+    /// Verdicts of unbounded future operators that are still pending at the end of the stream:
+    /// violated under finite trace semantics (finite) or reported as unknown (unknown). Unknown
+    /// requires an unbounded EVENTUALLY or UNTIL at the top of the policy and nowhere else
+    #[structopt(long = "eos-verdicts", default_value = "finite", possible_values = &["finite", "unknown"])]
+    eos_verdicts: String,
+
+    /// Stop at the earliest violation and report it. Exits with 1 if the policy is violated and 0 otherwise
+    #[structopt(long = "first-violation", conflicts_with = "max-verdicts")]
+    first_violation: bool,
+
+    /// Stop after N violations and report them. Exits with 1 if the policy is violated and 0 otherwise
+    #[structopt(long = "max-verdicts")]
+    max_verdicts: Option<usize>,
+
+    /// Push the verdicts to a socket instead of stdout or the output file, given as
+    /// tcp:<host>:<port> or unix:<path>. Reconnects if the consumer restarts. Needs output mode 1 to 4
+    #[structopt(long = "sink")]
+    sink: Option<SinkAddress>,
+
+    /// Number of verdict lines buffered while the sink is slow or unavailable, older lines are dropped
+    #[structopt(long = "sink-buffer", default_value = "10000")]
+    sink_buffer: usize,
+
+    /// Report on stderr how long after the last event of a time point its verdicts were final, as
+    /// percentiles and a histogram over all time points. The report is printed once, after the input ended
+    #[structopt(long = "latency-report")]
+    latency_report: bool,
+
+    /// Stop reading the input while N ingested time points are incomplete, i.e. their verdicts are
+    /// not final, as long as the monitor completes time points without more input
+    #[structopt(long = "max-incomplete", default_value = "1000")]
+    max_incomplete: usize,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "timelymon repl",
+    about = "Monitors policies interactively: events are typed in the CSV or JSON syntax and the verdicts of a time point are printed once it is complete."
+)]
+pub struct ReplArgs {
+    ///Number of workers
+    #[structopt(short, long, default_value = "1")]
+    workers: usize,
 }
 
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("repl") {
+        let args = ReplArgs::from_iter(std::env::args().skip(1));
+        if let Err(e) = repl::run(args.workers) {
+            println!("{}", e);
+            process::exit(2);
+        }
+        return;
+    }
+
     let args = ProgArgs::from_args();
+    let table = table_format(&args);
     let mut options = OperatorOptions::new();
 
     let policy = args.policy;
@@ -87,16 +242,32 @@ fn main() {
     options.set_step(args.step);
     options.set_output_batch(args.batch_output);
     options.set_deduplication(args.deduplication);
+    options.set_unknown_at_eos(args.eos_verdicts == "unknown");
 
     let out_put_mode = match args.mode_out_put {
-        0 | 1 | 2 => args.mode_out_put,
+        0 | 1 | 2 | 3 | 4 => args.mode_out_put,
         _ => {
             println!("Passed out put mode is invalid!");
-            3
+            5
         }
     };
     options.set_output_mode(out_put_mode);
 
+    // mode 0 only writes the verdicts once the input ended, which a consumer cannot wait for
+    if args.sink.is_some() && out_put_mode == 0 {
+        println!("The verdicts of output mode 0 are written at the end, use --sink with output mode 1, 2, 3 or 4");
+        process::exit(2);
+    }
+    let sink_buffer = args.sink_buffer;
+    options.set_output_sink(args.sink.map(|addr| SocketSink::new(addr, sink_buffer)));
+
+    let verdict_limit = if args.first_violation {
+        Some(VerdictLimit::new(1))
+    } else {
+        args.max_verdicts.map(VerdictLimit::new)
+    };
+    options.set_verdict_limit(verdict_limit.clone());
+
     // Check if policy is a file
     let new_policy = if let Ok(f) = File::open(policy.clone()) {
         let mut res = "".to_string();
@@ -105,97 +276,324 @@ fn main() {
         }
         res.to_string()
     } else {
-        policy
+        policy.clone()
     };
 
-    let mut tp_to_ts: HashMap<usize, usize> = HashMap::with_capacity(8);
+    // MonPoly formulas are translated to the policy syntax, keeping line breaks for comments
+    let new_policy = if args.syntax == "monpoly" {
+        let text = std::fs::read_to_string(&policy).unwrap_or(policy);
+        match parse_monpoly_formula(&text, args.ts_unit) {
+            Ok(f) => to_native_policy(&f),
+            Err(e) => {
+                println!("Unable to parse the MonPoly formula: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        new_policy
+    };
+
+    // bounds with a unit are converted to timestamps once, the workers get the parsed formula
+    let formula = parse_formula_with_resolution(&new_policy, args.ts_unit);
+    if let Some(e) = formula_error(&formula) {
+        println!("Invalid policy: {}", e);
+        process::exit(2);
+    }
+    if args.eos_verdicts == "unknown" {
+        if let Some(e) = unknown_verdicts_error(&formula) {
+            println!("Invalid policy: {}", e);
+            process::exit(2);
+        }
+    }
+
+    if (args.validate || args.on_error.is_some()) && args.file_type.is_some() {
+        println!("Only events in the CSV format can be validated");
+        process::exit(2);
+    }
 
     // println!("{} {:?}", policy.clone(), path_data.clone());
-    let res = if let Some(path_data) = some_path_data {
-        let (r, tpts) = execute_from_file(new_policy.clone(), path_data.clone(), options.clone());
-        tp_to_ts = tpts;
-        r
-    } else {
-        let (r, tpts) = execute_from_stdin(new_policy.clone(), options.clone(), args.file_type);
-        tp_to_ts = tpts;
-        r
+    let idle_timeout = args.idle_timeout.map(Duration::from_secs);
+    let lines = input_lines(some_path_data.clone(), args.follow, idle_timeout, args.listen);
+
+    if args.signature.is_some() && args.file_type.as_deref() == Some("json") {
+        println!("A signature cannot be applied to JSON events");
+        process::exit(2);
+    }
+    let signatures = args.signature.as_ref().map(|path| {
+        let signatures = Signatures::from_file(path).unwrap_or_else(|e| {
+            println!("Unable to read {}: {}", path.display(), e);
+            process::exit(2);
+        });
+        if let Err(e) = signatures.check_policy(&formula) {
+            println!("Policy does not match the signature: {}", e);
+            process::exit(2);
+        }
+        signatures
+    });
+
+    // events in the CSV format are typed here, the other formats while they are read
+    let lines: Box<dyn Iterator<Item = String> + Send> = match &signatures {
+        Some(signatures) if args.file_type.is_none() => Box::new(TypedLines::new(lines, signatures.clone())),
+        _ => lines,
+    };
+
+    if args.validate {
+        let errors = validate_lines(lines, &formula);
+        for error in errors.iter() {
+            println!("{}", error);
+        }
+        println!("{} invalid lines", errors.len());
+        process::exit(if errors.is_empty() { 0 } else { 1 });
+    }
+
+    let input_failed = Arc::new(AtomicBool::new(false));
+    let lines: Box<dyn Iterator<Item = String> + Send> = match args.on_error {
+        Some(error_policy) => Box::new(ValidatedLines::new(
+            lines,
+            InputValidator::new(&formula),
+            error_policy,
+            input_failed.clone(),
+        )),
+        None => lines,
+    };
+
+    let (res, mut tp_to_ts, latency) = match some_path_data {
+        // MonPoly logs are read into segments as their lines arrive, from a file or a stream
+        _ if args.file_type.as_deref() == Some("monpoly") => {
+            let log_failed = input_failed.clone();
+            let segments = MonpolySegments::new(lines.map(Ok), signatures.clone()).map_while(move |segment| {
+                segment.map_err(|e| {
+                    println!("Invalid MonPoly log: {}", e);
+                    log_failed.store(true, Ordering::SeqCst);
+                }).ok()
+            });
+            execute_from_file(formula.clone(), segments, options.clone(), args.latency_report, args.max_incomplete)
+        }
+        Some(path_data) if !args.follow => {
+            let read_error = |e: io::Error| -> ! {
+                println!("Unable to read {}: {}", path_data.display(), e);
+                process::exit(2);
+            };
+            // binary logs and tables are read as typed events, the other formats as text
+            match args.file_type.as_deref() {
+                Some(file_type @ "binary") | Some(file_type @ "table") => {
+                    let segments = match file_type {
+                        "binary" => binary_log_to_segments(&path_data, signatures.as_ref()),
+                        _ => table.and_then(|format| table_to_segments(&path_data, &format, signatures.as_ref())),
+                    };
+                    let segments = segments.unwrap_or_else(|e| read_error(e));
+                    execute_from_file(formula.clone(), segments, options.clone(), args.latency_report, args.max_incomplete)
+                }
+                _ => execute_from_file(formula.clone(), parse_lines_to_segments(lines), options.clone(), args.latency_report, args.max_incomplete),
+            }
+        }
+        _ => execute_from_lines(
+            formula.clone(),
+            options.clone(),
+            args.file_type,
+            lines,
+            args.latency_report,
+            args.max_incomplete,
+        ),
     };
 
     // support multiple output formats
     if !res.is_empty() {
-        if let Some(file_name) = options.get_output_file() {
+        let writer: Option<Box<dyn Write>> = if let Some(file_name) = options.get_output_file() {
             match File::create(file_name) {
-                Ok(mut file) => {
-                    //println!("{:?}", res);
-                    res.iter().for_each(|(tp, flow)| {
-                        //println!("@{tp} {:?}\n\n", flow);
-                        let mut out = format!("");
-                        flow.iter().for_each(|d| {
-                            let ver = d.clone();
-                            match ver {
-                                Data(true, vals) => {
-                                    if !vals.is_empty() {
-                                        if vals.len() == 1 {
-                                            match vals[0] {
-                                                Constant::Int(x) => {
-                                                    out += &format!(" ({:?}) ", x);
-                                                }
-                                                _ => {}
-                                            }
-                                        } else if vals.len() == 2 {
-                                            match vals[0] {
-                                                Constant::Int(x) => match vals[1] {
-                                                    Constant::Int(y) => {
-                                                        out += &format!(" ({:?},{:?}) ", x, y);
-                                                    }
-                                                    _ => {}
-                                                },
-                                                _ => {}
-                                            }
-                                        } else {
-                                            out += "(";
-                                            for v in 0..vals.len() - 1 {
-                                                match vals[v] {
-                                                    Constant::Int(x) => out += &format!("{:?},", x),
-                                                    _ => {}
-                                                }
-                                            }
-                                            match vals[vals.len() - 1] {
-                                                Constant::Int(x) => {
-                                                    out += &format!("{:?})", x);
-                                                }
-                                                Constant::Str(_) => {}
-                                                Constant::JSONValue(_) => {}
+                Ok(file) => Some(Box::new(file)),
+                Err(e) => {
+                    println!("Error {:?}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        if let Some(mut file) = writer {
+            //println!("{:?}", res);
+            res.iter().for_each(|(tp, flow)| {
+                //println!("@{tp} {:?}\n\n", flow);
+                let mut out = format!("");
+                let mut unknown = format!("");
+                flow.iter().for_each(|d| {
+                    let ver = d.clone();
+                    match ver {
+                        Data(true, vals) => {
+                            if !vals.is_empty() {
+                                if vals.len() == 1 {
+                                    match vals[0] {
+                                        Constant::Int(x) => {
+                                            out += &format!(" ({:?}) ", x);
+                                        }
+                                        _ => {}
+                                    }
+                                } else if vals.len() == 2 {
+                                    match vals[0] {
+                                        Constant::Int(x) => match vals[1] {
+                                            Constant::Int(y) => {
+                                                out += &format!(" ({:?},{:?}) ", x, y);
                                             }
+                                            _ => {}
+                                        },
+                                        _ => {}
+                                    }
+                                } else {
+                                    out += "(";
+                                    for v in 0..vals.len() - 1 {
+                                        match vals[v] {
+                                            Constant::Int(x) => out += &format!("{:?},", x),
+                                            _ => {}
                                         }
                                     }
+                                    match vals[vals.len() - 1] {
+                                        Constant::Int(x) => {
+                                            out += &format!("{:?})", x);
+                                        }
+                                        Constant::Str(_) => {}
+                                        Constant::JSONValue(_) => {}
+                                    }
                                 }
-                                _ => (),
                             }
-                        });
-                        let ts = *tp_to_ts.entry(*tp).or_default();
-                        match writeln!(file, "@{ts} (time point {tp}): {}", out) {
-                            Err(err) => println!("Error writing: {}", err),
-                            _ => (),
-                        };
-                    });
+                        }
+                        Unknown(vals) => {
+                            unknown += &format!(" {}", format_record(vals));
+                        }
+                        _ => (),
+                    }
+                });
+                let ts = *tp_to_ts.entry(*tp).or_default();
+                match writeln!(file, "@{ts} (time point {tp}): {}", out) {
+                    Err(err) => println!("Error writing: {}", err),
+                    _ => (),
+                };
+                if !unknown.is_empty() {
+                    match writeln!(file, "@{ts} (time point {tp}): unknown{}", unknown) {
+                        Err(err) => println!("Error writing: {}", err),
+                        _ => (),
+                    };
                 }
-                Err(e) => println!("Error {:?}", e),
-            }
+            });
+        }
+    }
+
+    if let Some(mut sink) = options.get_output_sink() {
+        if let Err(e) = sink.close(SINK_CLOSE_TIMEOUT) {
+            println!("{}", e);
         }
     }
+
+    if let Some(report) = latency {
+        eprintln!("{}", report);
+    }
+
+    if input_failed.load(Ordering::SeqCst) {
+        process::exit(2);
+    }
+
+    // the report goes to stderr so that it does not mix with the verdicts on stdout
+    if let Some(limit) = verdict_limit {
+        let violations = limit.get_violations();
+        if violations.is_empty() {
+            eprintln!("No violation");
+        }
+        for (tp, ts, tuple) in violations.iter() {
+            eprintln!(
+                "Violation @{ts} (time point {tp}): {}",
+                format_record(tuple.clone())
+            );
+        }
+        process::exit(if violations.is_empty() { 0 } else { 1 });
+    }
 }
 
-fn execute_from_stdin(
-    policy: String,
+// Lines of the data file (followed as it grows if follow is set), of the TCP connections accepted
+// on the listen address, or of stdin
+fn input_lines(
+    path_data: Option<PathBuf>,
+    follow: bool,
+    idle_timeout: Option<Duration>,
+    listen: Option<String>,
+) -> Box<dyn Iterator<Item = String> + Send> {
+    match (path_data, listen) {
+        (Some(path_data), _) if follow => match FollowLines::open(path_data.clone(), idle_timeout) {
+            Ok(lines) => Box::new(lines),
+            Err(e) => {
+                println!("Unable to follow {}: {}", path_data.display(), e);
+                process::exit(2);
+            }
+        },
+        (Some(path_data), _) => match File::open(&path_data) {
+            Ok(f) => Box::new(
+                BufReader::new(f)
+                    .lines()
+                    .map(|line| line.expect("Error reading line from data file")),
+            ),
+            Err(e) => {
+                println!("Unable to read {}: {}", path_data.display(), e);
+                process::exit(2);
+            }
+        },
+        (None, Some(addr)) => match TcpLines::bind(&addr) {
+            Ok(lines) => Box::new(lines),
+            Err(e) => {
+                println!("Unable to listen on {}: {}", addr, e);
+                process::exit(2);
+            }
+        },
+        (None, None) => Box::new(
+            BufReader::new(io::stdin())
+                .lines()
+                .map(|line| line.expect("Error reading line from stdin")),
+        ),
+    }
+}
+
+// Steps worker 0 and completes the time points whose verdicts became final with that step
+fn step<A: Allocate>(worker: &mut Worker<A>, probe: &ProbeHandle<usize>, latency: &mut Option<LatencyTracker>) {
+    worker.step();
+    complete(probe, latency);
+}
+
+fn complete(probe: &ProbeHandle<usize>, latency: &mut Option<LatencyTracker>) {
+    if let Some(latency) = latency.as_mut() {
+        latency.complete(probe);
+    }
+}
+
+fn table_format(args: &ProgArgs) -> io::Result<TableFormat> {
+    let predicate = match (&args.pred_column, &args.predicate) {
+        (Some(column), _) => PredicateSource::Column(column.clone()),
+        (None, Some(name)) => PredicateSource::Constant(name.clone()),
+        (None, None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a table needs --pred-column or --predicate",
+            ))
+        }
+    };
+    Ok(TableFormat {
+        timestamp: args.ts_column.clone(),
+        predicate,
+        columns: args.columns.clone(),
+        delimiter: args.delimiter,
+    })
+}
+
+fn execute_from_lines(
+    policy: Formula,
     options: OperatorOptions,
     file_type: Option<String>,
+    lines: Box<dyn Iterator<Item = String> + Send>,
+    latency_report: bool,
+    max_incomplete: usize,
 ) -> (
     Vec<(
         usize,
         Vec<mfodl_monitor::dataflow_constructor::types::FlowValues>,
     )>,
     HashMap<usize, usize>,
+    Option<LatencyReport>,
 ) {
     let (send, recv) = std::sync::mpsc::channel();
     let send = std::sync::Arc::new(std::sync::Mutex::new(send));
@@ -203,6 +601,11 @@ fn execute_from_stdin(
     let (tp_send, tp_recv) = std::sync::mpsc::channel();
     let tp_send = std::sync::Arc::new(std::sync::Mutex::new(tp_send));
 
+    let (latency_send, latency_recv) = std::sync::mpsc::channel();
+    let latency_send = std::sync::Arc::new(std::sync::Mutex::new(latency_send));
+
+    let lines = std::sync::Arc::new(std::sync::Mutex::new(Some(lines)));
+
     let options_ = options.clone();
 
     timely::execute(
@@ -212,14 +615,15 @@ fn execute_from_stdin(
             let mut tp_to_ts: HashMap<usize, usize> = HashMap::with_capacity(8);
             let send = send.lock().unwrap().clone();
             let tp_send = tp_send.lock().unwrap().clone();
-            let (mut input, mut cap, mut time_input, mut time_cap, _probe) = worker
+            let latency_send = latency_send.lock().unwrap().clone();
+            let (mut input, mut cap, mut time_input, mut time_cap, probe) = worker
                 .dataflow::<usize, _, _>(|scope| {
                     let ((time_input, time_cap), time_stream) =
                         scope.new_unordered_input::<TimeFlowValues>();
                     let ((input, input_cap), stream) = scope.new_unordered_input::<String>();
 
                     let (_attrs, output) = create_dataflow(
-                        parse_formula(&policy),
+                        policy.clone(),
                         stream,
                         time_stream,
                         options.clone(),
@@ -234,20 +638,55 @@ fn execute_from_stdin(
             let file_type = file_type.clone();
 
             if worker.index() == 0 {
+                let lines = lines.lock().unwrap().take().expect("Input lines already taken");
+                let mut lines = PolledSource::spawn(lines, INPUT_BUFFER);
+                let verdict_limit = options.get_verdict_limit();
                 let mut current = 0;
                 let mut current_is_set = false;
+                let mut max_tp = 0;
+                let mut max_wm = 0;
 
                 let mut current_segment = Vec::with_capacity(options.get_step());
+                let mut latency = if latency_report { Some(LatencyTracker::new()) } else { None };
+                let mut backpressure = Backpressure::new(max_incomplete);
 
                 match file_type {
                     Some(ft) => {
                         if ft == "json" {
-                            for line in io::stdin().lines() {
-                                let line = line.expect("Error reading line from stdin");
+                            loop {
+                                if verdict_limit.as_ref().is_some_and(VerdictLimit::reached) {
+                                    break;
+                                }
+                                backpressure.wait(worker, &probe, || complete(&probe, &mut latency));
+                                let line = match lines.poll(IDLE) {
+                                    Poll::Item(line) => line,
+                                    Poll::Pending => {
+                                        step(worker, &probe, &mut latency);
+                                        continue;
+                                    }
+                                    Poll::Ended => break,
+                                };
+                                if line.starts_with('>') {
+                                    if let ParserReturn::Watermark(wm) =
+                                        parser_extended_wrapper(line.clone())
+                                    {
+                                        let t = if wm < 0 { 0 } else { wm as usize };
+                                        cap.downgrade(&t);
+                                        time_cap.downgrade(&t);
+                                        step(worker, &probe, &mut latency);
+                                        max_wm = t;
+                                        continue;
+                                    }
+                                }
                                 match serde_json::from_str::<serde_json::Value>(&line) {
                                     Ok(json_value) => {
                                         if let Some(timestamp) = find_timestamp(&json_value) {
                                             let ts = timestamp as usize;
+                                            if let Some(latency) = latency.as_mut() {
+                                                latency.ingest(ts);
+                                            }
+                                            backpressure.ingest(ts);
+                                            max_tp = max(max_tp, ts);
                                             time_input
                                                 .session(time_cap.delayed(&ts))
                                                 .give(Timestamp(ts));
@@ -258,12 +697,12 @@ fn execute_from_stdin(
                                             let val = json_value.to_string();
                                             if options.get_step() == 1 {
                                                 input.session(cap.delayed(&ts)).give(val);
-                                                worker.step();
+                                                step(worker, &probe, &mut latency);
                                             } else {
                                                 threshold = threshold + 1;
                                                 input.session(cap.delayed(&ts)).give(val);
                                                 if threshold >= options.get_step() {
-                                                    worker.step();
+                                                    step(worker, &probe, &mut latency);
                                                     threshold = 0;
                                                 }
                                             }
@@ -276,17 +715,34 @@ fn execute_from_stdin(
                     }
                     // Default to CSV for now
                     None => {
-                        for line in io::stdin().lines() {
-                            match parser_extended_wrapper(line.unwrap()) {
+                        loop {
+                            if verdict_limit.as_ref().is_some_and(VerdictLimit::reached) {
+                                break;
+                            }
+                            backpressure.wait(worker, &probe, || complete(&probe, &mut latency));
+                            let line = match lines.poll(IDLE) {
+                                Poll::Item(line) => line,
+                                Poll::Pending => {
+                                    step(worker, &probe, &mut latency);
+                                    continue;
+                                }
+                                Poll::Ended => break,
+                            };
+                            match parser_extended_wrapper(line) {
                                 ParserReturn::Data(tp, ts, val) => {
+                                    if let Some(latency) = latency.as_mut() {
+                                        latency.ingest(tp);
+                                    }
+                                    backpressure.ingest(tp);
                                     tp_to_ts.entry(tp).or_insert(ts);
+                                    max_tp = max(max_tp, tp);
                                     if current_is_set {
                                         if tp == current {
                                             if options.get_step() == 1 {
                                                 input
                                                     .session(cap.delayed(&tp))
                                                     .give(val.to_string());
-                                                worker.step();
+                                                step(worker, &probe, &mut latency);
                                             } else {
                                                 // current_segment.push(val.to_string())
                                                 threshold = threshold + 1;
@@ -294,7 +750,7 @@ fn execute_from_stdin(
                                                     .session(cap.delayed(&ts))
                                                     .give(val.to_string());
                                                 if threshold >= options.get_step() {
-                                                    worker.step();
+                                                    step(worker, &probe, &mut latency);
                                                     threshold = 0;
                                                 }
                                             }
@@ -314,14 +770,14 @@ fn execute_from_stdin(
                                                     .session(cap.delayed(&ts))
                                                     .give(val.to_string());
                                                 if threshold >= options.get_step() {
-                                                    worker.step();
+                                                    step(worker, &probe, &mut latency);
                                                     threshold = 0;
                                                 }
                                             }
                                             // current_segment.clear();
                                             current = tp;
                                             // current_segment.push(val.to_string());
-                                            worker.step();
+                                            step(worker, &probe, &mut latency);
                                         }
                                     } else {
                                         current = tp;
@@ -332,12 +788,12 @@ fn execute_from_stdin(
 
                                         if options.get_step() == 1 {
                                             input.session(cap.delayed(&tp)).give(val.to_string());
-                                            worker.step();
+                                            step(worker, &probe, &mut latency);
                                         } else {
                                             threshold = threshold + 1;
                                             input.session(cap.delayed(&ts)).give(val.to_string());
                                             if threshold >= options.get_step() {
-                                                worker.step();
+                                                step(worker, &probe, &mut latency);
                                                 threshold = 0;
                                             }
                                         }
@@ -345,13 +801,16 @@ fn execute_from_stdin(
                                 }
                                 ParserReturn::Watermark(wm) => {
                                     threshold = threshold + current_segment.len();
-                                    input
-                                        .session(cap.delayed(&current))
-                                        .give_iterator(current_segment.clone().into_iter());
+                                    if !current_segment.is_empty() {
+                                        input
+                                            .session(cap.delayed(&current))
+                                            .give_iterator(current_segment.clone().into_iter());
+                                    }
                                     let t = if wm < 0 { 0 } else { wm as usize };
                                     cap.downgrade(&t);
                                     time_cap.downgrade(&t);
-                                    worker.step();
+                                    step(worker, &probe, &mut latency);
+                                    max_wm = t;
                                     current_segment.clear();
                                 }
                                 ParserReturn::Error(s) => {
@@ -360,7 +819,7 @@ fn execute_from_stdin(
                             }
 
                             if threshold >= options.get_step() {
-                                worker.step();
+                                step(worker, &probe, &mut latency);
                                 threshold = 0;
                             }
                         }
@@ -371,9 +830,10 @@ fn execute_from_stdin(
                 //     current_segment.clone().into_iter(),
                 // );
 
-                worker.step();
+                step(worker, &probe, &mut latency);
 
-                let new_prod = current + 1;
+                // lines of several sources may arrive out of time point order
+                let new_prod = max(max(current, max_tp), max_wm) + 1;
                 time_input
                     .session(cap.delayed(&new_prod))
                     .give(TimeFlowValues::EOS);
@@ -381,6 +841,16 @@ fn execute_from_stdin(
                     .session(cap.delayed(&new_prod))
                     .give("<eos>".parse().unwrap());
 
+                if let Some(mut latency) = latency {
+                    // the verdicts of the last time points are final once the dataflow is done
+                    drop((cap, time_cap));
+                    while !probe.done() {
+                        worker.step();
+                        latency.complete(&probe);
+                    }
+                    let _ = latency_send.send(latency.report());
+                }
+
                 for tp_ts in tp_to_ts {
                     let _ = tp_send.send(tp_ts);
                 }
@@ -388,6 +858,7 @@ fn execute_from_stdin(
         },
     )
     .unwrap();
+    let latency = latency_recv.try_recv().ok();
 
     if options_.get_output_mode() == 0 {
         let mut tp_to_ts = HashMap::with_capacity(8);
@@ -401,22 +872,25 @@ fn execute_from_stdin(
             .cloned()
             .map(|(time, tuples)| (time, tuples))
             .collect();
-        return (res, tp_to_ts.clone());
+        return (res, tp_to_ts.clone(), latency);
     }
 
-    (vec![], HashMap::new())
+    (vec![], HashMap::new(), latency)
 }
 
-fn execute_from_file(
-    policy: String,
-    path_data: PathBuf,
+fn execute_from_file<E: InputEvent, I: IntoIterator<Item = Segment<E>> + Send + 'static>(
+    policy: Formula,
+    segments: I,
     options: OperatorOptions,
+    latency_report: bool,
+    max_incomplete: usize,
 ) -> (
     Vec<(
         usize,
         Vec<mfodl_monitor::dataflow_constructor::types::FlowValues>,
     )>,
     HashMap<usize, usize>,
+    Option<LatencyReport>,
 ) {
     let (send, recv) = std::sync::mpsc::channel();
     let send = std::sync::Arc::new(std::sync::Mutex::new(send));
@@ -424,6 +898,11 @@ fn execute_from_file(
     let (tp_send, tp_recv) = std::sync::mpsc::channel();
     let tp_send = std::sync::Arc::new(std::sync::Mutex::new(tp_send));
 
+    let (latency_send, latency_recv) = std::sync::mpsc::channel();
+    let latency_send = std::sync::Arc::new(std::sync::Mutex::new(latency_send));
+
+    let segments = std::sync::Arc::new(std::sync::Mutex::new(Some(segments)));
+
     let options_ = options.clone();
 
     timely::execute(
@@ -433,15 +912,16 @@ fn execute_from_file(
             let mut tp_to_ts: HashMap<usize, usize> = HashMap::with_capacity(8);
             let send = send.lock().unwrap().clone();
             let tp_send = tp_send.lock().unwrap().clone();
+            let latency_send = latency_send.lock().unwrap().clone();
 
-            let (mut input, mut cap, mut time_input, mut time_cap, _probe) = worker
+            let (mut input, mut cap, mut time_input, mut time_cap, probe) = worker
                 .dataflow::<usize, _, _>(|scope| {
                     let ((time_input, time_cap), time_stream) =
                         scope.new_unordered_input::<TimeFlowValues>();
-                    let ((input, input_cap), stream) = scope.new_unordered_input::<String>();
+                    let ((input, input_cap), stream) = scope.new_unordered_input::<E>();
 
                     let (_attrs, output) = create_dataflow(
-                        parse_formula(&policy),
+                        policy.clone(),
                         stream,
                         time_stream,
                         options.clone(),
@@ -455,19 +935,39 @@ fn execute_from_file(
 
             // Send data and step the workers
             if worker.index() == 0 {
-                let segments = parse_file_to_segments(path_data.clone());
+                let verdict_limit = options.get_verdict_limit();
+                let segments = segments.lock().unwrap().take().expect("Segments already taken");
+                let mut segments = PolledSource::spawn(segments, INPUT_BUFFER);
                 let mut max_wm = 0;
                 let mut max_tp = 0;
-                for segs in segments {
+                let mut latency = if latency_report { Some(LatencyTracker::new()) } else { None };
+                let mut backpressure = Backpressure::new(max_incomplete);
+                loop {
+                    if verdict_limit.as_ref().is_some_and(VerdictLimit::reached) {
+                        break;
+                    }
+                    backpressure.wait(worker, &probe, || complete(&probe, &mut latency));
+                    let segs = match segments.poll(IDLE) {
+                        Poll::Item(segs) => segs,
+                        Poll::Pending => {
+                            step(worker, &probe, &mut latency);
+                            continue;
+                        }
+                        Poll::Ended => break,
+                    };
                     match segs {
                         Segment::Epoch(wm) => {
                             let t = if wm < 0 { 0 } else { wm as usize };
                             time_cap.downgrade(&t);
                             cap.downgrade(&t);
-                            worker.step();
+                            step(worker, &probe, &mut latency);
                             max_wm = t;
                         }
                         Segment::Seg(tp, ts, val) => {
+                            if let Some(latency) = latency.as_mut() {
+                                latency.ingest(tp);
+                            }
+                            backpressure.ingest(tp);
                             tp_to_ts.entry(tp).or_insert(ts);
                             max_tp = max(max_tp, tp);
                             time_input
@@ -477,12 +977,12 @@ fn execute_from_file(
                             input
                                 .session(cap.delayed(&tp))
                                 .give_iterator(val.into_iter());
-                            worker.step();
+                            step(worker, &probe, &mut latency);
                         }
                     }
 
                     if threshold >= options.get_step() {
-                        worker.step();
+                        step(worker, &probe, &mut latency);
                         threshold = 0;
                     }
                 }
@@ -493,8 +993,18 @@ fn execute_from_file(
                     .give(TimeFlowValues::EOS);
                 input
                     .session(cap.delayed(&new_prod))
-                    .give("<eos>".parse().unwrap());
-                worker.step();
+                    .give(E::eos());
+                step(worker, &probe, &mut latency);
+
+                if let Some(mut latency) = latency {
+                    // the verdicts of the last time points are final once the dataflow is done
+                    drop((cap, time_cap));
+                    while !probe.done() {
+                        worker.step();
+                        latency.complete(&probe);
+                    }
+                    let _ = latency_send.send(latency.report());
+                }
 
                 for tp_ts in tp_to_ts {
                     let _ = tp_send.send(tp_ts);
@@ -503,6 +1013,7 @@ fn execute_from_file(
         },
     )
     .unwrap();
+    let latency = latency_recv.try_recv().ok();
 
     if options_.get_output_mode() == 0 {
         let mut tp_to_ts = HashMap::with_capacity(8);
@@ -516,8 +1027,57 @@ fn execute_from_file(
             .cloned()
             .map(|(time, tuples)| (time, tuples))
             .collect();
-        return (res, tp_to_ts.clone());
+        return (res, tp_to_ts.clone(), latency);
     }
 
-    return (vec![], HashMap::new());
+    return (vec![], HashMap::new(), latency);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mfodl_monitor::dataflow_constructor::types::default_options;
+    use mfodl_monitor::parser::formula_parser::parse_formula;
+    use mfodl_monitor::parser::formula_syntax_tree::Constant::Int;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    #[test]
+    fn verdict_limit_stops_an_open_source() {
+        let limit = VerdictLimit::new(1);
+        let mut options = default_options();
+        options.set_verdict_limit(Some(limit.clone()));
+
+        // the sender stays alive, so the source never ends
+        let (line_send, line_recv) = channel();
+        let (done_send, done_recv) = channel();
+        thread::spawn(move || {
+            execute_from_lines(parse_formula("A(x)"), options, None, Box::new(line_recv.into_iter()), false, 1000);
+            done_send.send(()).unwrap();
+        });
+        line_send.send("A, tp=0, ts=0, x0=1".to_string()).unwrap();
+        line_send.send(">WATERMARK 1<".to_string()).unwrap();
+
+        assert!(done_recv.recv_timeout(Duration::from_secs(30)).is_ok(), "the monitor kept waiting for input");
+        assert_eq!(limit.get_violations(), vec![(0, 0, vec![Int(1)])]);
+        drop(line_send);
+    }
+
+    #[test]
+    fn latency_is_sampled_while_waiting_for_input() {
+        let (line_send, line_recv) = channel();
+        let run = thread::spawn(move || {
+            execute_from_lines(parse_formula("A(x)"), default_options(), None, Box::new(line_recv.into_iter()), true, 1000).2
+        });
+        line_send.send("A, tp=0, ts=0, x0=1".to_string()).unwrap();
+        line_send.send(">WATERMARK 1<".to_string()).unwrap();
+        // time point 0 is complete long before the next line arrives
+        thread::sleep(Duration::from_millis(500));
+        line_send.send("A, tp=1, ts=1, x0=2".to_string()).unwrap();
+        drop(line_send);
+
+        let report = run.join().unwrap().unwrap();
+        assert_eq!(report.len(), 2);
+        assert!(report.max().unwrap() < Duration::from_millis(250), "{}", report);
+    }
 }
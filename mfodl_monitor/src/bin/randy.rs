@@ -4,9 +4,10 @@ extern crate clap;
 extern crate nom;
 
 use std::fs::{DirBuilder, File};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::{Path};
-use mfodl_monitor::parser::csv_parser::{parse_gen_fma_wrapper, parse_timelymon_results, parse_verimon_results, parser_extended_wrapper, ParserReturn};
+use mfodl_monitor::parser::binary_log::read_csv_log;
+use mfodl_monitor::parser::csv_parser::{parse_timelymon_results, parse_verimon_results};
 
 use std::env::{set_current_dir};
 use std::collections::{BTreeMap, HashMap, HashSet};
@@ -15,14 +16,17 @@ use std::process::{Command, Output};
 use std::time::Instant;
 
 use mfodl_monitor::parser::formula_syntax_tree::Constant;
+use mfodl_monitor::parser::monpoly::to_native_policy;
+use mfodl_monitor::parser::signature::Signatures;
+use mfodl_monitor::policy_generator::{generate_policy, PolicyConfig};
+use mfodl_monitor::stream_io::reorder::{log_events, with_watermarks};
+use mfodl_monitor::stream_io::replayer::{read_log, replay, replay_lines, LogFormat, ReplayOptions};
+use mfodl_monitor::trace_generator::{write_trace, EventSource, TraceConfig};
 
 static PATH_TO_RESULTS: &str = "/Users/krq770/CLionProjects/timelymon/mfodl_monitor/data/Experiments/Results/results.csv";
 static PATH_TO_EXPERIMENTS: &str = "/Users/krq770/CLionProjects/timelymon/mfodl_monitor/data/Experiments";
 static PATH_TO_TIMELYMON: &str = "/Users/krq770/CLionProjects/timelymon/mfodl_monitor/target/release/timelymon";
-static PATH_TO_FORMULA_GENERATOR: &str = "/Users/krq770/Desktop/Experiments_Stream_Monitor/monpoly/_build/default/tools/gen_fma.exe";
 static PATH_TO_VERIMON: &str= "/Users/krq770/Desktop/Experiments_Stream_Monitor/monpoly/monpoly";
-static PATH_TO_TRACE_GENERATOR: &str = "/Users/krq770/CLionProjects/timelymon/mfodl_monitor/tools/Generator/Generator.jar";
-static PATH_TO_REPLAYER: &str = "/Users/krq770/CLionProjects/timelymon/mfodl_monitor/tools/Replayer/Replayer.jar";
 static MAX_TS: usize = 500;
 
 // todo two modes, a fixes n formula experiment and a randomized testing framework
@@ -30,67 +34,26 @@ static MAX_TS: usize = 500;
 // todo allow user to fix an operation and try to generate those operators fx generate formula with PREVIOUS [0, *)
 
 fn infuse_watermarks(file_name: String) {
-    let file = File::open(file_name.clone());
-    let mut res : String = "".to_string();
-    if let Ok(wf) = file {
-        let mut current_is_set = false;
-        let mut current_tp = 0;
-        for line in BufReader::new(&wf).lines() {
-            let resstr = line.unwrap();
-            match parser_extended_wrapper(resstr.clone()) {
-                ParserReturn::Data(tp, _, _) => {
-                    if current_is_set {
-                        if tp == current_tp {
-                            res.push_str(&format!("{}\n", resstr))
-                        } else {
-                            res.push_str(&format!(">WATERMARK {current_tp}<\n"));
-                            current_tp = tp;
-                            res.push_str(&format!("{}\n", resstr))
-                        }
-                    } else {
-                        current_tp = tp;
-                        current_is_set = true;
-                        res.push_str(&format!("{}\n", resstr))
-                    }
-                }
-                _ => {}
+    let lines = read_csv_log(Path::new(&file_name))
+        .and_then(log_events)
+        .and_then(|events| with_watermarks(&events, Some(1)));
+    if let Ok(lines) = lines {
+        let file = File::create(format!("wm_{}", file_name));
+        if let Ok(wf) = file {
+            let mut out = BufWriter::new(&wf);
+            for line in lines {
+                let _ = writeln!(out, "{}", line);
             }
         }
     }
-
-    let file = File::create(format!("wm_{}", file_name));
-    if let Ok(wf) = file {
-        let _ = BufWriter::new(&wf).write_all(res.as_ref());
-    }
 }
 
-fn create_base_data_set(output_name: String, t: i32) {
+fn create_base_data_set(output_name: String, t: i32) -> io::Result<()> {
     // Check the result
-    match Command::new("java")
-        .arg("-cp").arg(PATH_TO_TRACE_GENERATOR).arg("org.example.CsvStreamGenerator")
-        .arg("-sig").arg(&format!("{output_name}.sig"))
-        .arg("-x").arg("1.0")
-        .arg("-e").arg("1000")
-        .arg(&format!("{t}")).output() {
-        Ok(x) => {
-            let data_file = format!("{output_name}_{t}");
-            write_data_file(x, data_file, ".csv".to_string(), false);
-        }
-        Err(_) => {}
-    }
+    generate_trace(&output_name, t, format!("{output_name}_{t}.csv"))?;
 
     // replayer
-    match Command::new("java")
-        .arg("-cp").arg(PATH_TO_REPLAYER).arg("org.example.Replayer")
-        .arg("-f").arg("verimon")
-        .arg("-a").arg("0")
-        .arg(&format!("{output_name}_{t}.csv")).output() {
-        Ok(x) => {
-            let data_file = format!("verimon_{output_name}_{t}");
-            write_data_file(x, data_file, "".to_string(), true);
-        }
-        Err(_) => {}
-    }
+    to_monpoly_log(format!("{output_name}_{t}.csv"), format!("verimon_{output_name}_{t}"))?;
 
     // create verification set
     match Command::new(PATH_TO_VERIMON).arg("-sig")
@@ -118,48 +81,28 @@ fn create_base_data_set(output_name: String, t: i32) {
 
 
     // todo treat it like delayed data for now
-    match Command::new("java")
-        .arg("-cp").arg(PATH_TO_TRACE_GENERATOR).arg("org.example.CsvStreamGenerator")
-        .arg("-sig").arg(&format!("{output_name}.sig"))
-        .arg("-e").arg("1000")
-        .arg(&format!("{t}")).output() {
-        Ok(x) => {
-            let data_file = format!("{output_name}_{t}_delayed");
-            write_data_file(x, data_file, ".csv".to_string(), false);
-        }
-        Err(_) => {}
-    }
+    generate_trace(&output_name, t, format!("{output_name}_{t}_delayed.csv"))
 }
 
-fn parse_gen_fma_output(out: Output) -> (String, String) {
-    let output_str = String::from_utf8_lossy(&out.stdout).to_string();
-    parse_gen_fma_wrapper(output_str)
+fn generate_trace(output_name: &str, t: i32, data_file: String) -> io::Result<()> {
+    let signatures = Signatures::from_file(Path::new(&format!("{output_name}.sig")))?;
+    let mut config = TraceConfig::new(EventSource::Signature(signatures), t as usize);
+    config.event_rate = 1000;
+    let mut out = BufWriter::new(File::create(data_file)?);
+    write_trace(&config, false, &mut out)?;
+    out.flush()
 }
 
-fn replace_pattern(input: String) -> String {
-    // Define the regular expression pattern
-    let pattern = regex::Regex::new(r"\((\d+),(\d+)\)").unwrap();
-
-    // Replace matches using the provided closure
-    let result = pattern.replace_all(&*input, |caps: &regex::Captures| {
-        // Extract the captured numbers
-        let x1 = caps[1].to_string().parse::<usize>().unwrap();
-        let x2 = caps[2].to_string().parse::<usize>().unwrap();
-
-        // Construct the replacement string
-        if x1+1 == x2 {
-            format!("[{},{})", x1, x2)
-        } else {
-            format!("({},{})", x1, x2)
-        }
-
-    });
-
-    // Return the final result as a String
-    result.into()
+fn to_monpoly_log(csv_file: String, data_file: String) -> io::Result<()> {
+    let options = ReplayOptions::new(LogFormat::MonPoly);
+    let lines = replay_lines(read_log(Path::new(&csv_file), LogFormat::Csv)?, &options)?;
+    let mut out = BufWriter::new(File::create(data_file)?);
+    replay(lines, &options, &mut out)?;
+    write!(out, "@{MAX_TS};")?;
+    out.flush()
 }
 
-fn write_gen_fma_output(sig: String, mfotl: String, name: String) {
+fn write_policy(sig: String, mfotl: String, name: String) {
     let sig_name = format!("{name}.sig");
     let file = File::create(sig_name);
     if let Ok(mut wf) = file {
@@ -222,29 +165,27 @@ fn data_generation(operators: Vec<i32>, free_variables: Vec<i32>, trace_len: Vec
                     let output_name = format!("op{}fv{}i{}", op, fv, i);
                     // populate file if either non-existent or empty
                     if !Path::exists(Path::new(&output_name.clone())) {
-                        match Command::new(PATH_TO_FORMULA_GENERATOR).arg("-nolet").arg("-size").arg(&format!("{op}"))
-                            .arg("-free_vars").arg(&format!("{fv}")).arg("-max_lb").arg(&format!("{max_lb}")).arg("-max_interval")
-                            .arg(&format!("{max_rb}")).arg(&format!("op{}fv{}i{}", op, fv, i)).output() {
-                            Ok(x) => {
-                                let (sig, mfotl) = parse_gen_fma_output(x);
-                                let new_mfotl = replace_pattern(mfotl);
-                                write_gen_fma_output(sig, new_mfotl, output_name.clone());
-
-                                let mut is_empty = false;
-                                let file = File::open(output_name.clone());
-                                if let Ok(wf) = file {
-                                    is_empty = wf.metadata().unwrap().len() == 0;
-                                }
+                        let mut config = PolicyConfig::new(op as usize, fv as usize);
+                        config.max_lb = max_lb as usize;
+                        config.max_interval = max_rb as usize;
+                        config.seed = (op * 10_000 + fv * 100 + i) as usize;
+                        let (policy, signatures) = generate_policy(&config);
+                        write_policy(signatures.to_string(), to_native_policy(&policy), output_name.clone());
+
+                        let mut is_empty = false;
+                        let file = File::open(output_name.clone());
+                        if let Ok(wf) = file {
+                            is_empty = wf.metadata().unwrap().len() == 0;
+                        }
 
-                                for t in trace_len.clone() {
-                                    println!("                      Generate data sets {t}");
-                                    if !Path::exists(Path::new(&output_name.clone())) || (Path::exists(Path::new(&output_name.clone())) && is_empty) {
-                                        create_base_data_set(output_name.clone(), t);
-                                    }
+                        for t in trace_len.clone() {
+                            println!("                      Generate data sets {t}");
+                            if !Path::exists(Path::new(&output_name.clone())) || (Path::exists(Path::new(&output_name.clone())) && is_empty) {
+                                if let Err(e) = create_base_data_set(output_name.clone(), t) {
+                                    println!("                      Unable to generate data set {t}: {e}");
                                 }
                             }
-                            Err(x) => { println!("err {:?}", x) }
-                        };
+                        }
                     }
                 }
 
@@ -550,26 +491,3 @@ fn results_to_csv(result: &mut HashMap<(String, i32, i32, i32, i32, i32), u128>)
         println!("File not found {:?}", PATH_TO_RESULTS)
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use replace_pattern;
-
-    #[test]
-    fn t1() {
-        let act = format!("(EVENTUALLY(0,23] (((17 = 38) AND (NOT P0())) AND (NEXT[0,4) (((PREVIOUS(0,1) (P0() AND (ONCE(0,*) ((NOT (ONCE[0,*) P0())) SINCE[0,*) (34 = 26))))) AND ((NOT (P1() OR (21 = 16))) UNTIL(0,6] P1())) AND (((32 = 4) AND P1()) OR P2())))))");
-        let exp = format!("(EVENTUALLY(0,23] (((17 = 38) AND (NOT P0())) AND (NEXT[0,4) (((PREVIOUS[0,1) (P0() AND (ONCE(0,*) ((NOT (ONCE[0,*) P0())) SINCE[0,*) (34 = 26))))) AND ((NOT (P1() OR (21 = 16))) UNTIL(0,6] P1())) AND (((32 = 4) AND P1()) OR P2())))))");
-
-        let res = replace_pattern(act);
-        assert_eq!(res, exp);
-    }
-
-    #[test]
-    fn t2() {
-        let act = format!("((NOT ((NOT (ONCE(0,4] (9 = 39))) SINCE(0,*) (PREVIOUS(0,10) (EVENTUALLY(0,10] (((P0() SINCE[0,*) (P0() AND (NOT ((3 = 24) SINCE[0,*) (42 = 17))))) AND (NOT (ONCE[0,*) (28 = 38)))) AND (NOT (9 = 42))))))) SINCE[0,0] (((((NOT P1()) SINCE[0,22] P1()) AND P1()) SINCE[0,20] P2()) AND (NOT P1())))");
-        let exp = format!("((NOT ((NOT (ONCE(0,4] (9 = 39))) SINCE(0,*) (PREVIOUS(0,10) (EVENTUALLY(0,10] (((P0() SINCE[0,*) (P0() AND (NOT ((3 = 24) SINCE[0,*) (42 = 17))))) AND (NOT (ONCE[0,*) (28 = 38)))) AND (NOT (9 = 42))))))) SINCE[0,0] (((((NOT P1()) SINCE[0,22] P1()) AND P1()) SINCE[0,20] P2()) AND (NOT P1())))");
-
-        let res = replace_pattern(act);
-        assert_eq!(res, exp);
-    }
-}
\ No newline at end of file
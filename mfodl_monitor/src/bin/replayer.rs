@@ -0,0 +1,117 @@
+extern crate mfodl_monitor;
+extern crate structopt;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use mfodl_monitor::stream_io::replayer::{read_log, replay, replay_lines, LogFormat, ReplayOptions};
+use mfodl_monitor::stream_io::socket_sink::{SinkAddress, SocketSink};
+use mfodl_monitor::timeunits::TimeUnit;
+use structopt::StructOpt;
+
+const SINK_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "replayer",
+    about = "Converts event logs between CSV, JSON and MonPoly and replays them at a given speed."
+)]
+pub struct ReplayerArgs {
+    /// Input log
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+
+    /// Format of the input log: csv, json, monpoly or binary
+    #[structopt(short = "f", long = "format", default_value = "csv")]
+    format: LogFormat,
+
+    /// Format of the replayed log: csv, json or monpoly
+    #[structopt(long = "to", default_value = "csv")]
+    to: LogFormat,
+
+    /// Events per second, as fast as possible otherwise
+    #[structopt(long = "rate")]
+    rate: Option<f64>,
+
+    /// Replay with the speed of the timestamps, accelerated by this factor
+    #[structopt(short = "a", long = "acceleration")]
+    acceleration: Option<f64>,
+
+    /// Unit of the timestamps of the log (ms, s, m, h or d), used with --acceleration
+    #[structopt(long = "ts-unit", default_value = "s")]
+    ts_unit: TimeUnit,
+
+    /// Inject a watermark every N time points (timestamps for JSON), replacing those of the log
+    #[structopt(long = "watermark-period")]
+    watermark_period: Option<usize>,
+
+    /// Delay events randomly by up to N time points (timestamps for JSON)
+    #[structopt(long = "reorder", default_value = "0")]
+    reorder: usize,
+
+    /// Seed of the reordering
+    #[structopt(long = "seed", default_value = "0")]
+    seed: usize,
+
+    /// Push the log to a socket, given as tcp:<host>:<port> or unix:<path>
+    #[structopt(long = "sink", conflicts_with = "output")]
+    sink: Option<SinkAddress>,
+
+    /// Number of lines buffered for the sink, the replay waits while the buffer is full
+    #[structopt(long = "sink-buffer", default_value = "10000")]
+    sink_buffer: usize,
+
+    /// Output file, stdout otherwise
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    let args = ReplayerArgs::from_args();
+
+    if args.to == LogFormat::Binary {
+        println!("Binary logs are written by convert");
+        process::exit(2);
+    }
+    if args.rate.is_some() && args.acceleration.is_some() {
+        println!("Expected at most one of --rate and --acceleration");
+        process::exit(2);
+    }
+
+    let mut options = ReplayOptions::new(args.to);
+    options.rate = args.rate;
+    options.acceleration = args.acceleration;
+    options.ts_unit = args.ts_unit;
+    options.watermark_period = args.watermark_period;
+    options.reorder = args.reorder;
+    options.seed = args.seed;
+
+    let lines = match read_log(&args.input, args.format).and_then(|records| replay_lines(records, &options)) {
+        Ok(lines) => lines,
+        Err(e) => {
+            println!("Unable to convert {}: {}", args.input.display(), e);
+            process::exit(2);
+        }
+    };
+
+    let result = match (args.sink, &args.output) {
+        (Some(address), _) => {
+            let mut sink = SocketSink::blocking(address, args.sink_buffer);
+            replay(lines, &options, &mut sink).and_then(|_| sink.close(SINK_CLOSE_TIMEOUT))
+        }
+        (None, Some(path)) => File::create(path).and_then(|f| replay(lines, &options, &mut BufWriter::new(f)).map(|_| ())),
+        (None, None) => {
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            replay(lines, &options, &mut out).and_then(|_| out.flush())
+        }
+    };
+
+    if let Err(e) = result {
+        println!("Unable to replay the log: {}", e);
+        process::exit(2);
+    }
+}
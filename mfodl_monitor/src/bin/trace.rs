@@ -0,0 +1,150 @@
+extern crate mfodl_monitor;
+extern crate structopt;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+
+use mfodl_monitor::parser::binary_log::read_csv_log;
+use mfodl_monitor::stream_io::reorder::{delay, log_events, reverse, shuffle_epochs, shuffle_events, with_watermarks, DelayDistribution};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "timelymon-trace",
+    about = "Reorders CSV traces and infuses them with watermarks."
+)]
+pub enum TraceArgs {
+    /// Reverses the trace, or each epoch of it
+    #[structopt(name = "reverse")]
+    Reverse {
+        /// Reverse each epoch of N time points, the whole trace otherwise
+        #[structopt(long = "epoch")]
+        epoch: Option<usize>,
+
+        #[structopt(flatten)]
+        io: TraceIo,
+    },
+
+    /// Shuffles the time points of the trace, or of each epoch of it
+    #[structopt(name = "shuffle")]
+    Shuffle {
+        /// Shuffle each epoch of N time points, the whole trace otherwise
+        #[structopt(long = "epoch")]
+        epoch: Option<usize>,
+
+        /// Shuffle single events instead of whole time points
+        #[structopt(long = "events")]
+        events: bool,
+
+        /// Seed of the shuffling
+        #[structopt(long = "seed", default_value = "0")]
+        seed: usize,
+
+        #[structopt(flatten)]
+        io: TraceIo,
+    },
+
+    /// Delays events by a normally distributed number of time points
+    #[structopt(name = "delay")]
+    Delay {
+        /// Largest delay in time points
+        #[structopt(long = "max-delay")]
+        max_delay: usize,
+
+        /// Mean delay in time points
+        #[structopt(long = "mean", default_value = "0")]
+        mean: f64,
+
+        /// Standard deviation of the delay in time points
+        #[structopt(long = "std-dev", default_value = "1")]
+        std_dev: f64,
+
+        /// Seed of the delays
+        #[structopt(long = "seed", default_value = "0")]
+        seed: usize,
+
+        #[structopt(flatten)]
+        io: TraceIo,
+    },
+
+    /// Keeps the order of the trace and replaces its watermarks
+    #[structopt(name = "watermarks")]
+    Watermarks {
+        #[structopt(flatten)]
+        io: TraceIo,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TraceIo {
+    /// Input trace in CSV
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+
+    /// Watermark at the latest every N time points
+    #[structopt(long = "watermark-period", default_value = "1")]
+    watermark_period: usize,
+
+    /// Write no watermarks
+    #[structopt(long = "no-watermarks")]
+    no_watermarks: bool,
+
+    /// Output file, stdout otherwise
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+fn write_lines<W: Write>(lines: &[String], out: &mut W) -> io::Result<()> {
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+fn main() {
+    let args = TraceArgs::from_args();
+    let trace_io = match &args {
+        TraceArgs::Reverse { io, .. } | TraceArgs::Shuffle { io, .. } | TraceArgs::Delay { io, .. } | TraceArgs::Watermarks { io } => io,
+    };
+
+    let events = match read_csv_log(&trace_io.input).and_then(log_events) {
+        Ok(events) => events,
+        Err(e) => {
+            println!("Unable to read {}: {}", trace_io.input.display(), e);
+            process::exit(2);
+        }
+    };
+
+    let events = match &args {
+        TraceArgs::Reverse { epoch, .. } => reverse(events, *epoch),
+        TraceArgs::Shuffle { epoch, events: true, seed, .. } => shuffle_events(events, *epoch, *seed),
+        TraceArgs::Shuffle { epoch, seed, .. } => shuffle_epochs(events, *epoch, *seed),
+        TraceArgs::Delay { max_delay, mean, std_dev, seed, .. } => {
+            if *std_dev < 0.0 {
+                println!("Expected a non-negative --std-dev");
+                process::exit(2);
+            }
+            let distribution = DelayDistribution { mean: *mean, std_dev: *std_dev, max_delay: *max_delay };
+            delay(events, distribution, *seed)
+        }
+        TraceArgs::Watermarks { .. } => events,
+    };
+
+    let period = if trace_io.no_watermarks { None } else { Some(trace_io.watermark_period) };
+
+    let result = with_watermarks(&events, period).and_then(|lines| match &trace_io.output {
+        Some(path) => File::create(path).and_then(|f| write_lines(&lines, &mut BufWriter::new(f))),
+        None => {
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            write_lines(&lines, &mut out)
+        }
+    });
+
+    if let Err(e) = result {
+        println!("Unable to write the trace: {}", e);
+        process::exit(2);
+    }
+}
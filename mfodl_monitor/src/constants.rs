@@ -56,6 +56,8 @@ pub const NUM_WORKERS: usize = 4;
 pub const TEST_FACT: &'static str = "p(\'foo\')";
 
 pub const CONJ_NEG_ERROR: &str = "Negation on left hand side of conjunction is not allowed";
+pub const EMPTY_INTERVAL_ERROR: &str = "Empty or inverted interval";
+pub const INTERVAL_UNIT_ERROR: &str = "Interval bound is not a whole number of timestamps";
 pub const TYPES_PRINT_ERROR: &str = "Error while trying to print string for formula";
 
 pub fn get_diff<T: Eq + Hash + Clone>(fst: Vec<T>, snd: Vec<T>) -> usize {
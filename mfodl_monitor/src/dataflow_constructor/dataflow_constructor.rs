@@ -15,7 +15,8 @@ use parse_formula;
 
 use constants::calculate_hash;
 use dataflow_constructor::types::{
-    FlowValues::Data, FlowValues::MetaData, OperatorOptions, Record, TimeFlowValues,
+    FlowValues::Data, FlowValues::MetaData, InputEvent, OperatorOptions,
+    PendingCondition, Record, TimeFlowValues,
 };
 
 use evaluation_plan_generator::evaluation_plan_generator::Expr::*;
@@ -26,27 +27,26 @@ use parser::formula_syntax_tree::Formula::*;
 use parser::formula_syntax_tree::*;
 
 use dataflow_constructor::operators::{
-    find_common_bound_variables, find_common_bound_variables1, get_wanted_indices, Operators,
-    SupportOperators,
+    find_common_bound_variables, find_common_bound_variables1, get_wanted_indices, verdict_limit,
+    Operators, SupportOperators,
 };
 use parser::formula_syntax_tree::Constant::Str;
 
 use jq_rs::compile as jq_compile;
 
 type MonitorStream<G> = Stream<G, Record>;
-type DataStream<G> = Stream<G, String>;
+type DataStream<G, E> = Stream<G, E>;
 type TimeStream<G> = Stream<G, TimeFlowValues>;
 
-//TODO change data_stream: Stream<G, String> to Stream<G, Constant>.
-struct DataflowConstructor<G: Scope<Timestamp = usize>> {
-    data_stream: Stream<G, String>,
+struct DataflowConstructor<G: Scope<Timestamp = usize>, E: InputEvent> {
+    data_stream: Stream<G, E>,
     stream_map: HashMap<Expr, (Vec<String>, MonitorStream<G>)>,
     time_stream: Stream<G, TimeFlowValues>,
 }
 
-pub fn create_dataflow<G: Scope<Timestamp = usize>>(
+pub fn create_dataflow<G: Scope<Timestamp = usize>, E: InputEvent>(
     policy: Formula,
-    data_stream: DataStream<G>,
+    data_stream: DataStream<G, E>,
     time_stream: TimeStream<G>,
     options: OperatorOptions,
 ) -> (Vec<String>, MonitorStream<G>) {
@@ -66,8 +66,16 @@ pub fn create_dataflow<G: Scope<Timestamp = usize>>(
         options.get_deduplication(),
     );
 
-    if dataflow_constructor.stream_exists(plan.clone()) {
+    if dataflow_constructor.stream_exists(optimized_plan.clone()) {
         let (tmp_str, tmp_stream) = dataflow_constructor.get_stream(optimized_plan.clone());
+        let (tmp_str, tmp_stream) = if options.get_unknown_at_eos() {
+            dataflow_constructor.add_unknown_verdicts(optimized_plan.clone(), tmp_str, tmp_stream)
+        } else {
+            (tmp_str, tmp_stream)
+        };
+        if let Some(limit) = options.get_verdict_limit() {
+            verdict_limit(&tmp_stream, &output_time_steam.broadcast(), limit);
+        }
         // add exhaust operator to filter all metadata before pushing data to output
         //println!("OUTPUT {:?}", tmp_str);
         return (
@@ -81,7 +89,54 @@ pub fn create_dataflow<G: Scope<Timestamp = usize>>(
     panic!("Error - output stream missing");
 }
 
-impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
+/// Reason why the verdicts that are still pending at the end of the stream (see
+/// `OperatorOptions::set_unknown_at_eos`) cannot be reported for the policy. They are only tracked
+/// for an Eventually or Until with an unbounded interval at the root of the evaluation plan; the
+/// verdicts of every other unbounded future operator follow finite trace semantics.
+pub fn unknown_verdicts_error(policy: &Formula) -> Option<String> {
+    let nested = match optimize_evaluation_plan(generate_evaluation_plan(policy)) {
+        Expr::Eventually(e1, interval) if interval.is_infinite() => has_unbounded_future(&e1),
+        Expr::Until(e1, e2, interval) | Expr::NegUntil(e1, e2, interval)
+            if interval.is_infinite() =>
+        {
+            has_unbounded_future(&e1) || has_unbounded_future(&e2)
+        }
+        plan => has_unbounded_future(&plan),
+    };
+    if nested {
+        Some("unknown verdicts are only reported for an EVENTUALLY or UNTIL with an unbounded interval at the top of the policy, which contains no other unbounded future operator".to_string())
+    } else {
+        None
+    }
+}
+
+fn has_unbounded_future(plan: &Expr) -> bool {
+    match plan {
+        Expr::Eventually(e, interval) | Expr::Always(e, interval) => {
+            interval.is_infinite() || has_unbounded_future(e)
+        }
+        Expr::Until(lhs, rhs, interval) | Expr::NegUntil(lhs, rhs, interval) => {
+            interval.is_infinite() || has_unbounded_future(lhs) || has_unbounded_future(rhs)
+        }
+        Expr::Not(e)
+        | Expr::Once(e, _)
+        | Expr::Historically(e, _)
+        | Expr::Next(e, _)
+        | Expr::Prev(e, _)
+        | Expr::Project(_, e)
+        | Expr::Extend(_, _, e)
+        | Expr::Filter(_, _, e)
+        | Expr::NegFilter(_, _, e) => has_unbounded_future(e),
+        Expr::Join(lhs, rhs)
+        | Expr::UnionJoin(lhs, rhs)
+        | Expr::Antijoin(lhs, rhs)
+        | Expr::Since(lhs, rhs, _)
+        | Expr::NegSince(lhs, rhs, _) => has_unbounded_future(lhs) || has_unbounded_future(rhs),
+        _ => false,
+    }
+}
+
+impl<'a, G: Scope<Timestamp = usize>, E: InputEvent> DataflowConstructor<G, E> {
     fn create_base_stream(
         &mut self,
         visitor: &mut usize,
@@ -95,13 +150,11 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
         let f_vars = fv(args.clone());
         let f_vars_args: Vec<Arg> = args.clone();
 
-        let formula_clone = f.clone();
-
         let output = if !simple_mode {
             self.data_stream
                 .unary_frontier(exchange, "Base Stream", move |_cap, _info| {
                     let mut notifier = FrontierNotificator::new();
-                    let mut stash: HashMap<usize, HashSet<String>> = HashMap::new();
+                    let mut stash: HashMap<usize, HashSet<E>> = HashMap::new();
                     move |input, output| {
                         while let Some((time, data)) = input.next() {
                             if data.len() == 0 {
@@ -113,7 +166,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                             let name_copy = f_name.clone();
                             let f_vars_args_copy = f_vars_args.clone();
                             data.iter().for_each(|d| {
-                                match parse_formula(&d) {
+                                match d.to_fact() {
                                     Formula::Fact(name, vec) => {
                                         if vec.is_empty() && name == name_copy {
                                             output.session(&time).give(Data(true, vec![]));
@@ -195,7 +248,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
             self.data_stream
                 .unary_frontier(exchange, "Base Stream", move |_cap, _info| {
                     let mut notifier = FrontierNotificator::new();
-                    let mut stash: HashMap<usize, HashSet<String>> = HashMap::new();
+                    let mut stash: HashMap<usize, HashSet<E>> = HashMap::new();
                     move |input, output| {
                         while let Some((time, data)) = input.next() {
                             if data.len() == 0 {
@@ -207,10 +260,12 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
 
                             data.iter().for_each(|d| {
                                 // println!("In operator 2 {:?}", &d);
-                                if d == "<eos>" {
+                                if d.is_eos() {
                                     output.session(&time).give(MetaData(false, false));
                                 } else {
-                                    match &formula_clone {
+                                    // the valuation is read off the event, the atom of the policy
+                                    // only has variables
+                                    match d.to_fact() {
                                         Formula::Fact(name, vec) => {
                                             if vec.is_empty() && name.clone() == name_copy {
                                                 output.session(&time).give(Data(true, vec![]));
@@ -225,7 +280,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                                                                 .insert(d.clone());
                                                             let mut tmp =
                                                                 Vec::with_capacity(vec.len());
-                                                            for v in vec {
+                                                            for v in &vec {
                                                                 match v {
                                                                     Cst(x) => tmp.push(x.clone()),
                                                                     Var(_) => {}
@@ -240,7 +295,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                                                         tmp.insert(d.clone());
                                                         stash.insert(tp, tmp);
                                                         let mut tmp = Vec::with_capacity(vec.len());
-                                                        for v in vec {
+                                                        for v in &vec {
                                                             match v {
                                                                 Cst(x) => tmp.push(x.clone()),
                                                                 Var(_) => {}
@@ -299,10 +354,10 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                             }
                             let tp = time.time().clone();
                             data.iter().for_each(|d| {
-                                if d == "<eos>" {
+                                if d.is_eos() {
                                     output.session(&time).give(MetaData(false, false));
-                                } else {
-                                    let result = &compiled_query.run(&d).unwrap();
+                                } else if let Some(json) = d.json() {
+                                    let result = &compiled_query.run(json).unwrap();
                                     if result.is_empty() {
                                         return;
                                     }
@@ -552,7 +607,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                     let_attrs_map,
                 );
                 //let attrs = get_attributes(*e1, let_attrs_map);
-                if interval.get_start() == Some(0) && interval.is_infinite() {
+                if interval.contains(0) && interval.is_infinite() {
                     stream
                         .distribute(&mut 0, true, &at, &vec![])
                         .1
@@ -576,7 +631,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                     let_stream_map,
                     let_attrs_map,
                 );
-                if interval.get_start() == Some(0) && interval.is_infinite() {
+                if interval.contains(0) && interval.is_infinite() {
                     (
                         at.clone(),
                         stream
@@ -604,7 +659,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                     let_stream_map,
                     let_attrs_map,
                 );
-                if interval.get_start() == Some(0) && interval.is_infinite() {
+                if interval.contains(0) && interval.is_infinite() {
                     (
                         at.clone(),
                         stream
@@ -632,7 +687,7 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                     let_stream_map,
                     let_attrs_map,
                 );
-                if interval.get_start() == Some(0) && interval.is_infinite() {
+                if interval.contains(0) && interval.is_infinite() {
                     (
                         at.clone(),
                         stream
@@ -658,6 +713,40 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
                     )
                 }
             }
+            Expr::Historically(e1, interval) => {
+                let (at, stream) = self.create_stream_from_evaluation_plan(
+                    visitor,
+                    *e1.clone(),
+                    dedup,
+                    let_stream_map,
+                    let_attrs_map,
+                );
+                (
+                    at.clone(),
+                    stream
+                        .distribute(&mut 0, false, &vec![], &at)
+                        .1
+                        .historically(visitor, &self.time_stream.broadcast(), &at, interval)
+                        .1,
+                )
+            }
+            Expr::Always(e1, interval) => {
+                let (at, stream) = self.create_stream_from_evaluation_plan(
+                    visitor,
+                    *e1.clone(),
+                    dedup,
+                    let_stream_map,
+                    let_attrs_map,
+                );
+                (
+                    at.clone(),
+                    stream
+                        .distribute(&mut 0, false, &vec![], &at)
+                        .1
+                        .always(visitor, &self.time_stream.broadcast(), &at, interval)
+                        .1,
+                )
+            }
             Expr::Since(e1, e2, interval) => {
                 let (at, lhs) = self.create_stream_from_evaluation_plan(
                     visitor,
@@ -844,6 +933,49 @@ impl<'a, G: Scope<Timestamp = usize>> DataflowConstructor<G> {
         out
     }
 
+    // Unbounded future operators at the root of the plan can only be decided at the end of the
+    // stream; report their pending valuations as unknown instead of violated.
+    fn add_unknown_verdicts(
+        &self,
+        plan: Expr,
+        attrs: Vec<String>,
+        stream: MonitorStream<G>,
+    ) -> (Vec<String>, MonitorStream<G>) {
+        let (rhs, lhs, condition, interval) = match plan.clone() {
+            Expr::Eventually(e1, interval) if interval.is_infinite() => {
+                (*e1, None, PendingCondition::Eventually, interval)
+            }
+            Expr::Until(e1, e2, interval) | Expr::NegUntil(e1, e2, interval)
+                if interval.is_infinite() =>
+            {
+                let (lhs_attrs, _) = self.get_stream(*e1.clone());
+                let indices = lhs_attrs
+                    .iter()
+                    .map(|attr| attrs.iter().position(|a| a == attr).unwrap())
+                    .collect();
+                let condition = if let Expr::Until(..) = plan {
+                    PendingCondition::Until(indices)
+                } else {
+                    PendingCondition::NegUntil(indices)
+                };
+                (*e2, Some(*e1), condition, interval)
+            }
+            _ => return (attrs, stream),
+        };
+
+        let (_, rhs_stream) = self.get_stream(rhs);
+        let lhs_stream = lhs.map(|lhs| self.get_stream(lhs).1);
+        stream.unknown_verdicts(
+            &mut 0,
+            &self.time_stream,
+            &rhs_stream,
+            lhs_stream.as_ref(),
+            &attrs,
+            condition,
+            interval,
+        )
+    }
+
     fn stream_exists(&self, plan: Expr) -> bool {
         self.stream_map.contains_key(&plan)
     }
@@ -1080,7 +1212,9 @@ fn get_attributes(
         | Expr::Next(expr, _)
         | Expr::Prev(expr, _)
         | Expr::Once(expr, _)
-        | Expr::Eventually(expr, _) => return get_attributes(*expr, let_attrs_map),
+        | Expr::Eventually(expr, _)
+        | Expr::Historically(expr, _)
+        | Expr::Always(expr, _) => return get_attributes(*expr, let_attrs_map),
         Expr::Since(_, expr, _) | Expr::Until(_, expr, _) => {
             let rhs_attrs = get_attributes(*expr, let_attrs_map);
             rhs_attrs
@@ -1126,7 +1260,8 @@ mod tests {
     use timely::dataflow::operators::Input;
     use timely::dataflow::operators::{Probe, UnorderedInput};
 
-    use dataflow_constructor::types::default_options;
+    use dataflow_constructor::types::{default_options, VerdictLimit};
+    use dataflow_constructor::types::FlowValues::Unknown;
     use dataflow_constructor::types::TimeFlowValues::{Timestamp, EOS};
     use TS;
 
@@ -1971,10 +2106,20 @@ mod tests {
     }
 
     fn test_dataflow_unordered(
+        policy: String,
+        datas: Vec<Vec<&str>>,
+        times: Vec<(usize, usize)>,
+        expected: Vec<(usize, Vec<Record>)>,
+    ) {
+        test_dataflow_unordered_with_options(policy, datas, times, expected, default_options());
+    }
+
+    fn test_dataflow_unordered_with_options(
         policy: String,
         datas: Vec<Vec<&str>>,
         times: Vec<(usize, usize)>,
         mut expected: Vec<(usize, Vec<Record>)>,
+        options: OperatorOptions,
     ) {
         let (send, recv) = std::sync::mpsc::channel();
         let send = std::sync::Arc::new(std::sync::Mutex::new(send));
@@ -1993,7 +2138,7 @@ mod tests {
                     let ((time_input, time_cap), time_stream) =
                         scope.new_unordered_input::<TimeFlowValues>();
                     let ((input, input_cap), stream) = scope.new_unordered_input::<String>();
-                    let options = default_options();
+                    let options = options.clone();
                     let (_attrs, output) =
                         create_dataflow(parse_formula(&policy), stream, time_stream, options);
 
@@ -2043,6 +2188,24 @@ mod tests {
         assert!(set_equality(&mut expected, &mut actual_res));
     }
 
+    #[test]
+    fn optimized_plan() {
+        // the optimizer drops the join with TRUE, so only the stream of the fact is created
+        let data = vec![vec!["p(4)"], vec!["p(5)"], vec!["p(6)"]];
+
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let policy = "p(x) AND TRUE".to_string();
+
+        let expected = vec![
+            (0, vec![Data(true, vec![(Int(4))])]),
+            (1, vec![Data(true, vec![(Int(5))])]),
+            (2, vec![Data(true, vec![(Int(6))])]),
+        ];
+
+        test_dataflow(policy, data, times, expected);
+    }
+
     #[test]
     fn fact() {
         let data = vec![vec!["p(4)"], vec!["p(5)"], vec!["p(6)"]];
@@ -2285,6 +2448,264 @@ mod tests {
         test_dataflow_unordered(policy, data, times, expected);
     }
 
+    #[test]
+    fn historically_test() {
+        let data = vec![
+            vec!["q(1)"],
+            vec!["q(1)", "q(2)"],
+            vec!["q(1)", "q(2)"],
+            vec!["q(2)"],
+        ];
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let policy = "PAST_ALWAYS [0, 1] q(x)".to_string();
+
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1)])]),
+            (1, vec![Data(true, vec![Int(1)])]),
+            (2, vec![Data(true, vec![Int(1)]), Data(true, vec![Int(2)])]),
+            (3, vec![Data(true, vec![Int(2)])]),
+        ];
+
+        test_dataflow_unordered(policy, data, times, expected);
+    }
+
+    #[test]
+    fn always_test() {
+        let data = vec![
+            vec!["q(1)"],
+            vec!["q(1)", "q(2)"],
+            vec!["q(1)", "q(2)"],
+            vec!["q(2)"],
+        ];
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let policy = "ALWAYS [0, 1] q(x)".to_string();
+
+        // the window of the last tp is cut off at the end of the stream
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1)])]),
+            (1, vec![Data(true, vec![Int(1)]), Data(true, vec![Int(2)])]),
+            (2, vec![Data(true, vec![Int(2)])]),
+            (3, vec![Data(true, vec![Int(2)])]),
+        ];
+
+        test_dataflow_unordered(policy, data, times, expected);
+    }
+
+    #[test]
+    fn eventually_unknown_at_eos() {
+        let data = vec![vec!["q(1)"], vec!["p(1)"], vec!["q(2)"], vec!["p(1)"]];
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let policy = "EVENTUALLY [0, *) q(x)".to_string();
+
+        // finite trace semantics: no verdict for the pending valuations
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1)]), Data(true, vec![Int(2)])]),
+            (1, vec![Data(true, vec![Int(2)])]),
+            (2, vec![Data(true, vec![Int(2)])]),
+        ];
+        test_dataflow_unordered(policy.clone(), data.clone(), times.clone(), expected);
+
+        let mut options = default_options();
+        options.set_unknown_at_eos(true);
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1)]), Data(true, vec![Int(2)])]),
+            (1, vec![Data(true, vec![Int(2)]), Unknown(vec![Int(1)])]),
+            (2, vec![Data(true, vec![Int(2)]), Unknown(vec![Int(1)])]),
+            (3, vec![Unknown(vec![Int(1)]), Unknown(vec![Int(2)])]),
+        ];
+        test_dataflow_unordered_with_options(policy, data, times, expected, options);
+    }
+
+    #[test]
+    fn until_unknown_at_eos() {
+        let data = vec![vec!["p()"], vec!["p()", "q()"], vec![], vec!["p()"]];
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let policy = "p() UNTIL [0, *) q()".to_string();
+
+        // tp 2 is violated as p() does not hold, tp 3 can still be satisfied
+        let mut options = default_options();
+        options.set_unknown_at_eos(true);
+        let expected = vec![
+            (0, vec![Data(true, vec![])]),
+            (1, vec![Data(true, vec![])]),
+            (3, vec![Unknown(vec![])]),
+        ];
+        test_dataflow_unordered_with_options(policy, data, times, expected, options);
+    }
+
+    #[test]
+    fn unknown_verdicts_supported_policies() {
+        let supported = vec![
+            "EVENTUALLY [0, *) q(x)",
+            "p(x) UNTIL [2, *) q(x)",
+            "EVENTUALLY [0, *) (q(x) AND ONCE [0, 5] p(x))",
+            "EVENTUALLY [0, 3] q(x)",
+        ];
+        for policy in supported {
+            assert_eq!(unknown_verdicts_error(&parse_formula(policy)), None, "{}", policy);
+        }
+
+        let unsupported = vec![
+            "p(x) AND EVENTUALLY [0, *) q(x)",
+            "EVENTUALLY [0, *) EVENTUALLY [1, *) q(x)",
+            "ALWAYS [0, *) q(x)",
+        ];
+        for policy in unsupported {
+            assert!(unknown_verdicts_error(&parse_formula(policy)).is_some(), "{}", policy);
+        }
+    }
+
+    #[test]
+    fn verdict_output_mode() {
+        let data = vec![vec!["p()"], vec![], vec![], vec!["p()"]];
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut options = default_options();
+        options.set_output_mode(3);
+        options.set_output_file(Some(file.path().to_str().unwrap().to_string()));
+
+        test_dataflow_unordered_with_options(
+            "ONCE [0, 1] p()".to_string(),
+            data,
+            times,
+            vec![],
+            options,
+        );
+
+        let verdicts = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(
+            verdicts,
+            "@0 (time point 0): true\n@1 (time point 1): true\n@2 (time point 2): false\n@3 (time point 3): true\n"
+        );
+    }
+
+    #[test]
+    fn ordered_output_mode() {
+        let data = vec![
+            vec!["A(3, 1)", "A(1, 2)"],
+            vec!["A(2, 2)"],
+            vec![],
+            vec!["A(1, 1)"],
+        ];
+        let times = vec![(0, 0), (1, 1), (2, 5), (3, 6)];
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut options = default_options();
+        options.set_output_mode(4);
+        options.set_output_file(Some(file.path().to_str().unwrap().to_string()));
+
+        test_dataflow_unordered_with_options(
+            "ONCE [0, 4] A(a,b)".to_string(),
+            data,
+            times,
+            vec![],
+            options,
+        );
+
+        let verdicts = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(
+            verdicts,
+            "@0 (time point 0): (Int(1),Int(2))\n@0 (time point 0): (Int(3),Int(1))\n\
+             @1 (time point 1): (Int(1),Int(2))\n@1 (time point 1): (Int(2),Int(2))\n@1 (time point 1): (Int(3),Int(1))\n\
+             @5 (time point 2): (Int(2),Int(2))\n@6 (time point 3): (Int(1),Int(1))\n"
+        );
+    }
+
+    #[test]
+    fn verdict_limit_test() {
+        let data = vec![
+            vec!["A(3, 1)", "A(1, 2)"],
+            vec!["A(2, 2)"],
+            vec![],
+            vec!["A(1, 1)"],
+        ];
+        let times = vec![(0, 0), (1, 1), (2, 5), (3, 6)];
+
+        let limit = VerdictLimit::new(4);
+        let mut options = default_options();
+        options.set_verdict_limit(Some(limit.clone()));
+
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1), Int(2)]), Data(true, vec![Int(3), Int(1)])]),
+            (
+                1,
+                vec![
+                    Data(true, vec![Int(1), Int(2)]),
+                    Data(true, vec![Int(2), Int(2)]),
+                    Data(true, vec![Int(3), Int(1)]),
+                ],
+            ),
+            (2, vec![Data(true, vec![Int(2), Int(2)])]),
+            (3, vec![Data(true, vec![Int(1), Int(1)])]),
+        ];
+
+        test_dataflow_unordered_with_options(
+            "ONCE [0, 4] A(a,b)".to_string(),
+            data.clone(),
+            times.clone(),
+            expected,
+            options,
+        );
+
+        assert!(limit.reached());
+        assert_eq!(
+            limit.get_violations(),
+            vec![
+                (0, 0, vec![Int(1), Int(2)]),
+                (0, 0, vec![Int(3), Int(1)]),
+                (1, 1, vec![Int(1), Int(2)]),
+                (1, 1, vec![Int(2), Int(2)]),
+            ]
+        );
+
+        let limit = VerdictLimit::new(1);
+        let mut options = default_options();
+        options.set_verdict_limit(Some(limit.clone()));
+
+        test_dataflow_unordered_with_options("B(a)".to_string(), data, times, vec![], options);
+
+        assert!(!limit.reached());
+        assert!(limit.get_violations().is_empty());
+    }
+
+    #[test]
+    fn simple_mode_base_stream_test() {
+        // distinct variables only, so the base stream takes the arguments of the event as they are
+        let data = vec![vec!["q(1, 2)", "p(3, 4)", "q(5, 6)"], vec!["q(1, 2)"]];
+        let times = vec![(0, 0), (1, 1)];
+
+        let policy = "q(x, y)".to_string();
+
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1), Int(2)]), Data(true, vec![Int(5), Int(6)])]),
+            (1, vec![Data(true, vec![Int(1), Int(2)])]),
+        ];
+
+        test_dataflow_unordered(policy, data, times, expected);
+    }
+
+    #[test]
+    fn simple_mode_empty_window_test() {
+        // events of other predicates do not satisfy the atom, so the window at tp 2 is empty
+        let data = vec![vec!["q(1)"], vec!["p(7)"], vec![], vec!["q(2)"]];
+        let times = vec![(0, 0), (1, 1), (2, 2), (3, 3)];
+
+        let policy = "ONCE[0, 1] q(x)".to_string();
+
+        let expected = vec![
+            (0, vec![Data(true, vec![Int(1)])]),
+            (1, vec![Data(true, vec![Int(1)])]),
+            (3, vec![Data(true, vec![Int(2)])]),
+        ];
+
+        test_dataflow_unordered(policy, data, times, expected);
+    }
+
     #[test]
     fn benchmark_test() {
         // EOS is missing
@@ -2564,7 +2985,7 @@ mod tests {
             let mut tmp_vec = Vec::new();
             data.iter().for_each(|rec| match rec {
                 Data(_, rec) => tmp_vec.push(rec.clone()),
-                MetaData(_, _) => {}
+                MetaData(_, _) | Unknown(_) => {}
             });
 
             btree_res.insert(tp.clone(), tmp_vec);
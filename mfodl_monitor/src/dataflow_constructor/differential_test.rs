@@ -0,0 +1,249 @@
+#[cfg(test)]
+mod test {
+    use rand::{Rng, SeedableRng, StdRng};
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use end_to_end::{monitor, Verdicts};
+    use parser::csv_parser::Segment;
+    use parser::formula_syntax_tree::{free_variables, is_var, Formula};
+    use parser::monpoly::to_native_policy;
+    use reference_evaluator::random_case;
+    use parse_formula;
+
+    const WORKERS: [usize; 3] = [1, 2, 4];
+    // time points per epoch of the reversed feeding
+    const EPOCH: usize = 3;
+    // largest delay of an event in time points
+    const MAX_DELAY: usize = 3;
+    // a run that takes longer is stuck, for example after a worker panicked
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    type Trace = Vec<(usize, Vec<String>)>;
+
+    #[derive(Clone, Copy, Debug)]
+    enum Feeding {
+        InOrder,
+        ReversedWithWatermarks,
+        Delayed,
+    }
+
+    // Segments in the order of the feeding, a watermark follows all events of earlier time points
+    fn schedule(trace: &Trace, feeding: Feeding, seed: usize) -> Vec<Segment> {
+        let mut steps = Vec::new();
+        match feeding {
+            Feeding::InOrder => {
+                for (tp, (ts, events)) in trace.iter().enumerate() {
+                    steps.push(Segment::Seg(tp, *ts, events.clone()));
+                    steps.push(Segment::Epoch(tp as i64 + 1));
+                }
+            }
+            Feeding::ReversedWithWatermarks => {
+                let tps: Vec<usize> = (0..trace.len()).collect();
+                for epoch in tps.chunks(EPOCH) {
+                    for &tp in epoch.iter().rev() {
+                        steps.push(Segment::Seg(tp, trace[tp].0, trace[tp].1.clone()));
+                    }
+                    steps.push(Segment::Epoch(epoch[epoch.len() - 1] as i64 + 1));
+                }
+            }
+            Feeding::Delayed => {
+                // every event and the timestamp of every time point arrive up to MAX_DELAY time
+                // points late, the events of a time point that arrive together are sent at once
+                let mut rng = StdRng::from_seed(&[seed]);
+                let mut arrivals: Vec<BTreeMap<usize, Vec<String>>> = vec![BTreeMap::new(); trace.len() + MAX_DELAY];
+                for (tp, (_, events)) in trace.iter().enumerate() {
+                    arrivals[tp + rng.gen_range(0, MAX_DELAY + 1)].entry(tp).or_default();
+                    for event in events {
+                        arrivals[tp + rng.gen_range(0, MAX_DELAY + 1)].entry(tp).or_default().push(event.clone());
+                    }
+                }
+                for (slot, arrived) in arrivals.into_iter().enumerate() {
+                    let mut arrived: Vec<(usize, Vec<String>)> = arrived.into_iter().collect();
+                    rng.shuffle(&mut arrived);
+                    for (tp, events) in arrived {
+                        steps.push(Segment::Seg(tp, trace[tp].0, events));
+                    }
+                    if slot >= MAX_DELAY {
+                        steps.push(Segment::Epoch((slot - MAX_DELAY + 1).min(trace.len()) as i64));
+                    }
+                }
+            }
+        }
+        steps
+    }
+
+    // Runs the dataflow with the given number of workers and feeding and returns its verdicts or
+    // why it failed
+    fn run(policy: &str, trace: &Trace, workers: usize, feeding: Feeding, seed: usize) -> Result<Verdicts, String> {
+        monitor(policy, schedule(trace, feeding, seed), trace.len(), workers, TIMEOUT).map(|(_, verdicts)| verdicts)
+    }
+
+    // Describes the first run whose verdicts differ from those of one worker fed in order
+    fn difference(policy: &Formula, trace: &Trace, seed: usize) -> Option<String> {
+        let policy = to_native_policy(policy);
+        let mut expected = None;
+        for &workers in WORKERS.iter() {
+            for &feeding in [Feeding::InOrder, Feeding::ReversedWithWatermarks, Feeding::Delayed].iter() {
+                let actual = match run(&policy, trace, workers, feeding, seed) {
+                    Ok(actual) => actual,
+                    Err(e) => return Some(format!("{} workers with {:?} feeding: {}", workers, feeding, e)),
+                };
+                match expected {
+                    None => expected = Some(actual),
+                    Some(ref expected) if actual != *expected => {
+                        return Some(format!("{} workers with {:?} feeding: {:?} instead of {:?}", workers, feeding, actual, expected));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        None
+    }
+
+    // Subformulas that are policies on their own, outermost first
+    fn subformulas(f: &Formula, result: &mut Vec<Formula>) {
+        match f {
+            Formula::Not(g) | Formula::Exists(_, g) | Formula::Once(g, _) | Formula::Eventually(g, _)
+            | Formula::Historically(g, _) | Formula::Always(g, _) | Formula::Prev(g, _) | Formula::Next(g, _) => {
+                children(result, &[g]);
+            }
+            Formula::Conj(l, r) | Formula::Disj(l, r) | Formula::AntiConj(l, r) | Formula::Since(l, r, _)
+            | Formula::Until(l, r, _) | Formula::NegSince(l, r, _) | Formula::NegUntil(l, r, _) => {
+                children(result, &[l, r]);
+            }
+            _ => {}
+        }
+    }
+
+    fn children(result: &mut Vec<Formula>, children: &[&Formula]) {
+        for &child in children {
+            let open_negation = matches!(child, Formula::Not(_)) && free_variables(child.clone()).iter().any(is_var);
+            if !matches!(child, Formula::Equals(_, _)) && !open_negation {
+                result.push(child.clone());
+            }
+        }
+        for &child in children {
+            subformulas(child, result);
+        }
+    }
+
+    // Smaller cases: subformulas of the policy, then the trace without a time point or an event
+    fn smaller_cases(policy: &Formula, trace: &Trace) -> Vec<(Formula, Trace)> {
+        let mut policies = Vec::new();
+        subformulas(policy, &mut policies);
+        let mut cases: Vec<(Formula, Trace)> = policies.into_iter().map(|p| (p, trace.clone())).collect();
+        for tp in 0..trace.len() {
+            let mut smaller = trace.clone();
+            smaller.remove(tp);
+            cases.push((policy.clone(), smaller));
+        }
+        for tp in 0..trace.len() {
+            for i in 0..trace[tp].1.len() {
+                let mut smaller = trace.clone();
+                smaller[tp].1.remove(i);
+                cases.push((policy.clone(), smaller));
+            }
+        }
+        cases
+    }
+
+    // Shrinks a differing case until no smaller case differs
+    fn shrink<F>(mut policy: Formula, mut trace: Trace, mut diff: String, difference: F) -> (Formula, Trace, String)
+        where F: Fn(&Formula, &Trace) -> Option<String> {
+        while let Some((p, t, d)) = smaller_cases(&policy, &trace).into_iter()
+            .find_map(|(p, t)| difference(&p, &t).map(|d| (p, t, d))) {
+            policy = p;
+            trace = t;
+            diff = d;
+        }
+        (policy, trace, diff)
+    }
+
+    #[test]
+    fn schedules_send_every_event_before_its_watermark() {
+        let (_, _, trace) = random_case(3);
+        for &feeding in [Feeding::InOrder, Feeding::ReversedWithWatermarks, Feeding::Delayed].iter() {
+            let mut sent: Trace = trace.iter().map(|(ts, _)| (*ts, vec![])).collect();
+            let mut watermark = 0;
+            for step in schedule(&trace, feeding, 3) {
+                match step {
+                    Segment::Seg(tp, ts, events) => {
+                        assert!(tp >= watermark, "{:?} sends {} after watermark {}", feeding, tp, watermark);
+                        assert_eq!(ts, trace[tp].0);
+                        sent[tp].1.extend(events);
+                    }
+                    Segment::Epoch(wm) => {
+                        let tp = wm as usize;
+                        assert!(tp >= watermark);
+                        watermark = tp;
+                    }
+                }
+            }
+            assert_eq!(watermark, trace.len());
+            for (tp, (_, events)) in sent.iter_mut().enumerate() {
+                events.sort();
+                let mut expected = trace[tp].1.clone();
+                expected.sort();
+                assert_eq!(*events, expected, "{:?}", feeding);
+            }
+        }
+    }
+
+    #[test]
+    fn shrinking_keeps_the_difference() {
+        // a stand-in for a differing case: any case where P0 has an event
+        let policy = parse_formula("(ONCE[0,2] P0(x0)) AND P1(x0)");
+        let trace: Trace = vec![(0, vec!["P0(1)".to_string(), "P1(2)".to_string()]), (1, vec!["P0(2)".to_string()])];
+        let difference = |p: &Formula, t: &Trace| {
+            let events = t.iter().map(|(_, events)| events.len()).sum::<usize>();
+            if to_native_policy(p).contains("P0") && events > 0 { Some(format!("{} events", events)) } else { None }
+        };
+
+        let (policy, trace, diff) = shrink(policy, trace, "3 events".to_string(), difference);
+        assert_eq!(policy, parse_formula("P0(x0)"));
+        assert_eq!(diff, "1 events");
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].1.len(), 1);
+    }
+
+    #[test]
+    fn verdicts_agree_across_workers_and_feedings() {
+        for seed in 0..30 {
+            let (policy, _, trace) = random_case(seed);
+            if let Some(diff) = difference(&policy, &trace, seed) {
+                let (policy, trace, diff) = shrink(policy, trace, diff, |p, t| difference(p, t, seed));
+                panic!("policy {} on {:?} differs with {}", to_native_policy(&policy), trace, diff);
+            }
+        }
+    }
+
+    #[test]
+    fn until_with_delayed_events() {
+        // shrunk from a run that panicked when until released a time point too early and then
+        // derived a verdict for it
+        let policy = parse_formula("P1(y0) UNTIL(1,2] P1(y0)");
+        let trace = trace_of(vec![(1, vec!["P1(2)"]), (3, vec!["P1(2)", "P0()"]), (7, vec!["P1(1)", "P0()", "P1(2)"])]);
+        for seed in 0..30 {
+            assert_eq!(difference(&policy, &trace, seed), None, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn once_with_window_starting_before_the_first_time_point() {
+        // shrunk from a run where search_lowest only found a time point whose timestamp was the
+        // lower end of the window, not a later one inside it
+        let policy = parse_formula("ONCE[2,5) (EXISTS y0. (P0(y0) AND NOT P1()))");
+        let trace = trace_of(vec![(3, vec!["P0(1)"]), (4, vec![]), (6, vec![])]);
+        for seed in 0..30 {
+            assert_eq!(difference(&policy, &trace, seed), None, "seed {}", seed);
+        }
+    }
+
+    fn trace_of(time_points: Vec<(usize, Vec<&str>)>) -> Trace {
+        time_points
+            .into_iter()
+            .map(|(ts, events)| (ts, events.into_iter().map(|e| e.to_string()).collect()))
+            .collect()
+    }
+}
@@ -3,4 +3,5 @@ pub mod operators;
 pub mod types;
 mod partial_sequence;
 mod operator_test;
+mod differential_test;
 mod observation_sequence;
\ No newline at end of file
@@ -4,6 +4,7 @@ use std::cmp::min;
 use std::collections::HashSet;
 use dataflow_constructor::observation_sequence::InfinityIntervals::{InfInterval, Literal, Interval};
 use dataflow_constructor::observation_sequence::InfinityIntervalsReturn::Empty;
+use timeunits::TimeInterval;
 
 fn compare(interval : &InfinityIntervals, time_point : usize) -> i8 {
     match interval {
@@ -137,8 +138,9 @@ fn search_lowest(tmp : &Vec<InfinityIntervals>, k: usize, lower_ts : usize, uppe
     } else {
         if exact {
             match tmp[0] {
-                Literal(_, _) => {
-                    if compare_ts(&tmp[0], lower_ts) == 0 { Some(0) }else { None }
+                // the first literal is the lowest one in the range if its ts is, also when the range starts earlier
+                Literal(_, ts) => {
+                    if ts >= lower_ts && ts <= upper_ts { Some(0) } else { None }
                 }
                 // if not inf interval than interval is bounded by literal to the right; such that upper bound is the same as the literal
                 Interval(_, _, _, b) => {
@@ -147,8 +149,8 @@ fn search_lowest(tmp : &Vec<InfinityIntervals>, k: usize, lower_ts : usize, uppe
             }
         } else {
             match tmp[0] {
-                Literal(_, _) => {
-                    if compare_ts(&tmp[0], lower_ts) == 0 { Some(0) }else { None }
+                Literal(_, ts) => {
+                    if ts >= lower_ts && ts <= upper_ts { Some(0) } else { None }
                 }
                 _ => None
             }
@@ -302,6 +304,8 @@ pub trait TimeSeq {
     fn associated_interval_tp(&mut self, time_point : usize) -> InfinityIntervalsReturn;
     fn associated_interval_ts_exact(&mut self, lower_ts : usize, upper_ts : usize) -> InfinityIntervalsReturn;
     fn associated_interval_ts_inexact(&mut self, lower_ts: usize, upper_ts: usize) -> InfinityIntervalsReturn;
+    fn associated_window_exact(&mut self, window: TimeInterval) -> InfinityIntervalsReturn;
+    fn associated_window_inexact(&mut self, window: TimeInterval) -> InfinityIntervalsReturn;
     fn associated_lower_bound_ts(&mut self, ts: usize) -> InfinityIntervalsReturn;
     fn associated_upper_bound_ts(&mut self, ts: usize) -> InfinityIntervalsReturn;
     fn zero_ts(&mut self) -> Option<usize>;
@@ -608,6 +612,21 @@ impl TimeSeq for ObservationSequence {
         }
     }
 
+    // The time points of a window of timestamps, open bounds exclude the timestamp they name
+    fn associated_window_exact(&mut self, window: TimeInterval) -> InfinityIntervalsReturn {
+        match window.last_ts() {
+            Some(last_ts) => self.associated_interval_ts_exact(window.first_ts(), last_ts),
+            _ => Empty,
+        }
+    }
+
+    fn associated_window_inexact(&mut self, window: TimeInterval) -> InfinityIntervalsReturn {
+        match window.last_ts() {
+            Some(last_ts) => self.associated_interval_ts_inexact(window.first_ts(), last_ts),
+            _ => Empty,
+        }
+    }
+
     fn associated_lower_bound_ts(&mut self, ts: usize) -> InfinityIntervalsReturn {
         let opt_ind = find_index(&self.observations, ts, false);
         let ind = match opt_ind { None => 0, Some(x) => x };
@@ -806,6 +825,7 @@ mod test_ds {
     use dataflow_constructor::observation_sequence::{inside_tp, compare, find_index, binary_search, ObservationSequence, TimeSeq, InfinityIntervalsReturn};
     use dataflow_constructor::observation_sequence::InfinityIntervals::{Interval, InfInterval, Literal};
     use dataflow_constructor::observation_sequence::InfinityIntervalsReturn::Empty;
+    use timeunits::{TimeInterval, TS};
 
     extern crate itertools;
     use dataflow_constructor::observation_sequence::test_ds::itertools::Itertools;
@@ -1026,6 +1046,27 @@ mod test_ds {
         assert_eq!(Empty, obs.associated_interval_ts_exact(251, 255)); // empty
         assert_eq!(Empty, obs.associated_interval_ts_exact(201, 201)); // empty
         // fully contained in the query interval
+
+        // the range starts before the first timestamp
+        let mut obs = ObservationSequence::init();
+        obs.insert(0, 4);
+        obs.insert(1, 5);
+        obs.insert(2, 7);
+        assert_eq!(InfinityIntervalsReturn::Interval(0, 1), obs.associated_interval_ts_exact(3, 5));
+        assert_eq!(InfinityIntervalsReturn::Literal(0), obs.associated_interval_ts_exact(2, 4));
+    }
+
+    #[test]
+    fn associated_window_honours_open_bounds() {
+        let mut obs = ObservationSequence::init();
+        obs.insert(0, 4);
+        obs.insert(1, 5);
+        obs.insert(2, 7);
+        assert_eq!(InfinityIntervalsReturn::Interval(0, 2), obs.associated_window_exact(TimeInterval::new(TS::new(4), TS::new(7))));
+        assert_eq!(InfinityIntervalsReturn::Interval(1, 2), obs.associated_window_exact(TimeInterval::with_bounds(TS::new(4), true, TS::new(7), false)));
+        assert_eq!(InfinityIntervalsReturn::Interval(0, 1), obs.associated_window_exact(TimeInterval::with_bounds(TS::new(4), false, TS::new(7), true)));
+        assert_eq!(InfinityIntervalsReturn::Literal(1), obs.associated_window_inexact(TimeInterval::with_bounds(TS::new(4), true, TS::new(7), true)));
+        assert_eq!(Empty, obs.associated_window_exact(TimeInterval::with_bounds(TS::new(0), false, TS::new(0), true)));
     }
 
     #[test]
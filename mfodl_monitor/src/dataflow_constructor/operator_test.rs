@@ -17,7 +17,7 @@ mod test {
     use timely::dataflow::operators::{UnorderedInput, Probe, Capture, Input, Broadcast};
     use parser::formula_syntax_tree::Constant::{Int, Str};
     use timeunits::TimeInterval;
-    use dataflow_constructor::types::{default_options, Record as Record, TimeFlowValues};
+    use dataflow_constructor::types::{default_options, PendingCondition, Record as Record, TimeFlowValues};
     use dataflow_constructor::types::TimeFlowValues::{EOS, Timestamp};
 
 
@@ -919,6 +919,279 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_unary_historically() {
+        let data = vec![
+            vec![StreamData::D(0, vec![1]), StreamData::D(0, vec![2])],
+            vec![StreamData::D(1, vec![1])],
+            vec![StreamData::D(2, vec![1]), StreamData::D(2, vec![2])],
+            vec![StreamData::D(3, vec![2])],
+        ];
+        let times = vec![(0,0), (1,1), (2,2), (3,3)];
+        let attrs: Vec<String> = vec!["x".into()];
+
+        let cases = vec![
+            (
+                TimeInterval::new(TS::new(0), TS::new(1)),
+                vec![
+                    (0, vec![(0, vec![1]), (0, vec![2])]),
+                    (1, vec![(1, vec![1])]),
+                    (2, vec![(2, vec![1])]),
+                    (3, vec![(3, vec![2])]),
+                ],
+            ),
+            (
+                // the window of tp 0 is empty
+                TimeInterval::new(TS::new(1), TS::new(2)),
+                vec![
+                    (1, vec![(1, vec![1]), (1, vec![2])]),
+                    (2, vec![(2, vec![1])]),
+                    (3, vec![(3, vec![1])]),
+                ],
+            ),
+            (
+                TimeInterval::new(TS::new(0), TS::INFINITY),
+                vec![
+                    (0, vec![(0, vec![1]), (0, vec![2])]),
+                    (1, vec![(1, vec![1])]),
+                    (2, vec![(2, vec![1])]),
+                ],
+            ),
+        ];
+
+        for (time_interval, expected) in cases {
+            for _x in 0..NUMBER_OF_ROUNDS {
+                test_unary_once_eventually(2, data.clone(), attrs.clone(), times.clone(), expected.clone(), new_order_shuffle, time_interval.clone());
+                test_unary_once_eventually(2, data.clone(), attrs.clone(), times.clone(), expected.clone(), new_order_reverse, time_interval.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unary_always() {
+        let data = vec![
+            vec![StreamData::D(0, vec![1]), StreamData::D(0, vec![2])],
+            vec![StreamData::D(1, vec![1])],
+            vec![StreamData::D(2, vec![1]), StreamData::D(2, vec![2])],
+            vec![StreamData::D(3, vec![2])],
+        ];
+        let times = vec![(0,0), (1,1), (2,2), (3,3)];
+        let attrs: Vec<String> = vec!["x".into()];
+
+        // windows reaching past the last tp are cut off at the end of the stream
+        let cases = vec![
+            (
+                TimeInterval::new(TS::new(0), TS::new(1)),
+                vec![
+                    (0, vec![(0, vec![1])]),
+                    (1, vec![(1, vec![1])]),
+                    (2, vec![(2, vec![2])]),
+                    (3, vec![(3, vec![2])]),
+                ],
+            ),
+            (
+                TimeInterval::new(TS::new(1), TS::new(2)),
+                vec![
+                    (0, vec![(0, vec![1])]),
+                    (1, vec![(1, vec![2])]),
+                    (2, vec![(2, vec![2])]),
+                ],
+            ),
+        ];
+
+        for (time_interval, expected) in cases {
+            for _x in 0..NUMBER_OF_ROUNDS {
+                test_unary_once_eventually(3, data.clone(), attrs.clone(), times.clone(), expected.clone(), new_order_shuffle, time_interval.clone());
+                test_unary_once_eventually(3, data.clone(), attrs.clone(), times.clone(), expected.clone(), new_order_reverse, time_interval.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_verdicts() {
+        let mut out = Vec::new();
+        write_verdicts(&mut out, 0, 10, true, vec![Record::Data(true, vec![]), Record::Data(true, vec![])]).unwrap();
+        write_verdicts(&mut out, 1, 12, true, vec![]).unwrap();
+        write_verdicts(&mut out, 2, 15, true, vec![Record::Unknown(vec![])]).unwrap();
+        write_verdicts(&mut out, 3, 16, false, vec![Record::Unknown(vec![Int(2)]), Record::Data(true, vec![Int(3)]), Record::Data(true, vec![Int(1)])]).unwrap();
+        write_verdicts(&mut out, 4, 17, false, vec![]).unwrap();
+
+        let expected = "@10 (time point 0): true\n\
+                        @12 (time point 1): false\n\
+                        @15 (time point 2): unknown\n\
+                        @16 (time point 3): true (Int(1))\n\
+                        @16 (time point 3): true (Int(3))\n\
+                        @16 (time point 3): unknown (Int(2))\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn once_with_incrementally_completed_time_points() {
+        let (send, recv) = std::sync::mpsc::channel();
+        let send = std::sync::Arc::new(std::sync::Mutex::new(send));
+        timely::execute(timely::Config::process(NUM_WORKERS), move |worker| {
+            let send = send.lock().unwrap().clone();
+            let attrs: Vec<String> = vec!["x".into()];
+
+            let ((mut input, mut cap), (mut time_input, mut cap_time), probe) = worker
+                .dataflow::<usize, _, _>(|scope| {
+                    let (time_input, time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+                    let (input, stream) = scope.new_unordered_input::<Record>();
+
+                    let (_, output) = stream
+                        .distribute(&mut 0, false, &vec![], &attrs).1
+                        .once(&mut 0, &time_stream.broadcast(), &attrs, TimeInterval::new(TS::new(0), TS::new(5)), false);
+                    let probe = output.probe();
+                    output.capture_into(send);
+                    (input, time_input, probe)
+                });
+
+            // every time point is complete before the next one arrives, as with a watermark after
+            // each time point, so the beta p(1) of time point 0 has to outlive time point 0
+            for tp in 0..4 {
+                if worker.index() == 0 {
+                    time_input.session(cap_time.delayed(&tp)).give(Timestamp(tp));
+                    if tp == 0 {
+                        input.session(cap.delayed(&tp)).give(Record::Data(true, vec![Int(1)]));
+                    }
+                }
+                cap.downgrade(&(tp + 1));
+                cap_time.downgrade(&(tp + 1));
+                let start = std::time::Instant::now();
+                while probe.less_than(&(tp + 1)) && start.elapsed() < std::time::Duration::from_secs(10) {
+                    worker.step();
+                }
+            }
+
+            input.session(cap.delayed(&4)).give(Record::MetaData(false, false));
+            if worker.index() == 0 {
+                time_input.session(cap_time.delayed(&4)).give(EOS);
+            }
+        }).unwrap();
+
+        let mut actual: Vec<(usize, Vec<Record>)> = recv.extract().into_iter()
+            .map(|(tp, recs)| (tp, recs.into_iter().filter(|rec| matches!(rec, Record::Data(true, _))).collect::<Vec<_>>()))
+            .filter(|(_, recs)| !recs.is_empty())
+            .collect();
+        actual.iter_mut().for_each(|(_, recs)| recs.dedup());
+        let expected: Vec<(usize, Vec<Record>)> = (0..4).map(|tp| (tp, vec![Record::Data(true, vec![Int(1)])])).collect();
+        assert_eq!(actual, expected);
+    }
+
+    // collects what the workers write
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn batched_output_writes_unknown_verdicts() {
+        let buffer = SharedBuffer::default();
+        let shared = buffer.clone();
+        timely::execute(timely::Config::process(NUM_WORKERS), move |worker| {
+            let writer = shared.clone();
+            let ((mut input, cap), (mut time_input, cap_time)) = worker
+                .dataflow::<usize, _, _>(|scope| {
+                    let (time_input, time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+                    let (input, stream) = scope.new_unordered_input::<Record>();
+                    batched_output(&stream, &time_stream, 10, Box::new(writer));
+                    (input, time_input)
+                });
+
+            if worker.index() == 0 {
+                // the unknown verdict of time point 1 arrives before its timestamp
+                input.session(cap.delayed(&1)).give(Record::Unknown(vec![Int(2)]));
+                for _ in 0..10 {
+                    worker.step();
+                }
+                time_input.session(cap_time.delayed(&0)).give(Timestamp(0));
+                time_input.session(cap_time.delayed(&1)).give(Timestamp(7));
+                input.session(cap.delayed(&0)).give(Record::Data(true, vec![Int(1)]));
+                input.session(cap.delayed(&0)).give(Record::Unknown(vec![Int(3)]));
+            }
+            drop((cap, cap_time));
+        }).unwrap();
+
+        // fewer verdicts than the batch size are written once the input ends
+        let out = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let mut lines: Vec<&str> = out.lines().filter(|line| !line.is_empty()).collect();
+        lines.sort();
+        assert_eq!(lines, vec![
+            "@0 (time point 0): (Int(1))",
+            "@0 (time point 0): unknown (Int(3))",
+            "@7 (time point 1): unknown (Int(2))",
+        ]);
+    }
+
+    #[test]
+    fn unknown_verdicts_release_settled_time_points() {
+        let (send, recv) = std::sync::mpsc::channel();
+        let send = std::sync::Arc::new(std::sync::Mutex::new(send));
+        let (released_send, released_recv) = std::sync::mpsc::channel();
+        let released_send = std::sync::Arc::new(std::sync::Mutex::new(released_send));
+        timely::execute(timely::Config::process(NUM_WORKERS), move |worker| {
+            let send = send.lock().unwrap().clone();
+
+            let ((mut input, cap), (mut time_input, cap_time), probe) = worker
+                .dataflow::<usize, _, _>(|scope| {
+                    let (time_input, time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+                    let (input, stream) = scope.new_unordered_input::<Record>();
+
+                    // closed EVENTUALLY[0,*) whose output is only satisfied at time point 0
+                    let (_, output) = stream.unknown_verdicts(
+                        &mut 0,
+                        &time_stream,
+                        &stream,
+                        None,
+                        &vec![],
+                        PendingCondition::Eventually,
+                        TimeInterval::new(TS::new(0), TS::INFINITY),
+                    );
+                    let probe = output.probe();
+                    output.capture_into(send);
+                    (input, time_input, probe)
+                });
+
+            if worker.index() == 0 {
+                for tp in 0..3 {
+                    time_input.session(cap_time.delayed(&tp)).give(Timestamp(tp));
+                }
+                input.session(cap.delayed(&0)).give(Record::Data(true, vec![]));
+            }
+            let (mut cap, mut cap_time) = (cap, cap_time);
+            cap.downgrade(&1);
+            cap_time.downgrade(&1);
+
+            // time point 0 is settled before the end of the stream, 1 and 2 are still pending
+            let start = std::time::Instant::now();
+            while probe.less_than(&1) && start.elapsed() < std::time::Duration::from_secs(10) {
+                worker.step();
+            }
+            released_send.lock().unwrap().send(!probe.less_than(&1) && probe.less_than(&2)).unwrap();
+
+            if worker.index() == 0 {
+                time_input.session(cap_time.delayed(&3)).give(EOS);
+            }
+        }).unwrap();
+
+        assert!(released_recv.iter().all(|released| released));
+
+        let expected = vec![
+            (0, vec![Record::Data(true, vec![])]),
+            (1, vec![Record::Unknown(vec![])]),
+            (2, vec![Record::Unknown(vec![])]),
+        ];
+        assert_eq!(recv.extract(), expected);
+    }
+
     #[test]
     fn next_test() {
         let data_lhs = vec![
@@ -7676,6 +7949,18 @@ mod test {
                                 .eventually(&mut 0, &time_stream.broadcast(), &lhs_attrs, time_interval, false).1
                                 .exhaust(&mut 0, time_stream.broadcast(),vec![], default_options())
                         }
+                        2 => {
+                            lhs_stream
+                                .distribute(&mut 0, false, &vec![], &lhs_attrs).1
+                                .historically(&mut 0, &time_stream.broadcast(), &lhs_attrs, time_interval).1
+                                .exhaust(&mut 0, time_stream.broadcast(),vec![], default_options())
+                        }
+                        3 => {
+                            lhs_stream
+                                .distribute(&mut 0, false, &vec![], &lhs_attrs).1
+                                .always(&mut 0, &time_stream.broadcast(), &lhs_attrs, time_interval).1
+                                .exhaust(&mut 0, time_stream.broadcast(),vec![], default_options())
+                        }
                         _ => panic!(),
                     };
 
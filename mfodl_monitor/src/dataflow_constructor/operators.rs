@@ -2,18 +2,21 @@
 #![warn(unused_imports)]
 #[warn(unused_assignments)]
 use std::cmp::{max, min};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::iter::zip;
+use std::sync::atomic::Ordering;
 
 use timely::dataflow::channels::pact::{ParallelizationContract, Pipeline};
 use timely::dataflow::channels::pact::Exchange as PactExchange;
 
-use timely::dataflow::operators::{Operator, Map, Capability, FrontierNotificator, Filter, Exchange};
+use timely::dataflow::operators::{Operator, Map, Capability, FrontierNotificator, Filter, Exchange, Concat, Broadcast};
 
 use timely::dataflow::{Scope, Stream};
 
 use constants::calculate_hash;
-use dataflow_constructor::types::{FlowValues::Data as Data, FlowValues::MetaData as MetaData, OperatorOptions, Record as Record, TimeFlowValues};
+use dataflow_constructor::types::{FlowValues::Data as Data, FlowValues::MetaData as MetaData, FlowValues::Unknown as Unknown, OperatorOptions, PendingCondition, Record as Record, TimeFlowValues, VerdictLimit};
 
 use dataflow_constructor::partial_sequence::{contains, Intervals, PartialSequence, SatisfactionDs};
 use parser::formula_syntax_tree::{Arg, Constant};
@@ -26,6 +29,7 @@ use dataflow_constructor::observation_sequence::{InfinityIntervalsReturn, Observ
 use dataflow_constructor::observation_sequence::InfinityIntervalsReturn::Literal;
 use dataflow_constructor::types::TimeFlowValues::Timestamp as FTimestamp;
 use timeunits::TimeInterval;
+use stream_io::socket_sink::SocketSink;
 
 pub trait OperatorsWithSummary<G, D1> where G: Scope, D1: timely::Data {
     fn binary_frontier_sum<D2, D3, B, L, P1, P2>(&self, other: &Stream<G, D2>, pact1: P1, pact2: P2, name: &str, summary_time: <G::Timestamp as Timestamp>::Summary, summary_data: <G::Timestamp as Timestamp>::Summary, constructor: B) -> Stream<G, D3>
@@ -151,6 +155,8 @@ pub trait SupportOperators<G: Scope<Timestamp = usize>> {
     fn distribute(&self, visitor: &mut usize, side: bool, lhs_attrs: &Vec<String>, rhs_attrs: &Vec<String>) -> (Vec<String>, Stream<G, Record>);
 
     fn exhaust(&self, visitor: &mut usize, time_stream: Stream<G, TimeFlowValues>, fv: Vec<String>, options: OperatorOptions) -> (Vec<String>, Stream<G, Record>);
+
+    fn unknown_verdicts(&self, visitor: &mut usize, time_stream: &Stream<G, TimeFlowValues>, rhs_stream: &Stream<G, Record>, lhs_stream: Option<&Stream<G, Record>>, attrs: &Vec<String>, condition: PendingCondition, interval: TimeInterval) -> (Vec<String>, Stream<G, Record>);
 }
 
 impl<G: Scope<Timestamp = usize>> SupportOperators<G> for Stream<G, Record> {
@@ -209,13 +215,14 @@ impl<G: Scope<Timestamp = usize>> SupportOperators<G> for Stream<G, Record> {
         let out_stream = match options.get_output_mode() {
             0 => {//write to file at the end
                 Filter::filter(self, move |rec| match rec {
-                    Data(true, _) => true,
+                    Data(_, _) | Unknown(_) => true,
                     _ => false
                 })
             },
             1 => {//print to stdin
-                let mut stash : Vec<(usize, Vec<Constant>)> = Vec::with_capacity(8);
+                let mut stash : Vec<(usize, Record)> = Vec::with_capacity(8);
                 let mut tp_to_ts = HashMap::with_capacity(8);
+                let mut writer = output_writer(&options);
                 self.binary(&time_stream, Pipeline, Pipeline, "Stdout", move |_,_| move |data_input, time_input, _output| {
                     time_input.for_each(|t, data| {
                         data.swap(&mut vector2);
@@ -226,9 +233,9 @@ impl<G: Scope<Timestamp = usize>> SupportOperators<G> for Stream<G, Record> {
                         }
 
                         if !stash.is_empty() {
-                            stash.retain(|(tp, arg)| {
+                            stash.retain(|(tp, rec)| {
                                 if let Some(ts) = tp_to_ts.get(tp) {
-                                    println!("@{ts} (time point {}): {}", tp, format_record(arg.clone()));
+                                    write!(writer, "{}", verdict_line(*ts, *tp, rec.clone())).expect("Unable to write output");
                                     false
                                 } else {
                                     true
@@ -240,77 +247,245 @@ impl<G: Scope<Timestamp = usize>> SupportOperators<G> for Stream<G, Record> {
                     data_input.for_each(|time, data| {
                         data.swap(&mut vector1);
                         for rec in vector1.drain(..) {
-                            match rec {
-                                Data(true, arg) => {
-                                    if let Some(ts) = tp_to_ts.get(time.time()) {
-                                        println!("@{ts} (time point {}): {}", time.time(), format_record(arg));
-                                    } else {
-                                        stash.push((*time.time(), arg))
-                                    }
+                            if let Data(true, _) | Unknown(_) = rec {
+                                if let Some(ts) = tp_to_ts.get(time.time()) {
+                                    write!(writer, "{}", verdict_line(*ts, *time.time(), rec)).expect("Unable to write output");
+                                } else {
+                                    stash.push((*time.time(), rec))
                                 }
-                                _ => {}
                             }
                         }
                     });
+                    writer.flush().expect("Unable to write output");
                 })
             },
             2 => { // continues file write
-                let batch_size = options.get_output_batch();
-                let mut stash : Vec<(usize, Vec<Constant>)> = Vec::with_capacity(8);
-                let mut out_stash : String = "".to_string();
-                let mut counter = 0;
-                let mut tp_to_ts = HashMap::with_capacity(8);
-                self.binary(&time_stream, Pipeline, Pipeline, "Stdout", move |_,_| move |data_input, time_input, _output| {
-                    time_input.for_each(|t, data| {
-                        data.swap(&mut vector2);
-                        for rec in vector2.drain(..) {
-                            if let FTimestamp(x) = rec {
-                                tp_to_ts.entry(*t.time()).or_insert(x);
+                batched_output(self, &time_stream, options.get_output_batch(), output_writer(&options))
+            }
+            3 => { // three-valued verdicts in time point order
+                let no_free_vars = free_variables.is_empty();
+                ordered_output(self, &time_stream, options.get_output_sink(), options.get_output_file(), move |writer, tp, ts, records| write_verdicts(writer, tp, ts, no_free_vars, records))
+            }
+            4 => { // print to stdout in (tp, tuple) order
+                ordered_output(self, &time_stream, options.get_output_sink(), options.get_output_file(), write_satisfactions)
+            }
+            _ => {
+                //disregard output
+                Filter::filter(self, move |_| false)
+            }
+        };
+
+        (free_variables, out_stream)
+    }
+
+    // Runs after an unbounded future operator and forwards its output. Once the stream has ended,
+    // every time point at which a valuation is neither satisfied nor violated for good is reported
+    // as Unknown(valuation). The valuations are the ones seen on the rhs (resp. the empty one for
+    // closed formulas); all other valuations are violated under finite trace semantics.
+    // Whether a verdict is pending only depends on the lhs variables of the valuation (all of them
+    // for Eventually), so the valuations are partitioned by them over the workers. A time point is
+    // settled once no valuation, including the ones that only show up later, can be pending at it.
+    // The capability follows the frontier of the inputs but stays at the lowest time point that is
+    // not settled, and the state of settled time points is dropped.
+    fn unknown_verdicts(&self, visitor: &mut usize, time_stream: &Stream<G, TimeFlowValues>, rhs_stream: &Stream<G, Record>, lhs_stream: Option<&Stream<G, Record>>, attrs: &Vec<String>, condition: PendingCondition, interval: TimeInterval) -> (Vec<String>, Stream<G, Record>) {
+        *visitor += 1;
+        let no_free_vars = attrs.is_empty();
+
+        // 0: output of the operator, 1: rhs, 2: lhs
+        let mut tagged = self.map(|rec| (0u8, rec)).concat(&rhs_stream.map(|rec| (1u8, rec)));
+        if let Some(lhs_stream) = lhs_stream {
+            tagged = tagged.concat(&lhs_stream.map(|rec| (2u8, rec)));
+        }
+
+        let key_indices = match &condition {
+            PendingCondition::Until(indices) | PendingCondition::NegUntil(indices) => Some(indices.clone()),
+            PendingCondition::Eventually => None,
+        };
+        let exchange = PactExchange::new(move |(tag, rec): &(u8, Record)| match (tag, rec, &key_indices) {
+            // lhs tuples are the keys of the valuations
+            (2, Data(_, tuple), _) | (_, Data(_, tuple), None) => calculate_hash(tuple),
+            (_, Data(_, tuple), Some(indices)) => calculate_hash(&split_keys_ref(tuple, indices)),
+            _ => 0,
+        });
+
+        // tp -> ts of the time points that are not settled yet
+        let mut tps: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut satisfied: HashMap<usize, HashSet<Vec<Constant>>> = HashMap::with_capacity(8);
+        let mut valuations: HashSet<Vec<Constant>> = HashSet::with_capacity(8);
+        // lhs tuple -> tps at which it held
+        let mut lhs_tps: HashMap<Vec<Constant>, BTreeSet<usize>> = HashMap::with_capacity(8);
+        // largest timestamp below the frontier, later time points do not have a smaller one
+        let mut complete_ts = None;
+        let mut eos_tp = None;
+
+        // the empty valuation of a closed formula is only tracked by the worker it is exchanged to
+        let peers = self.scope().peers() as u64;
+        let index = self.scope().index() as u64;
+        let responsible = !no_free_vars || calculate_hash(&Vec::<Constant>::new()) % peers == index;
+
+        let output_stream = tagged.binary_frontier(&time_stream.broadcast(), exchange, Pipeline, "UnknownVerdicts", move |capability, _info| {
+            let mut data_vec = Vec::new();
+            let mut time_vec = Vec::new();
+            let mut cap = Some(capability);
+
+            move |data_input, time_input, output| {
+                time_input.for_each(|time, data| {
+                    data.swap(&mut time_vec);
+                    for tfv in time_vec.drain(..) {
+                        match tfv {
+                            TimeFlowValues::Timestamp(ts) => {
+                                if responsible {
+                                    tps.insert(*time.time(), ts);
+                                }
                             }
+                            TimeFlowValues::EOS => eos_tp = Some(*time.time()),
                         }
+                    }
+                });
 
-                        if !stash.is_empty() {
-                            stash.retain(|(tp, arg)| {
-                                if let Some(ts) = tp_to_ts.get(tp) {
-                                    println!("@{ts} (time point {}): {}", tp, format_record(arg.clone()));
-                                    false
-                                } else {
-                                    true
-                                }
-                            });
+                data_input.for_each(|time, data| {
+                    let tp = *time.time();
+                    data.swap(&mut data_vec);
+                    for (tag, rec) in data_vec.drain(..) {
+                        match (tag, rec) {
+                            (0, Data(flag, tuple)) => {
+                                satisfied.entry(tp).or_insert_with(|| HashSet::with_capacity(8)).insert(tuple.clone());
+                                valuations.insert(tuple.clone());
+                                output.session(&time).give(Data(flag, tuple));
+                            }
+                            (0, MetaData(flag, is)) => {
+                                output.session(&time).give(MetaData(flag, is));
+                            }
+                            (1, Data(_, tuple)) => {
+                                valuations.insert(tuple);
+                            }
+                            (_, Data(_, tuple)) => {
+                                lhs_tps.entry(tuple).or_default().insert(tp);
+                            }
+                            _ => {}
                         }
-                    });
+                    }
+                });
 
-                    data_input.for_each(|time, data| {
-                        data.swap(&mut vector1);
-                        for rec in vector1.drain(..) {
-                            match rec {
-                                Data(true, arg) => {
-                                    if let Some(ts) = tp_to_ts.get(time.time()) {
-                                        counter += 1;
-                                        out_stash.push_str(&format!("@{} (time point {}): {}\n", ts, time.time(), format_record(arg)));
-                                        if counter >= batch_size {
-                                            println!("{}", out_stash);
-                                            counter = 0;
-                                            out_stash = "".to_string();
+                let frontier = time_input.frontier().frontier().iter().chain(data_input.frontier().frontier().iter()).min().cloned();
+                let finished = match (eos_tp, frontier) {
+                    (Some(eos), Some(frontier)) => frontier > eos,
+                    (_, frontier) => frontier.is_none(),
+                };
+
+                if finished {
+                    if let Some(cap) = cap.as_ref() {
+                        // lhs tuple -> lowest tp from which on it held (Until) or last tp at which it held (NegUntil)
+                        let mut bounds: HashMap<Vec<Constant>, usize> = HashMap::with_capacity(lhs_tps.len());
+                        for (tuple, held) in lhs_tps.iter() {
+                            let bound = match condition {
+                                PendingCondition::NegUntil(_) => held.iter().next_back().cloned(),
+                                _ => {
+                                    let mut lowest = None;
+                                    for tp in tps.keys().rev() {
+                                        if !held.contains(tp) {
+                                            break;
                                         }
-                                    } else {
-                                        stash.push((*time.time(), arg))
+                                        lowest = Some(*tp);
                                     }
+                                    lowest
                                 }
-                                _ => {}
+                            };
+                            if let Some(bound) = bound {
+                                bounds.insert(tuple.clone(), bound);
                             }
                         }
-                    });
-                })
-            }
-            _ => {
-                //disregard output
-                Filter::filter(self, move |_| false)
+
+                        let candidates: Vec<Vec<Constant>> = if no_free_vars { vec![vec![]] } else { valuations.iter().cloned().collect() };
+                        for tp in tps.keys() {
+                            let mut unknown = Vec::new();
+                            for valuation in candidates.iter() {
+                                if satisfied.get(tp).is_some_and(|s| s.contains(valuation)) {
+                                    continue;
+                                }
+                                let pending = match &condition {
+                                    PendingCondition::Eventually => true,
+                                    PendingCondition::Until(indices) => bounds.get(&split_keys_ref(valuation, indices)).is_some_and(|lowest| lowest <= tp),
+                                    PendingCondition::NegUntil(indices) => bounds.get(&split_keys_ref(valuation, indices)).is_none_or(|last| last < tp),
+                                };
+                                if pending {
+                                    unknown.push(Unknown(valuation.clone()));
+                                }
+                            }
+                            if !unknown.is_empty() {
+                                output.session(&cap.delayed(tp)).give_iterator(unknown.into_iter());
+                            }
+                        }
+                    }
+                    cap = None;
+                    return;
+                }
+
+                let frontier = match frontier {
+                    Some(frontier) => frontier,
+                    None => return,
+                };
+                if let Some((_, ts)) = tps.range(..frontier).next_back() {
+                    complete_ts = Some(complete_ts.map_or(*ts, |c: usize| c.max(*ts)));
+                }
+                // every valuation that later satisfies the rhs also satisfies the time point
+                let future_satisfied = |ts: usize| complete_ts.is_some_and(|c| interval.future_window(ts).first_ts() <= c);
+
+                // only time points below the frontier are complete, settle them in order
+                let mut settled = Vec::new();
+                for (&tp, &ts) in tps.range(..frontier) {
+                    let sat = satisfied.get(&tp);
+                    let is_satisfied = |valuation: &Vec<Constant>| sat.is_some_and(|s| s.contains(valuation));
+                    let done = match &condition {
+                        PendingCondition::Eventually => {
+                            if no_free_vars {
+                                is_satisfied(&vec![])
+                            } else {
+                                future_satisfied(ts) && valuations.iter().all(is_satisfied)
+                            }
+                        }
+                        // pending valuations need their lhs tuple to hold from tp on
+                        PendingCondition::Until(_) => {
+                            (no_free_vars && is_satisfied(&vec![])) || lhs_tps.values().filter(|held| held.contains(&tp)).all(|held| {
+                                tps.range(tp + 1..frontier).any(|(later, _)| !held.contains(later))
+                            })
+                        }
+                        // pending valuations need their lhs tuple to not hold from tp on
+                        PendingCondition::NegUntil(indices) => {
+                            let held_later = |valuation: &Vec<Constant>| {
+                                lhs_tps.get(&split_keys_ref(valuation, indices)).is_some_and(|held| held.range(tp..frontier).next().is_some())
+                            };
+                            if no_free_vars {
+                                is_satisfied(&vec![]) || held_later(&vec![])
+                            } else {
+                                future_satisfied(ts) && valuations.iter().all(|v| is_satisfied(v) || held_later(v))
+                            }
+                        }
+                    };
+                    if !done {
+                        break;
+                    }
+                    settled.push(tp);
+                }
+
+                for tp in settled {
+                    tps.remove(&tp);
+                    satisfied.remove(&tp);
+                }
+                let lowest = tps.keys().next().map_or(frontier, |tp| min(*tp, frontier));
+                if let Some(cap) = cap.as_mut() {
+                    if *cap.time() < lowest {
+                        cap.downgrade(&lowest);
+                        for held in lhs_tps.values_mut() {
+                            *held = held.split_off(&lowest);
+                        }
+                        lhs_tps.retain(|_, held| !held.is_empty());
+                    }
+                }
             }
-        };
+        });
 
-        (free_variables, out_stream)
+        (attrs.clone(), output_stream)
     }
 }
 
@@ -456,6 +631,22 @@ pub trait Operators<G: Scope<Timestamp = usize>> {
         attrs: &Vec<String>
     ) -> (Vec<String>, Stream<G, Record>);
 
+    fn historically(
+        &self,
+        visitor: &mut usize,
+        time_stream: &Stream<G, TimeFlowValues>,
+        attrs: &Vec<String>,
+        interval: TimeInterval,
+    ) -> (Vec<String>, Stream<G, Record>);
+
+    fn always(
+        &self,
+        visitor: &mut usize,
+        time_stream: &Stream<G, TimeFlowValues>,
+        attrs: &Vec<String>,
+        interval: TimeInterval,
+    ) -> (Vec<String>, Stream<G, Record>);
+
     fn neg_since(
         self,
         visitor: &mut usize,
@@ -779,6 +970,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is_true));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
 
@@ -808,6 +1000,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is))
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
 
@@ -947,6 +1140,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is_true));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -974,6 +1168,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -997,12 +1192,11 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
 
                         // never release capability if infinite
                         if !interval.is_infinite() && has_ts {
-                            let b = interval.get_raw_end();
                             // get the ts of the frontier tp
                             let current_ts = *time_table.entry(current_tp).or_default();
 
-                            if !(b > current_ts) {
-                                let cut_off = current_ts - b;
+                            // later time points can still share current_ts, only windows that end before it are complete
+                            if let Some(cut_off) = interval.last_complete_ts(current_ts) {
                                 let mut to_remove = Vec::new();
 
                                 if let Some((_low, up)) = ts_to_tp.get_mut(&cut_off) {
@@ -1029,6 +1223,11 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                 cap = None
                             }
                         }
+
+                        // infinite intervals are only decided at the end of the stream
+                        if end_of_stream_lhs && end_of_stream_rhs && end_of_stream_tp_lhs == end_of_stream_tp_rhs && end_of_stream_tp_rhs == current_tp {
+                            cap = None
+                        }
                     }
                 });
 
@@ -1185,6 +1384,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -1250,6 +1450,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is))
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     }
                     times.push(tp);
@@ -1333,6 +1534,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is))
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -1432,6 +1634,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is))
                                 }
                             }
+                            Unknown(_) => {}
                         }
 
                     }
@@ -1510,11 +1713,11 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                 highest_tp_ts = max(highest_tp_ts, tp);
                             }
 
-                            let a = interval.get_raw_start();
-                            let lower = if a > ts { None } else { Some(ts - a) };
-                            if interval.is_infinite() {
-                                if lower.is_some() {
-                                    match obs_seq.associated_upper_bound_ts(lower.unwrap()) {
+                            // the betas whose ts lies in the window of the new ts
+                            if let Some(window) = interval.past_window(ts) {
+                                // the window reaches back to ts 0
+                                if interval.is_infinite() || interval.get_raw_end() > ts {
+                                    match obs_seq.associated_upper_bound_ts(window.last_ts().unwrap_or_default()) {
                                         Literal(a) => {
                                             for i in 0..a + 1 {
                                                 relevant_tp.insert(i);
@@ -1525,33 +1728,14 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                         }
                                         _ => {}
                                     };
-                                }
-                            } else {
-                                let b = interval.get_raw_end();
-                                let upper = if b > ts { None } else { Some(ts - b) };
-
-                                if lower.is_some() && upper.is_some() {
-                                    //println!("   Search space {}   {}", upper.unwrap(), lower.unwrap());
-                                    match obs_seq.associated_interval_ts_exact(upper.unwrap(), lower.unwrap()) {
+                                } else {
+                                    match obs_seq.associated_window_exact(window) {
                                         Literal(a) => { relevant_tp.insert(a); }
                                         InfinityIntervalsReturn::Interval(a, b) => {
                                             for i in a..b + 1 { relevant_tp.insert(i); }
                                         }
                                         _ => {}
                                     };
-                                } else if lower.is_some() {
-                                    //println!("   Search space {}", lower.unwrap());
-                                    match obs_seq.associated_upper_bound_ts(lower.unwrap()) {
-                                        Literal(a) => {
-                                            for i in 0..a + 1 {
-                                                relevant_tp.insert(i);
-                                            }
-                                        }
-                                        InfinityIntervalsReturn::Interval(a, b) => {
-                                            for i in a..b + 1 { relevant_tp.insert(i); }
-                                        }
-                                        _ => {}
-                                    };
                                 }
                             }
                         }
@@ -1623,6 +1807,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -1683,12 +1868,11 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                     // no clean up if infinite
                     if !interval.is_infinite() {
                         if let Some(frontier_ts) = tp_to_ts.get(&tp) {
-                            // compute highest tp
-                            let highest_ts = frontier_ts + interval.get_raw_end();
+                            // betas stay relevant as long as they reach the frontier ts
+                            let highest_ts = *frontier_ts;
                             new_beta.retain(|k, _v| {
                                 if let Some(beta_ts) = tp_to_ts.get(k) {
-                                    let beta_high = beta_ts + interval.get_raw_end();
-                                    !(highest_ts > beta_high)
+                                    interval.below_end(highest_ts.saturating_sub(*beta_ts))
                                 } else {
                                     true
                                 }
@@ -1697,7 +1881,15 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                     }
 
                     results.remove(&tp);
-                    obs_seq.clean_up(tp, *tp_to_ts.entry(tp).or_default());
+                    // the observations of time points with live betas are still needed to find their range
+                    match new_beta.keys().min() {
+                        Some(&oldest) if oldest <= tp => {
+                            if oldest > 0 {
+                                obs_seq.clean_up(oldest - 1, *tp_to_ts.entry(oldest - 1).or_default());
+                            }
+                        }
+                        _ => obs_seq.clean_up(tp, *tp_to_ts.entry(tp).or_default()),
+                    }
                     unique_res.remove(&tp).unwrap_or_default();
                     unique_beta.remove(&tp).unwrap_or_default();
 
@@ -1815,6 +2007,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     }
 
@@ -1910,17 +2103,15 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                 }
                             }
 
-                            let lower = interval.get_raw_start() + ts;
+                            let window = interval.future_window(ts);
                             if interval.is_infinite() {
-                                match obs_seq.associated_upper_bound_ts(lower) {
+                                match obs_seq.associated_upper_bound_ts(window.first_ts()) {
                                     Literal(a) => { for i in a..highest_tp_ts + 1 { relevant_tp.insert(i); } }
                                     InfinityIntervalsReturn::Interval(a, _) => { for i in a..highest_tp_ts + 1 { relevant_tp.insert(i); } }
                                     _ => {}
                                 };
                             } else {
-                                let upper = interval.get_raw_end() + ts;
-                                //println!("   Search space {}   {}", upper.unwrap(), lower.unwrap());
-                                match obs_seq.associated_interval_ts_exact(lower, upper) {
+                                match obs_seq.associated_window_exact(window) {
                                     Literal(a) => {
                                         for i in a..highest_tp_ts + 1 { relevant_tp.insert(i); }
                                     }
@@ -1996,6 +2187,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -2013,14 +2205,13 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
 
                     // never release capability if infinite
                     if !interval.is_infinite() && has_ts {
-                        let b = interval.get_raw_end();
 
                         // get the ts of the frontier tp
                         let current_ts = *tp_to_ts.entry(current_tp).or_default();
 
                         // get all tps for this ts and below
-                        if !(b > current_ts) {
-                            let cut_off = current_ts - b;
+                        // later time points can still share current_ts, only windows that end before it are complete
+                        if let Some(cut_off) = interval.last_complete_ts(current_ts) {
                             let mut to_remove = Vec::new();
 
                             if let Some((_low, up)) = ts_to_tp.get_mut(&cut_off) {
@@ -2100,6 +2291,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     }
                 });
@@ -2112,6 +2304,244 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
         (attrs.clone(), output)
     }
 
+    // A valuation satisfies Historically at tp i if it satisfied the subformula at every tp of the
+    // window. Time points are only evaluated once they are complete, at which point every earlier tp
+    // is complete as well. For every valuation we keep the stretches of consecutive tps at which it held.
+    fn historically(&self, visitor: &mut usize, time_stream: &Stream<G, TimeFlowValues>, attrs: &Vec<String>, interval: TimeInterval) -> (Vec<String>, Stream<G, Record>) {
+        *visitor += 1;
+        let worker_index = self.scope().index();
+        // an empty window is vacuously satisfied, which can only be reported for closed subformulas
+        let no_free_vars = attrs.is_empty();
+
+        // tp -> ts for incomplete and complete tps
+        let mut pending_ts: HashMap<usize, usize> = HashMap::with_capacity(8);
+        let mut tp_to_ts: BTreeMap<usize, usize> = BTreeMap::new();
+        // satisfactions of incomplete tps
+        let mut pending: HashMap<usize, HashSet<Vec<Constant>>> = HashMap::with_capacity(8);
+        let mut runs: ValuationRuns = HashMap::with_capacity(8);
+
+        let mut first_tp = None;
+        let mut previous_tp = None;
+
+        let mut eos_tp = 0;
+        let mut eos_flag = false;
+
+        let output_stream = self.binary_frontier(time_stream, Pipeline, Pipeline, "Historically", move |capability, _info| {
+            let mut notificator = FrontierNotificator::new();
+            let mut data_vec = Vec::new();
+            let mut time_vec = Vec::new();
+            let mut cap = Some(capability);
+
+            move |data_input, time_input, output| {
+                time_input.for_each(|time, data| {
+                    let tp = *time.time();
+                    notificator.notify_at(time.delayed(&tp));
+                    data.swap(&mut time_vec);
+                    for tfv in time_vec.drain(..) {
+                        if let TimeFlowValues::Timestamp(ts) = tfv {
+                            pending_ts.entry(tp).or_insert(ts);
+                        }
+                    }
+                });
+
+                data_input.for_each(|time, data| {
+                    let tp = *time.time();
+                    notificator.notify_at(time.delayed(&tp));
+                    data.swap(&mut data_vec);
+                    for rec in data_vec.drain(..) {
+                        match rec {
+                            Data(_, tuple) => {
+                                pending.entry(tp).or_insert_with(|| HashSet::with_capacity(8)).insert(tuple);
+                            }
+                            MetaData(flag, is) => {
+                                if !flag {
+                                    eos_flag = true;
+                                    eos_tp = tp;
+                                }
+                                if worker_index == 0 {
+                                    output.session(&time).give(MetaData(flag, is));
+                                }
+                            }
+                            Unknown(_) => {}
+                        }
+                    }
+                });
+
+                notificator.for_each(&[time_input.frontier(), data_input.frontier()], |time, _| {
+                    let tp = *time.time();
+                    let satisfied = pending.remove(&tp).unwrap_or_default();
+
+                    if let Some(ts) = pending_ts.remove(&tp) {
+                        tp_to_ts.insert(tp, ts);
+                        if first_tp.is_none() {
+                            first_tp = Some(tp);
+                        }
+                        extend_valuation_runs(&mut runs, satisfied, tp, previous_tp);
+                        previous_tp = Some(tp);
+
+                        match historically_window(&tp_to_ts, tp, ts, interval) {
+                            Some((lower, upper)) => {
+                                // an unbounded window always reaches back to the first tp
+                                let lower = if interval.is_infinite() { first_tp.unwrap_or(lower) } else { lower };
+                                output.session(&time).give_iterator(runs
+                                    .iter()
+                                    .filter(|(_, stretches)| holds_throughout(stretches, lower, upper))
+                                    .map(|(valuation, _)| Data(true, valuation.clone())));
+
+                                if interval.is_infinite() {
+                                    // only valuations that held at every tp so far can still be satisfied
+                                    runs.retain(|_, stretches| matches!(stretches.front(), Some((start, end)) if Some(*start) == first_tp && *end == tp));
+                                    tp_to_ts = tp_to_ts.split_off(&upper);
+                                } else {
+                                    prune_valuation_runs(&mut runs, lower);
+                                    tp_to_ts = tp_to_ts.split_off(&lower);
+                                }
+                            }
+                            None => {
+                                if no_free_vars && worker_index == 0 {
+                                    output.session(&time).give(Data(true, vec![]));
+                                }
+                            }
+                        }
+                    }
+
+                    if let Some(cap) = cap.as_mut() {
+                        cap.downgrade(&(tp + 1));
+                    }
+                    if eos_flag && eos_tp == tp {
+                        cap = None;
+                    }
+                });
+
+                if time_input.frontier().is_empty() && data_input.frontier().is_empty() {
+                    cap = None;
+                }
+            }
+        });
+
+        (attrs.clone(), output_stream)
+    }
+
+    // A valuation satisfies Always at tp i if it satisfies the subformula at every tp of the window.
+    // The verdict for i is final once a complete tp lies beyond the window, or at the end of the
+    // stream, where the window is cut off at the last tp (finite trace semantics). Until then the
+    // capability is held at the lowest undecided tp.
+    fn always(&self, visitor: &mut usize, time_stream: &Stream<G, TimeFlowValues>, attrs: &Vec<String>, interval: TimeInterval) -> (Vec<String>, Stream<G, Record>) {
+        *visitor += 1;
+        let worker_index = self.scope().index();
+        let no_free_vars = attrs.is_empty();
+
+        let mut pending_ts: HashMap<usize, usize> = HashMap::with_capacity(8);
+        let mut tp_to_ts: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut pending: HashMap<usize, HashSet<Vec<Constant>>> = HashMap::with_capacity(8);
+        let mut runs: ValuationRuns = HashMap::with_capacity(8);
+        // complete tps whose verdict is not yet final
+        let mut undecided: VecDeque<usize> = VecDeque::with_capacity(8);
+
+        let mut previous_tp = None;
+
+        let mut eos_tp = 0;
+        let mut eos_flag = false;
+
+        let output_stream = self.binary_frontier(time_stream, Pipeline, Pipeline, "Always", move |capability, _info| {
+            let mut notificator = FrontierNotificator::new();
+            let mut data_vec = Vec::new();
+            let mut time_vec = Vec::new();
+            let mut cap = Some(capability);
+
+            move |data_input, time_input, output| {
+                time_input.for_each(|time, data| {
+                    let tp = *time.time();
+                    notificator.notify_at(time.delayed(&tp));
+                    data.swap(&mut time_vec);
+                    for tfv in time_vec.drain(..) {
+                        if let TimeFlowValues::Timestamp(ts) = tfv {
+                            pending_ts.entry(tp).or_insert(ts);
+                        }
+                    }
+                });
+
+                data_input.for_each(|time, data| {
+                    let tp = *time.time();
+                    notificator.notify_at(time.delayed(&tp));
+                    data.swap(&mut data_vec);
+                    for rec in data_vec.drain(..) {
+                        match rec {
+                            Data(_, tuple) => {
+                                pending.entry(tp).or_insert_with(|| HashSet::with_capacity(8)).insert(tuple);
+                            }
+                            MetaData(flag, is) => {
+                                if !flag {
+                                    eos_flag = true;
+                                    eos_tp = tp;
+                                }
+                                if worker_index == 0 {
+                                    output.session(&time).give(MetaData(flag, is));
+                                }
+                            }
+                            Unknown(_) => {}
+                        }
+                    }
+                });
+
+                let mut finished = false;
+                notificator.for_each(&[time_input.frontier(), data_input.frontier()], |time, _| {
+                    let tp = *time.time();
+                    let satisfied = pending.remove(&tp).unwrap_or_default();
+
+                    if let Some(ts) = pending_ts.remove(&tp) {
+                        tp_to_ts.insert(tp, ts);
+                        extend_valuation_runs(&mut runs, satisfied, tp, previous_tp);
+                        previous_tp = Some(tp);
+
+                        if !interval.is_infinite() {
+                            while let Some(&front) = undecided.front() {
+                                let front_ts = *tp_to_ts.get(&front).unwrap_or(&0);
+                                if interval.below_end(ts.saturating_sub(front_ts)) {
+                                    break;
+                                }
+                                undecided.pop_front();
+                                if let Some(cap) = cap.as_ref() {
+                                    output.session(&cap.delayed(&front)).give_iterator(always_verdicts(&runs, &tp_to_ts, front, interval, no_free_vars && worker_index == 0).into_iter());
+                                }
+                            }
+                        }
+                        undecided.push_back(tp);
+                    }
+
+                    if eos_flag && eos_tp == tp {
+                        finished = true;
+                    } else if let Some(cap) = cap.as_mut() {
+                        // keep the capability at the lowest undecided tp
+                        let lowest = *undecided.front().unwrap_or(&(tp + 1));
+                        cap.downgrade(&min(lowest, tp + 1));
+                    }
+
+                    if let Some(&front) = undecided.front() {
+                        prune_valuation_runs(&mut runs, front);
+                        tp_to_ts = tp_to_ts.split_off(&front);
+                    }
+                });
+
+                if time_input.frontier().is_empty() && data_input.frontier().is_empty() {
+                    finished = true;
+                }
+
+                if finished {
+                    // the remaining windows are cut off at the end of the trace
+                    if let Some(cap) = cap.as_ref() {
+                        for front in undecided.drain(..) {
+                            output.session(&cap.delayed(&front)).give_iterator(always_verdicts(&runs, &tp_to_ts, front, interval, no_free_vars && worker_index == 0).into_iter());
+                        }
+                    }
+                    cap = None;
+                }
+            }
+        });
+
+        (attrs.clone(), output_stream)
+    }
+
     fn neg_since(self, visitor: &mut usize, rhs_stream: Stream<G, Record>, time_stream: Stream<G, TimeFlowValues>, lhs_attrs: &Vec<String>, rhs_attrs: &Vec<String>, interval: TimeInterval, deduplication: bool) -> (Vec<String>, Stream<G, Record>) {
         // update visitor
         *visitor = visitor.clone() + 1;
@@ -2181,6 +2611,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is_true));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -2211,7 +2642,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                             map.entry(tp).or_default().push(tuple.clone());
                                         }
 
-                                        if interval.get_raw_start() == 0 {
+                                        if interval.contains(0) {
                                             //println!("Send at RHS: @{tp}  {:?}", tuple);
                                             output.session(&time).give(Data(true, tuple.clone()));
                                             unique_res.entry(tp).or_default().insert(tuple.clone());
@@ -2230,6 +2661,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is))
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -2349,6 +2781,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is_true));
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -2376,7 +2809,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                         map.entry(tp).or_default().push(tuple.clone());
                                     }
 
-                                    if interval.get_raw_start() == 0 {
+                                    if interval.contains(0) {
                                         //println!("Send at RHS: @{tp}  {:?}", tuple);
                                         output.session(&time).give(Data(true, tuple.clone()));
                                         unique_res.entry(tp).or_default().insert(tuple.clone());
@@ -2395,6 +2828,7 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                                     output.session(&time).give(MetaData(flag, is))
                                 }
                             }
+                            Unknown(_) => {}
                         }
                     };
                 });
@@ -2413,12 +2847,11 @@ impl<G: Scope<Timestamp = usize>> Operators<G> for Stream<G, Record> {
                     }
 
                     if !interval.is_infinite(){
-                        let b = interval.get_raw_end();
                         // get the ts of the frontier tp
                         let current_ts = *time_table.entry(frontier_tp).or_default();
 
-                        if !(b > current_ts) {
-                            let cut_off = current_ts - b;
+                        // later time points can still share current_ts, only windows that end before it are complete
+                        if let Some(cut_off) = interval.last_complete_ts(current_ts) {
                             let mut to_remove = Vec::new();
 
                             if let Some((_low, up)) = ts_to_tp.get_mut(&cut_off) {
@@ -2464,7 +2897,7 @@ pub(crate) fn anti_temporal_until(frontier: usize, alphas : &mut HashMap<Vec<Con
                     for tps in (lowest_tp..*sub).rev() {
                         if !contains(alpha_ds.satisfactions.clone(), tps) {
                             let frontier_ts = *tp_to_ts.entry(tps.clone()).or_default();
-                            if if interval.is_infinite() { false } else { frontier_ts + interval.get_raw_end() < beta_ts } {
+                            if !interval.below_end(beta_ts.saturating_sub(frontier_ts)) {
                                 break
                             }
 
@@ -2577,6 +3010,13 @@ fn process_literal(val : &mut (usize,usize,usize,bool), tp: usize) -> Vec<usize>
     }
 }
 
+// Whether the observations reach far enough back to look up the time points of the window.
+fn window_observed(obs_seq: &mut ObservationSequence, window: TimeInterval) -> bool {
+    let zero_ts_satisfied = obs_seq.zero_ts().is_some_and(|zero_ts| window.below_end(zero_ts));
+    let lowest_ts_satisfied = obs_seq.lowest_ts().is_some_and(|lowest_ts| window.first_ts() >= lowest_ts);
+    zero_ts_satisfied || lowest_ts_satisfied
+}
+
 pub fn process_eventually_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Constant>, (usize, usize, usize, bool)>>, obs_seq : &mut ObservationSequence, interval: TimeInterval, relevant_tps : HashSet<usize>) -> HashMap<Vec<Constant>, HashSet<usize>> {
     //println!("Process Alpha Eventually finite");
     let mut res : HashMap<Vec<Constant>, HashSet<usize>> = HashMap::with_capacity(8);
@@ -2588,37 +3028,11 @@ pub fn process_eventually_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Co
                 match obs_seq.associated_interval_tp(beta_tp) {
                     Literal(beta_ts) => {
                         //println!("          Literal case");
-                        let lower_bound_ts = if beta_ts >= interval.get_raw_end() { beta_ts - interval.get_raw_end() } else { 0 };
-                        let upper_bound_ts = if beta_ts >= interval.get_raw_start() { beta_ts - interval.get_raw_start() } else { 0 };
-                        let out_of_bounds_negative = interval.get_raw_start() > beta_ts;
-                        let zero_ts_satisfied = match obs_seq.zero_ts() {
-                            None => {false}
-                            Some(low_ts) => {
-                                if !out_of_bounds_negative {
-                                    beta_ts - interval.get_raw_start() >= low_ts
-                                } else {
-                                    false
-                                }
-                            }
-                        };
-                        let lowest_ts_satisfied = match obs_seq.lowest_ts() {
-                            None => {false}
-                            Some(lowest_ts) => {
-                                if !out_of_bounds_negative {
-                                    lower_bound_ts >= lowest_ts
-                                } else {
-                                    false
-                                }
-                            }
-                        };
-
-                        let lowest_satisfied = (zero_ts_satisfied || lowest_ts_satisfied ) && !out_of_bounds_negative;
-
-                        if lowest_satisfied {
-                            match obs_seq.associated_interval_ts_exact(lower_bound_ts, upper_bound_ts) {
+                        if let Some(window) = interval.past_window(beta_ts).filter(|window| window_observed(obs_seq, *window)) {
+                            match obs_seq.associated_window_exact(window) {
                                 Literal(b) => {
                                     //println!("              Literal case @{} {}", beta_tp, b);
-                                    if interval.get_raw_start() == 0 {
+                                    if interval.contains(0) {
                                         let x = process_interval(val, b, beta_tp);
                                         //println!("{:?}", x);
                                         if !x.is_empty() {
@@ -2640,7 +3054,7 @@ pub fn process_eventually_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Co
                                 }
                                 InfinityIntervalsReturn::Interval(a, b) => {
                                     //println!("              Interval case @{}  a{} b{}", beta_tp, a, b);
-                                    let up = if interval.get_raw_start() == 0 { beta_tp } else { b };
+                                    let up = if interval.contains(0) { beta_tp } else { b };
                                     let x = process_interval(val, a, up);
                                     //println!("{:?}", x);
                                     if !x.is_empty() {
@@ -2654,77 +3068,7 @@ pub fn process_eventually_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Co
                             }
                         }
                     }
-                    InfinityIntervalsReturn::Interval(low_ts, up_ts) => {
-                        let lower = if low_ts >= interval.get_raw_end() {low_ts - interval.get_raw_end()} else {0};
-                        let out_of_bounds_low = interval.get_raw_end() > low_ts;
-                        let higher = if up_ts >= interval.get_raw_start() {up_ts - interval.get_raw_start()} else {0};
-                        let out_of_bounds_higher = interval.get_raw_start() > up_ts;
-
-                        // || instead &&
-                        let out_of_bounds_negative = !out_of_bounds_low || !out_of_bounds_higher;
-
-                        let zero_ts_satisfied = match obs_seq.zero_ts() {
-                            None => {false}
-                            Some(lowest_ts) => {
-                                if !out_of_bounds_low {
-                                    low_ts - interval.get_raw_end() >= lowest_ts
-                                } else {
-                                    false
-                                }
-                            }
-                        };
-                        let lowest_ts_satisfied = match obs_seq.lowest_ts() {
-                            None => {false}
-                            Some(lowest_ts) => {
-                                if !out_of_bounds_low {
-                                    lower >= lowest_ts
-                                } else {
-                                    false
-                                }
-                            }
-                        };
-
-                        let lowest_satisfied = (zero_ts_satisfied || lowest_ts_satisfied ) && !out_of_bounds_negative;
-                        //println!("              {:?}  low {} upper {}    {} {} {}", rec, lower, higher, lowest_satisfied, zero_ts_satisfied, lowest_ts_satisfied);
-                        if lowest_satisfied {
-                            match obs_seq.associated_interval_ts_inexact(lower, higher) {
-                                Literal(b) => {
-                                    //println!("              Literal case @{} {}", beta_tp, b);
-                                    if interval.get_raw_start() == 0 {
-                                        let x = process_interval(val, b, beta_tp);
-                                        //println!("{:?}", x);
-                                        if !x.is_empty() {
-                                            //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
-                                            for n in x {
-                                                res.entry(rec.clone()).or_insert_with(|| HashSet::new()).insert(n);
-                                            }
-                                        }
-                                    } else {
-                                        let x = process_literal(val, b);
-                                        //println!("{:?}", x);
-                                        if !x.is_empty() {
-                                            //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
-                                            for n in x {
-                                                res.entry(rec.clone()).or_insert_with(|| HashSet::new()).insert(n);
-                                            }
-                                        }
-                                    }
-                                }
-                                InfinityIntervalsReturn::Interval(a, b) => {
-                                    //println!("              Interval @{}  a{} b{}\n", beta_tp, a, b);
-                                    let up = if interval.get_raw_start() == 0 { beta_tp } else { b };
-                                    let x = process_interval(val, a, up);
-                                    if !x.is_empty() {
-                                        //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
-                                        for n in x {
-                                            res.entry(rec.clone()).or_insert_with(|| HashSet::new()).insert(n);
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
+                    // the window of a time point is only known together with its timestamp
                     _ => {}
                 }
             }
@@ -2737,40 +3081,13 @@ pub fn process_eventually_data_finite(tuple: Vec<Constant>, tp: usize, interval:
     return match obs_seq.associated_interval_tp(tp) {
         Literal(beta_ts) => {
             //println!("          Literal case");
-            let lower_bound_ts = if beta_ts >= interval.get_raw_end() {beta_ts - interval.get_raw_end()} else {0};
-            let upper_bound_ts = if beta_ts >= interval.get_raw_start() {beta_ts - interval.get_raw_start()} else {0};
-            let out_of_bounds_negative = interval.get_raw_start() > beta_ts;
-            // check if lower_bound_ts exists or lowest exists
-            let zero_ts_satisfied = match obs_seq.zero_ts() {
-                None => {false}
-                Some(low_ts) => {
-                    if !out_of_bounds_negative {
-                        beta_ts - interval.get_raw_start() >= low_ts
-                    } else {
-                        false
-                    }
-                }
-            };
-            let lowest_ts_satisfied = match obs_seq.lowest_ts() {
-                None => {false}
-                Some(lowest_ts) => {
-                    if !out_of_bounds_negative {
-                        lower_bound_ts >= lowest_ts
-                    } else {
-                        false
-                    }
-                }
-            };
-
-            let lowest_satisfied = (zero_ts_satisfied || lowest_ts_satisfied ) && !out_of_bounds_negative;
-            //println!("              {:?}   low {} upper {}    {}", tuple, lower_bound_ts, upper_bound_ts, lowest_satisfied);
-            if lowest_satisfied {
-                match obs_seq.associated_interval_ts_exact(lower_bound_ts, upper_bound_ts) {
+            if let Some(window) = interval.past_window(beta_ts).filter(|window| window_observed(obs_seq, *window)) {
+                match obs_seq.associated_window_exact(window) {
                     Literal(b) => {
                         //println!("              Literal @{}  b{}\n", tp, b);
-                        let irrelevant = tp == 0 && interval.get_raw_start() != 0;
+                        let irrelevant = tp == 0 && !interval.contains(0);
                         if !irrelevant {
-                            let up = if interval.get_raw_start() == 0 {tp} else {b};
+                            let up = if interval.contains(0) {tp} else {b};
                             let vals : Vec<usize> = (b..up+1).collect();
                             betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, b, up, true));
                             vals
@@ -2780,14 +3097,14 @@ pub fn process_eventually_data_finite(tuple: Vec<Constant>, tp: usize, interval:
                     }
                     InfinityIntervalsReturn::Interval(a, b) => {
                         //println!("              Interval @{}  a{} b{}\n", tp, a, b);
-                        let up = if interval.get_raw_start() == 0 {tp} else {b};
+                        let up = if interval.contains(0) {tp} else {b};
                         let vals : Vec<usize> = (a..up+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, a, up, true));
                         vals
                     }
                     InfinityIntervalsReturn::Empty => {
                         //println!("              Literal Empty @{}\n", tp);
-                        if interval.get_raw_start() == 0 {
+                        if interval.contains(0) {
                             betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                             vec![tp]
                         } else {
@@ -2797,80 +3114,7 @@ pub fn process_eventually_data_finite(tuple: Vec<Constant>, tp: usize, interval:
                     }
                 }
             } else {
-                if interval.get_raw_start() == 0 {
-                    betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
-                    vec![tp]
-                } else {
-                    betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, 0, false));
-                    vec![]
-                }
-            }
-        }
-        // compute two intervals and get the ts of the overlapping interval and query for tps given the timepoints
-        InfinityIntervalsReturn::Interval(low_ts, up_ts) => {
-            //println!("          Interval case {} {}", low_ts, up_ts);
-            let lower = if low_ts >= interval.get_raw_end() {low_ts - interval.get_raw_end()} else {0};
-            let out_of_bounds_low = interval.get_raw_end() > low_ts;
-
-            let higher = if up_ts >= interval.get_raw_start() {up_ts - interval.get_raw_start()} else {0};
-            let out_of_bounds_higher = interval.get_raw_start() > up_ts;
-
-            let out_of_bounds_negative = !out_of_bounds_low || !out_of_bounds_higher;
-
-            let zero_ts_satisfied = match obs_seq.zero_ts() {
-                None => {false}
-                Some(lowest_ts) => {
-                    if !out_of_bounds_low {
-                        low_ts - interval.get_raw_end() >= lowest_ts
-                    } else {
-                        false
-                    }
-                }
-            };
-            let lowest_ts_satisfied = match obs_seq.lowest_ts() {
-                None => {false}
-                Some(lowest_ts) => {
-                    if !out_of_bounds_low {
-                        lower >= lowest_ts
-                    } else {
-                        false
-                    }
-                }
-            };
-
-            let lowest_satisfied = (zero_ts_satisfied || lowest_ts_satisfied ) && !out_of_bounds_negative;
-            //println!("          low_ts {} - a{} == {}", low_ts, interval.get_raw_start(), (low_ts as i64 - interval.get_raw_start() as i64));
-            //println!("              {:?}  low {} upper {}    {}", tuple, lower, higher, lowest_satisfied);
-            if lowest_satisfied {
-                match obs_seq.associated_interval_ts_inexact(lower, higher) {
-                    Literal(b) => {
-                        //println!("              Literal @{}  {} a{} b{}\n", tp, b, lower, higher);
-                        let up = if interval.get_raw_start() == 0 {tp} else {b};
-                        let vals : Vec<usize> = (b..up+1).collect();
-                        betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, b, up, true));
-                        vals
-                    }
-                    InfinityIntervalsReturn::Interval(a, b) => {
-                        //println!("              Interval @{}  a{} b{}\n", tp, a, b);
-                        let up = if interval.get_raw_start() == 0 {tp} else {b};
-                        let vals : Vec<usize> = (a..up+1).collect();
-                        betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, a, up, true));
-                        //println!("{:?}", vals);
-                        vals
-                    }
-                    InfinityIntervalsReturn::Empty => {
-                        //println!("              Interval Empty @{}\n", tp);
-                        if interval.get_raw_start() == 0 {
-                            betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
-                            vec![tp]
-                        } else {
-                            betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, 0, false));
-                            vec![]
-                        }
-                    }
-                }
-            } else {
-                if interval.get_raw_start() == 0 {
+                if interval.contains(0) {
                     betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                     vec![tp]
                 } else {
@@ -2879,9 +3123,10 @@ pub fn process_eventually_data_finite(tuple: Vec<Constant>, tp: usize, interval:
                 }
             }
         }
+        // the window of a time point is only known together with its timestamp
         _ => {
             //println!("              Match Empty @{}", tp);
-            if interval.get_raw_start() == 0 {
+            if interval.contains(0) {
                 betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                 vec![tp]
             } else {
@@ -2902,15 +3147,11 @@ pub fn process_eventually_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<
                 match obs_seq.associated_interval_tp(beta_tp) {
                     Literal(beta_ts) => {
                         //println!("          Literal case @{}", beta_tp);
-                        let upper_bound_ts = if beta_ts >= interval.get_raw_start() { beta_ts - interval.get_raw_start() } else { 0 };
-                        let out_of_bounds = interval.get_raw_start() > beta_ts;
-                        //println!("              low {}      {} {} = {}", upper_bound_ts, beta_ts, interval.get_raw_start(), out_of_bounds);
-
-                        if !out_of_bounds {
-                            match obs_seq.associated_upper_bound_ts(upper_bound_ts) {
+                        if let Some(last_ts) = interval.past_window(beta_ts).and_then(|window| window.last_ts()) {
+                            match obs_seq.associated_upper_bound_ts(last_ts) {
                                 Literal(b) => {
                                     //println!("              Literal case @{} {}", beta_tp, b);
-                                    let up = if interval.get_raw_start() == 0 { beta_ts } else { b };
+                                    let up = if interval.contains(0) { beta_ts } else { b };
                                     let x = process_interval(val, 0, up);
                                     //println!("@{} {:?}",beta_tp, x);
                                     if !x.is_empty() {
@@ -2921,7 +3162,7 @@ pub fn process_eventually_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<
                                 }
                                 InfinityIntervalsReturn::Interval(_a, b) => {
                                     //println!("              Interval case @{} a{} b{}", beta_tp, a, b);
-                                    let up = if interval.get_raw_start() == 0 { beta_tp } else { b };
+                                    let up = if interval.contains(0) { beta_tp } else { b };
                                     let x = process_interval(val, 0, up);
                                     //println!("@{} {:?}",beta_tp, x);
                                     if !x.is_empty() {
@@ -2931,7 +3172,7 @@ pub fn process_eventually_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<
                                     }
                                 }
                                 _ => {
-                                    if interval.get_raw_start() == 0 {
+                                    if interval.contains(0) {
                                         let x = process_interval(val, 0, beta_tp);
                                         if !x.is_empty() {
                                             //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -2946,15 +3187,11 @@ pub fn process_eventually_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<
                     }
                     InfinityIntervalsReturn::Interval(low_ts, _up_ts) => {
                         //println!("          Interval case @{}  {} {}", beta_tp, low_ts, up_ts);
-                        let higher = if low_ts >= interval.get_raw_start() { low_ts - interval.get_raw_start() } else { 0 };
-                        let out_of_bounds = interval.get_raw_start() > low_ts;
-                        //println!("           {} {} = {}", low_ts, interval.get_raw_start(), out_of_bounds);
-
-                        if !out_of_bounds {
-                            match obs_seq.associated_upper_bound_ts(higher) {
+                        if let Some(last_ts) = interval.past_window(low_ts).and_then(|window| window.last_ts()) {
+                            match obs_seq.associated_upper_bound_ts(last_ts) {
                                 Literal(b) => {
                                     //println!("              Literal case @{} {}", beta_tp, b);
-                                    let up = if interval.get_raw_start() == 0 {beta_tp} else {b};
+                                    let up = if interval.contains(0) {beta_tp} else {b};
                                     let x = process_interval(val, 0, up);
                                     //println!("@{} {:?}",beta_tp, x);
                                     if !x.is_empty() {
@@ -2965,7 +3202,7 @@ pub fn process_eventually_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<
                                 }
                                 InfinityIntervalsReturn::Interval(_a, b) => {
                                     //println!("              Interval @{}  a{} b{}\n", beta_tp, a, b);
-                                    let up = if interval.get_raw_start() == 0 { beta_tp } else { b };
+                                    let up = if interval.contains(0) { beta_tp } else { b };
                                     let x = process_interval(val, 0, up);
                                     //println!("@{} {:?}",beta_tp, x);
                                     if !x.is_empty() {
@@ -2975,7 +3212,7 @@ pub fn process_eventually_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<
                                     }
                                 }
                                 _ => {
-                                    if interval.get_raw_start() == 0 {
+                                    if interval.contains(0) {
                                         let x = process_interval(val, 0, beta_tp);
                                         if !x.is_empty() {
                                             //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3000,16 +3237,13 @@ pub fn process_eventually_data_infinite(tuple: Vec<Constant>, tp: usize, interva
     return match obs_seq.associated_interval_tp(tp) {
         Literal(beta_ts) => {
             //println!("          Literal case");
-            let upper_bound_ts = if beta_ts >= interval.get_raw_start() {beta_ts - interval.get_raw_start()} else {0};
-            let out_of_bounds = interval.get_raw_start() > beta_ts;
-            //println!("          @{} [{}]", tp, upper_bound_ts);
-            if !out_of_bounds {
-                match obs_seq.associated_interval_ts_exact(upper_bound_ts, upper_bound_ts) {
+            if let Some(last_ts) = interval.past_window(beta_ts).and_then(|window| window.last_ts()) {
+                match obs_seq.associated_interval_ts_exact(last_ts, last_ts) {
                     Literal(b) => {
                         //println!("              Literal @{}  b{}\n", tp, b);
-                        let irrelevant = tp == 0 && interval.get_raw_start() != 0;
+                        let irrelevant = tp == 0 && !interval.contains(0);
                         if !irrelevant {
-                            let up = if interval.get_raw_start() == 0 {tp} else {b};
+                            let up = if interval.contains(0) {tp} else {b};
                             let vals : Vec<usize> = (0..up+1).collect();
                             betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, up, true));
                             vals
@@ -3019,14 +3253,14 @@ pub fn process_eventually_data_infinite(tuple: Vec<Constant>, tp: usize, interva
                     }
                     InfinityIntervalsReturn::Interval(_a, b) => {
                         //println!("              Interval @{}  a{} b{}\n", tp, a, b);
-                        let up = if interval.get_raw_start() == 0 {tp} else {b};
+                        let up = if interval.contains(0) {tp} else {b};
                         let vals : Vec<usize> = (0..up+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, up, true));
                         vals
                     }
                     InfinityIntervalsReturn::Empty => {
                         //println!("              Interval Empty @{}\n", tp);
-                        if interval.get_raw_start() == 0 {
+                        if interval.contains(0) {
                             betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, tp, true));
                             vec![tp]
                         } else {
@@ -3043,31 +3277,25 @@ pub fn process_eventually_data_infinite(tuple: Vec<Constant>, tp: usize, interva
         // compute two intervals and get the ts of the overlapping interval and query for tps given the timepoints
         InfinityIntervalsReturn::Interval(low_ts, _up_ts) => {
             //println!("          Interval case {} {}", low_ts, up_ts);
-            //let lower = if up_ts >= interval.get_raw_end() {up_ts - interval.get_raw_end()} else {0};
-            //println!("          up_ts {} - b{} == {}", up_ts, interval.get_raw_end(), (up_ts as i64 - interval.get_raw_end() as i64));
-            let higher = if low_ts >= interval.get_raw_start() {low_ts - interval.get_raw_start()} else {0};
-            let out_of_bounds = interval.get_raw_start() > low_ts;
-            //println!("          low_ts {} - a{} == {}", low_ts, interval.get_raw_start(), (low_ts as i64 - interval.get_raw_start() as i64));
-
-            if !out_of_bounds {
-                match obs_seq.associated_lower_bound_ts(higher) {
+            if let Some(last_ts) = interval.past_window(low_ts).and_then(|window| window.last_ts()) {
+                match obs_seq.associated_lower_bound_ts(last_ts) {
                     Literal(b) => {
-                        //println!("              Literal @{}  {} a{}\n", tp, b, higher);
-                        let up = if interval.get_raw_start() == 0 {tp} else {b};
+                        //println!("              Literal @{}  {} a{}\n", tp, b, last_ts);
+                        let up = if interval.contains(0) {tp} else {b};
                         let vals : Vec<usize> = (0..up+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, up, true));
                         vals
                     }
                     InfinityIntervalsReturn::Interval(_a, b) => {
                         //println!("              Interval @{}  a{} b{}\n", tp, a, b);
-                        let up = if interval.get_raw_start() == 0 {tp} else {b};
+                        let up = if interval.contains(0) {tp} else {b};
                         let vals : Vec<usize> = (0..up+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, up, true));
                         vals
                     }
                     InfinityIntervalsReturn::Empty => {
                         //println!("              Interval Empty @{}\n", tp);
-                        if interval.get_raw_start() == 0 {
+                        if interval.contains(0) {
                             let vals : Vec<usize> = (0..tp+1).collect();
                             betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, tp, true));
                             vals
@@ -3084,7 +3312,7 @@ pub fn process_eventually_data_infinite(tuple: Vec<Constant>, tp: usize, interva
         }
         _ => {
             //println!("              Match Empty @{}", tp);
-            if interval.get_raw_start() == 0 {
+            if interval.contains(0) {
                 let vals : Vec<usize> = (0..tp+1).collect();
                 betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, 0, tp, true));
                 vals
@@ -3108,14 +3336,10 @@ pub fn process_once_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Constant
                 match obs_seq.associated_interval_tp(beta_tp) {
                     Literal(beta_ts) => {
                         //println!("          Literal case {:?}", rec);
-                        let lower_bound_ts = beta_ts + interval.get_raw_start();
-                        let upper_bound_ts = beta_ts + interval.get_raw_end();
-
-                        //println!("              low {} upper {}", lower_bound_ts, upper_bound_ts);
-                        match obs_seq.associated_interval_ts_exact(lower_bound_ts, upper_bound_ts) {
+                        match obs_seq.associated_window_exact(interval.future_window(beta_ts)) {
                             Literal(b) => {
                                 //println!("         Literal");
-                                if interval.get_raw_start() == 0 {
+                                if interval.contains(0) {
                                     //println!("            Literal case {} {}", beta_tp, b);
                                     let x = process_interval(val, beta_tp, b);
                                     if !x.is_empty() {
@@ -3135,7 +3359,7 @@ pub fn process_once_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Constant
                                 }
                             }
                             InfinityIntervalsReturn::Interval(a, b) => {
-                                let low = if interval.get_raw_start() == 0 { beta_tp } else { a };
+                                let low = if interval.contains(0) { beta_tp } else { a };
                                 let x = process_interval(val, low, b);
                                 if !x.is_empty() {
                                     //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3149,13 +3373,13 @@ pub fn process_once_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Constant
                     }
                     InfinityIntervalsReturn::Interval(low_ts, up_ts) => {
                         //println!("          Interval case {:?}", rec);
-                        let snd_lower_bound_ts = up_ts + interval.get_raw_start();
-                        let fst_upper_bound_ts = low_ts + interval.get_raw_end();
+                        // the windows of all timestamps from low_ts to up_ts
+                        let window = TimeInterval { end: interval.future_window(low_ts).end, ..interval.future_window(up_ts) };
 
-                        match obs_seq.associated_interval_ts_inexact(snd_lower_bound_ts, fst_upper_bound_ts) {
+                        match obs_seq.associated_window_inexact(window) {
                             Literal(b) => {
                                 //println!("              Literal case {} {}", beta_tp, b);
-                                if interval.get_raw_start() == 0 {
+                                if interval.contains(0) {
                                     let x = process_interval(val, beta_tp, b);
                                     if !x.is_empty() {
                                         //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3176,7 +3400,7 @@ pub fn process_once_time_finite(betas : &mut HashMap<usize, HashMap<Vec<Constant
                             }
                             InfinityIntervalsReturn::Interval(a, b) => {
                                 //println!("              Interval @{}  {} {}\n", beta_tp, a, b);
-                                let low = if interval.get_raw_start() == 0 { beta_tp } else { a };
+                                let low = if interval.contains(0) { beta_tp } else { a };
                                 let x = process_interval(val, low, b);
                                 if !x.is_empty() {
                                     //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3200,13 +3424,10 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
     return match obs_seq.associated_interval_tp(tp) {
         Literal(beta_ts) => {
             //println!("          Literal case {:?}", tuple);
-            let lower_bound_ts = beta_ts + interval.get_raw_start();
-            let upper_bound_ts = beta_ts + interval.get_raw_end();
-
-            match obs_seq.associated_interval_ts_exact(lower_bound_ts, upper_bound_ts) {
+            match obs_seq.associated_window_exact(interval.future_window(beta_ts)) {
                 Literal(b) => {
                     //println!("              Literal @{}  b{}\n", tp, b);
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         let vals : Vec<usize> = (tp..b+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, b, true));
                         vals
@@ -3217,7 +3438,7 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
                 }
                 InfinityIntervalsReturn::Interval(a, b) => {
                     //println!("              Interval @{}  a{} b{}\n", tp, a, b);
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         let vals : Vec<usize> = (tp..b+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, b, true));
                         vals
@@ -3229,7 +3450,7 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
                 }
                 _ => {
                     //println!("              Interval Empty @{}\n", tp);
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                         vec![tp]
                     } else {
@@ -3242,17 +3463,17 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
         // compute two intervals and get the ts of the overlapping interval and query for tps given the timepoints
         InfinityIntervalsReturn::Interval(low_ts, up_ts) => {
             //println!("          Interval case {} {} {:?}", low_ts, up_ts, tuple);
-            let snd_lower_bound_ts = up_ts + interval.get_raw_start();
-            let fst_upper_bound_ts = low_ts + interval.get_raw_end();
+            // the windows of all timestamps from low_ts to up_ts
+            let window = TimeInterval { end: interval.future_window(low_ts).end, ..interval.future_window(up_ts) };
 
             // the interval upper bound is not certain hence it cannot include the literal to the right
 
 
             //println!("{:?}", obs_seq.observations);
-            match obs_seq.associated_interval_ts_inexact(snd_lower_bound_ts, fst_upper_bound_ts) {
+            match obs_seq.associated_window_inexact(window) {
                 Literal(b) => {
-                    //println!("              Literal @{}  {} a{} b{}\n", tp, b, snd_lower_bound_ts, fst_upper_bound_ts);
-                    if interval.get_raw_start() == 0 {
+                    //println!("              Literal @{}  {} {}\n", tp, b, window);
+                    if interval.contains(0) {
                         let vals : Vec<usize> = (tp..b+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, b, true));
                         vals
@@ -3263,7 +3484,7 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
                 }
                 InfinityIntervalsReturn::Interval(a, b) => {
                     //println!("              Interval @{}  a{} b{}\n", tp, a, b);
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         let vals : Vec<usize> = (tp..b+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, b, true));
                         vals
@@ -3275,7 +3496,7 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
                 }
                 _ => {
                    // println!("              Interval Empty @{}\n", tp);
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                         vec![tp]
                     } else {
@@ -3287,7 +3508,7 @@ pub fn process_once_data_finite(tuple: Vec<Constant>, tp: usize, interval: TimeI
         }
         _ => {
             //println!("              Match Empty @{}", tp);
-            if interval.get_raw_start() == 0 {
+            if interval.contains(0) {
                 betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                 vec![tp]
             } else {
@@ -3307,10 +3528,10 @@ pub fn process_once_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<Consta
                 match obs_seq.associated_interval_tp(beta_tp) {
                     Literal(beta_ts) => {
                         //println!("Literal case");
-                        let lower_bound_ts = beta_ts + interval.get_raw_start();
+                        let lower_bound_ts = interval.future_window(beta_ts).first_ts();
                         match obs_seq.associated_lower_bound_ts(lower_bound_ts) {
                             Literal(a) => {
-                                if interval.get_raw_start() == 0 {
+                                if interval.contains(0) {
                                     //println!("  0 Literal low {beta_tp} highest {}", highest_tp);
                                     //println!("Val before {:?}", val);
                                     let x = process_interval(val, beta_tp, highest_tp);
@@ -3334,7 +3555,7 @@ pub fn process_once_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<Consta
                             }
                             InfinityIntervalsReturn::Interval(a, b) => {
                                 //println!("  Interval");
-                                let low = if interval.get_raw_start() == 0 { beta_tp } else { a };
+                                let low = if interval.contains(0) { beta_tp } else { a };
                                 let high = if highest_tp > b { highest_tp } else { b };
                                 let x = process_interval(val, low, high);
                                 if !x.is_empty() {
@@ -3346,7 +3567,7 @@ pub fn process_once_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<Consta
                             }
                             _ => {
                                 if highest_tp >= beta_tp {
-                                    if interval.get_raw_start() == 0 {
+                                    if interval.contains(0) {
                                         let x = process_interval(val, beta_tp, highest_tp);
                                         if !x.is_empty() {
                                             //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3363,12 +3584,12 @@ pub fn process_once_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<Consta
                     // compute two intervals and get the ts of the overlapping interval and query for tps given the timepoints
                     InfinityIntervalsReturn::Interval(_low_ts, up_ts) => {
                         //println!("Interval case");
-                        let snd_lower_bound_ts = up_ts + interval.get_raw_start();
+                        let snd_lower_bound_ts = interval.future_window(up_ts).first_ts();
                         // compute potential intersection
                         match obs_seq.associated_lower_bound_ts(snd_lower_bound_ts) {
                             Literal(a) => {
                                 //println!("  Literal");
-                                let low = if interval.get_raw_start() == 0 { beta_tp } else { a };
+                                let low = if interval.contains(0) { beta_tp } else { a };
                                 let x = process_interval(val, low, highest_tp);
                                 if !x.is_empty() {
                                     //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3379,7 +3600,7 @@ pub fn process_once_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<Consta
                             }
                             InfinityIntervalsReturn::Interval(a, b) => {
                                 //println!("  Interval");
-                                let low = if interval.get_raw_start() == 0 { beta_tp } else { a };
+                                let low = if interval.contains(0) { beta_tp } else { a };
                                 let high = if highest_tp > b { highest_tp } else { b };
                                 let x = process_interval(val, low, high);
                                 if !x.is_empty() {
@@ -3391,7 +3612,7 @@ pub fn process_once_time_infinite(betas : &mut HashMap<usize, HashMap<Vec<Consta
                             }
                             _ => {
                                 if highest_tp >= beta_tp {
-                                    if interval.get_raw_start() == 0 {
+                                    if interval.contains(0) {
                                         let x = process_interval(val, beta_tp, highest_tp);
                                         if !x.is_empty() {
                                             //println!("        @{beta_tp} {:?}  {:?}", rec.clone(), x.clone());
@@ -3417,11 +3638,11 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
     return match obs_seq.associated_interval_tp(tp) {
         Literal(beta_ts) => {
             //println!("Literal Case");
-            let lower_bound_ts = beta_ts + interval.get_raw_start();
+            let lower_bound_ts = interval.future_window(beta_ts).first_ts();
             //println!("Lower bound {}", lower_bound_ts);
             match obs_seq.associated_lower_bound_ts(lower_bound_ts) {
                 Literal(a) => {
-                    let low = if interval.get_raw_start() == 0 {tp} else {a};
+                    let low = if interval.contains(0) {tp} else {a};
                     let vals : Vec<usize> = (low..highest_tp_ts+1).collect();
                     //println!("  Literal tp@{}  a{}", low, highest_tp_ts);
                     //println!("  {:?}", (tp, low, highest_tp_ts, true));
@@ -3429,7 +3650,7 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
                     vals
                 }
                 InfinityIntervalsReturn::Interval(a, b) => {
-                    let low = if interval.get_raw_start() == 0 {tp} else {a};
+                    let low = if interval.contains(0) {tp} else {a};
                     let up = if highest_tp_ts > b {highest_tp_ts} else {b};
                     let vals: Vec<usize> = (low..up+1).collect();
                     //println!("  Interval tp@{}  low {} up {}", tp, low, up);
@@ -3438,7 +3659,7 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
                 }
                 InfinityIntervalsReturn::Empty => {
                     //println!("  Empty");
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         let vals : Vec<usize> = (tp..highest_tp_ts+1).collect();
                         betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, highest_tp_ts, true));
                         //println!("  Empty tp@{}  low {} up {}", tp, tp, highest_tp_ts);
@@ -3453,12 +3674,12 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
         // compute two intervals and get the ts of the overlapping interval and query for tps given the timepoints
         InfinityIntervalsReturn::Interval(_low_ts, up_ts) => {
             //println!("Interval Case");
-            let snd_lower_bound_ts = up_ts + interval.get_raw_start();
+            let snd_lower_bound_ts = interval.future_window(up_ts).first_ts();
             // compute potential intersection
             match obs_seq.associated_lower_bound_ts(snd_lower_bound_ts) {
                 Literal(a) => {
                     //println!("  Literal tp@{}  a{}", tp, a);
-                    let low = if interval.get_raw_start() == 0 {tp} else {a};
+                    let low = if interval.contains(0) {tp} else {a};
                     let vals : Vec<usize> = (low..highest_tp_ts+1).collect();
                     //println!("  Literal tp@{}  low {} up {}", tp, low, highest_tp_ts);
                     betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, low, highest_tp_ts, true));
@@ -3466,7 +3687,7 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
                 }
                 InfinityIntervalsReturn::Interval(a, b) => {
                     //println!("  Interval tp@{}  a{} b{}", tp, a, b);
-                    let low = if interval.get_raw_start() == 0 {tp} else {a};
+                    let low = if interval.contains(0) {tp} else {a};
                     let up= if highest_tp_ts > b {highest_tp_ts} else {b};
                     let vals: Vec<usize> = (low..up+1).collect();
                     //println!("  Interval tp@{}  low {} up {}", tp, low, up);
@@ -3482,7 +3703,7 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
         }
         _ => {
             //println!("Infinity Case");
-            if interval.get_raw_start() == 0 {
+            if interval.contains(0) {
                 betas.entry(tp).or_insert_with(|| HashMap::new()).insert(tuple, (tp, tp, tp, true));
                 vec![tp]
             } else {
@@ -3494,6 +3715,90 @@ pub fn process_once_data_infinite(tuple: Vec<Constant>, tp: usize, highest_tp_ts
 }
 
 
+// valuation -> stretches (first tp, last tp) of consecutive complete tps at which it held
+pub(crate) type ValuationRuns = HashMap<Vec<Constant>, VecDeque<(usize, usize)>>;
+
+// Extends the stretches of all valuations that hold at tp. A stretch only continues if it ended at
+// the previous complete tp, otherwise a new one starts at tp.
+pub(crate) fn extend_valuation_runs(runs: &mut ValuationRuns, satisfied: HashSet<Vec<Constant>>, tp: usize, previous_tp: Option<usize>) {
+    for tuple in satisfied {
+        let stretches = runs.entry(tuple).or_insert_with(|| VecDeque::with_capacity(2));
+        match stretches.back_mut() {
+            Some((_, end)) if Some(*end) == previous_tp => *end = tp,
+            _ => stretches.push_back((tp, tp)),
+        }
+    }
+}
+
+// Drops all stretches that end before the lowest tp any future window can contain.
+pub(crate) fn prune_valuation_runs(runs: &mut ValuationRuns, lowest_tp: usize) {
+    runs.retain(|_, stretches| {
+        while let Some((_, end)) = stretches.front() {
+            if *end < lowest_tp {
+                stretches.pop_front();
+            } else {
+                break;
+            }
+        }
+        !stretches.is_empty()
+    });
+}
+
+pub(crate) fn holds_throughout(stretches: &VecDeque<(usize, usize)>, lower: usize, upper: usize) -> bool {
+    stretches.iter().any(|(start, end)| *start <= lower && upper <= *end)
+}
+
+// Lowest and highest complete tp within the past window of (tp, ts), None if the window is empty.
+pub(crate) fn historically_window(tp_to_ts: &BTreeMap<usize, usize>, tp: usize, ts: usize, interval: TimeInterval) -> Option<(usize, usize)> {
+    let mut window = None;
+    for (j, ts_j) in tp_to_ts.range(..tp + 1).rev() {
+        let distance = ts.saturating_sub(*ts_j);
+        if !interval.above_start(distance) {
+            continue;
+        }
+        if !interval.below_end(distance) {
+            break;
+        }
+        window = match window {
+            None => Some((*j, *j)),
+            Some((_, upper)) => Some((*j, upper)),
+        };
+    }
+    window
+}
+
+// Lowest and highest complete tp within the future window of tp, None if the window is empty.
+pub(crate) fn always_window(tp_to_ts: &BTreeMap<usize, usize>, tp: usize, interval: TimeInterval) -> Option<(usize, usize)> {
+    let ts = *tp_to_ts.get(&tp)?;
+    let mut window = None;
+    for (j, ts_j) in tp_to_ts.range(tp..) {
+        let distance = ts_j.saturating_sub(ts);
+        if !interval.above_start(distance) {
+            continue;
+        }
+        if !interval.below_end(distance) {
+            break;
+        }
+        window = match window {
+            None => Some((*j, *j)),
+            Some((lower, _)) => Some((lower, *j)),
+        };
+    }
+    window
+}
+
+fn always_verdicts(runs: &ValuationRuns, tp_to_ts: &BTreeMap<usize, usize>, tp: usize, interval: TimeInterval, vacuous: bool) -> Vec<Record> {
+    match always_window(tp_to_ts, tp, interval) {
+        Some((lower, upper)) => runs
+            .iter()
+            .filter(|(_, stretches)| holds_throughout(stretches, lower, upper))
+            .map(|(valuation, _)| Data(true, valuation.clone()))
+            .collect(),
+        None if vacuous => vec![Data(true, vec![])],
+        None => Vec::new(),
+    }
+}
+
 pub(crate) fn compute_satisfactions(alpha_ds: &mut PartialSequence, time_table: &HashMap<usize, usize>, time_interval: TimeInterval, beta_or_alpha : bool, record: Vec<Constant>, ) -> Vec<(usize, Vec<Constant>)> {
     let out = if beta_or_alpha {
         alpha_ds.single_output(record)
@@ -3749,7 +4054,227 @@ pub(crate) fn var_equals_val(tuple: &Vec<Constant>, val: &Arg, indices: &Vec<usi
     all_equal
 }
 
-fn format_record(rec: Vec<Constant>) -> String {
+// Verdicts are pushed to the socket sink if one is set and printed to stdout otherwise
+fn output_writer(options: &OperatorOptions) -> Box<dyn Write> {
+    match options.get_output_sink() {
+        Some(sink) => Box::new(sink),
+        None => Box::new(io::stdout()),
+    }
+}
+
+// Writes the satisfactions and unknown verdicts in batches of batch_size lines. Verdicts of time
+// points without a timestamp yet are stashed until it arrives, and the last incomplete batch is
+// written once both inputs are exhausted.
+pub(crate) fn batched_output<G: Scope<Timestamp = usize>>(stream: &Stream<G, Record>, time_stream: &Stream<G, TimeFlowValues>, batch_size: usize, mut writer: Box<dyn Write>) -> Stream<G, Record> {
+    let mut stash: Vec<(usize, Record)> = Vec::with_capacity(8);
+    let mut out_stash = String::new();
+    let mut counter = 0;
+    let mut tp_to_ts = HashMap::with_capacity(8);
+    let mut data_vec = Vec::new();
+    let mut time_vec = Vec::new();
+
+    stream.binary_frontier(time_stream, Pipeline, Pipeline, "Stdout", move |_, _| {
+        move |data_input, time_input, _output| {
+            time_input.for_each(|t, data| {
+                data.swap(&mut time_vec);
+                for rec in time_vec.drain(..) {
+                    if let FTimestamp(x) = rec {
+                        tp_to_ts.entry(*t.time()).or_insert(x);
+                    }
+                }
+
+                if !stash.is_empty() {
+                    stash.retain(|(tp, rec)| {
+                        if let Some(ts) = tp_to_ts.get(tp) {
+                            write!(writer, "{}", verdict_line(*ts, *tp, rec.clone())).expect("Unable to write output");
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+            });
+
+            data_input.for_each(|time, data| {
+                data.swap(&mut data_vec);
+                for rec in data_vec.drain(..) {
+                    if let Data(true, _) | Unknown(_) = rec {
+                        if let Some(ts) = tp_to_ts.get(time.time()) {
+                            counter += 1;
+                            out_stash.push_str(&verdict_line(*ts, *time.time(), rec));
+                            if counter >= batch_size {
+                                writeln!(writer, "{}", out_stash).expect("Unable to write output");
+                                counter = 0;
+                                out_stash.clear();
+                            }
+                        } else {
+                            stash.push((*time.time(), rec))
+                        }
+                    }
+                }
+            });
+
+            if data_input.frontier().is_empty() && time_input.frontier().is_empty() && !out_stash.is_empty() {
+                writeln!(writer, "{}", out_stash).expect("Unable to write output");
+                counter = 0;
+                out_stash.clear();
+            }
+            writer.flush().expect("Unable to write output");
+        }
+    })
+}
+
+// The output line of a satisfaction or unknown verdict, empty for any other record
+fn verdict_line(ts: usize, tp: usize, rec: Record) -> String {
+    match rec {
+        Data(true, arg) => format!("@{} (time point {}): {}\n", ts, tp, format_record(arg)),
+        Unknown(arg) => format!("@{} (time point {}): unknown {}\n", ts, tp, format_record(arg)),
+        _ => String::new(),
+    }
+}
+
+// Collects the output on worker 0 and hands the records of the time points the frontier has passed
+// to complete_tps, in increasing time point order. Only time points that are not yet complete are
+// buffered. The ingestion bounds their number with its Backpressure as long as they complete without
+// more input, but a time point that never completes (e.g. an input without watermarks that does not
+// end) holds back the output of all later time points in memory on worker 0.
+fn ordered_time_points<G, F>(stream: &Stream<G, Record>, time_stream: &Stream<G, TimeFlowValues>, name: &str, mut complete_tps: F) -> Stream<G, Record>
+where
+    G: Scope<Timestamp = usize>,
+    F: FnMut(Vec<(usize, usize, Vec<Record>)>) + 'static,
+{
+    let worker_index = stream.scope().index();
+    let mut records: HashMap<usize, Vec<Record>> = HashMap::with_capacity(8);
+    let mut tp_to_ts: HashMap<usize, usize> = HashMap::with_capacity(8);
+    let mut data_vec = Vec::new();
+    let mut time_vec = Vec::new();
+
+    stream.binary_frontier(time_stream, PactExchange::new(|_: &Record| 0u64), Pipeline, name, move |_, _| {
+        let mut notificator = FrontierNotificator::new();
+        move |data_input, time_input, _output| {
+            time_input.for_each(|time, data| {
+                data.swap(&mut time_vec);
+                for rec in time_vec.drain(..) {
+                    if let FTimestamp(ts) = rec {
+                        tp_to_ts.entry(*time.time()).or_insert(ts);
+                        notificator.notify_at(time.delayed(time.time()));
+                    }
+                }
+            });
+
+            data_input.for_each(|time, data| {
+                data.swap(&mut data_vec);
+                for rec in data_vec.drain(..) {
+                    if let Data(_, _) | Unknown(_) = rec {
+                        records.entry(*time.time()).or_default().push(rec);
+                    }
+                }
+            });
+
+            let mut completed = Vec::new();
+            notificator.for_each(&[data_input.frontier(), time_input.frontier()], |time, _| completed.push(*time.time()));
+            completed.sort();
+            let mut complete = Vec::with_capacity(completed.len());
+            for tp in completed {
+                let tp_records = records.remove(&tp).unwrap_or_default();
+                if let Some(ts) = tp_to_ts.remove(&tp) {
+                    complete.push((tp, ts, tp_records));
+                }
+            }
+            if worker_index == 0 && !complete.is_empty() {
+                complete_tps(complete);
+            }
+        }
+    })
+}
+
+// Hands the records of every complete time point to write_tp in increasing time point order and
+// flushes the writer after each batch of complete time points.
+fn ordered_output<G, F>(stream: &Stream<G, Record>, time_stream: &Stream<G, TimeFlowValues>, output_sink: Option<SocketSink>, output_file: Option<String>, mut write_tp: F) -> Stream<G, Record>
+where
+    G: Scope<Timestamp = usize>,
+    F: FnMut(&mut dyn Write, usize, usize, Vec<Record>) -> io::Result<()> + 'static,
+{
+    let mut writer: Box<dyn Write> = match (stream.scope().index(), output_sink, output_file) {
+        (_, Some(sink), _) => Box::new(sink),
+        (0, None, Some(file_name)) => Box::new(BufWriter::new(File::create(file_name).expect("Unable to create output file"))),
+        _ => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    ordered_time_points(stream, time_stream, "OrderedOutput", move |complete| {
+        for (tp, ts, tp_records) in complete {
+            write_tp(&mut writer, tp, ts, tp_records).expect("Unable to write output");
+        }
+        writer.flush().expect("Unable to write output");
+    })
+}
+
+// Collects the first violations of the verdict limit in (time point, tuple) order and raises its
+// stop flag once the limit is reached.
+pub(crate) fn verdict_limit<G: Scope<Timestamp = usize>>(stream: &Stream<G, Record>, time_stream: &Stream<G, TimeFlowValues>, limit: VerdictLimit) -> Stream<G, Record> {
+    ordered_time_points(stream, time_stream, "VerdictLimit", move |complete| {
+        let mut violations = limit.violations.lock().unwrap();
+        for (tp, ts, mut tp_records) in complete {
+            tp_records.sort();
+            tp_records.dedup();
+            for rec in tp_records {
+                if violations.len() >= limit.limit {
+                    break;
+                }
+                if let Data(true, tuple) = rec {
+                    violations.push((tp, ts, tuple));
+                }
+            }
+        }
+        if violations.len() >= limit.limit {
+            limit.stop.store(true, Ordering::SeqCst);
+        }
+    })
+}
+
+// Writes the satisfying and unknown tuples of a complete time point in tuple order.
+pub(crate) fn write_satisfactions(writer: &mut dyn Write, tp: usize, ts: usize, mut records: Vec<Record>) -> io::Result<()> {
+    records.sort();
+    records.dedup();
+    // satisfactions before unknown verdicts, which sort after them
+    for rec in records {
+        match rec {
+            Data(true, tuple) => writeln!(writer, "@{} (time point {}): {}", ts, tp, format_record(tuple))?,
+            Unknown(tuple) => writeln!(writer, "@{} (time point {}): unknown {}", ts, tp, format_record(tuple))?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Writes the verdicts of a complete time point. Closed policies get exactly one verdict per time
+// point, open policies one line per satisfying or unknown valuation.
+pub(crate) fn write_verdicts(writer: &mut dyn Write, tp: usize, ts: usize, no_free_vars: bool, mut records: Vec<Record>) -> io::Result<()> {
+    records.sort();
+    records.dedup();
+    if no_free_vars {
+        let verdict = if records.iter().any(|rec| matches!(rec, Data(true, _))) {
+            "true"
+        } else if records.iter().any(|rec| matches!(rec, Unknown(_))) {
+            "unknown"
+        } else {
+            "false"
+        };
+        writeln!(writer, "@{} (time point {}): {}", ts, tp, verdict)?;
+    } else {
+        // satisfactions before unknown verdicts, which sort after them
+        for rec in records {
+            match rec {
+                Data(true, tuple) => writeln!(writer, "@{} (time point {}): true {}", ts, tp, format_record(tuple))?,
+                Unknown(tuple) => writeln!(writer, "@{} (time point {}): unknown {}", ts, tp, format_record(tuple))?,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn format_record(rec: Vec<Constant>) -> String {
     let res = if rec.is_empty() {
         let res = "()".to_string();
         res
@@ -3825,60 +4350,13 @@ pub(crate) fn compute_alpha_satisfactions(alpha_ds: &mut PartialSequence, time_t
 }
 
 pub(crate) fn mem_until(beta_ts: usize, alpha_ts: usize, time_interval: TimeInterval) -> bool {
-    let mut is_left_infinite = false;
-    let mut a = 0;
-    let mut is_right_infinite = false;
-    let mut b = 0;
-
-    match time_interval.get_start() {
-        None => is_left_infinite = true,
-        Some(x) => a = x,
-    }
-
-    match time_interval.get_end() {
-        None => is_right_infinite = true,
-        Some(x) => b = x,
-    }
-
-    if !is_right_infinite && !is_left_infinite {
-        return alpha_ts + a <= beta_ts && alpha_ts + b >= beta_ts;
-    }
-
-    if is_right_infinite {
-        // [a, inf) interval
-        return alpha_ts + a <= beta_ts
-    }
-
-    return false
+    // beta_ts - alpha_ts in the interval, which honours open bounds
+    alpha_ts <= beta_ts && time_interval.contains(beta_ts - alpha_ts)
 }
 
 pub(crate) fn mem_since(beta_ts: usize, alpha_ts: usize, time_interval: TimeInterval) -> bool {
-    let mut is_left_infinite = false;
-    let mut a = 0;
-    let mut is_right_infinite = false;
-    let mut b = 0;
-
-    match time_interval.get_start() {
-        None => is_left_infinite = true,
-        Some(x) => a = x,
-    }
-
-    match time_interval.get_end() {
-        None => is_right_infinite = true,
-        Some(x) => b = x,
-    }
-
-    if !is_right_infinite && !is_left_infinite {
-        // [a, b] interval
-        return beta_ts + a <= alpha_ts && alpha_ts <= beta_ts + b
-    }
-
-    if is_right_infinite && !(a > alpha_ts) {
-        // [a, inf) interval
-        return beta_ts <= alpha_ts - a
-    }
-
-    return false
+    // alpha_ts - beta_ts in the interval, which honours open bounds
+    beta_ts <= alpha_ts && time_interval.contains(alpha_ts - beta_ts)
 }
 
 // for since and until
@@ -3910,7 +4388,7 @@ pub(crate) fn process_beta(
     let mut satisfied = Vec::with_capacity(1 + res);
 
     // check if interval includes bound
-    if match interval.get_start() { None => false, Some(x) => x == 0} {
+    if interval.contains(0) {
         satisfied.push((tp, tuple.clone()));
     };
 
@@ -224,7 +224,7 @@ impl SatisfactionDs for PartialSequence {
         };
 
         // element already exists
-        if index == 0 {
+        if index == 0 || compare(&self.satisfactions[index], time_point) == 0 {
             return;
         }
 
@@ -349,10 +349,7 @@ impl SatisfactionDs for PartialSequence {
                 let bound_ts = *tp_to_ts.entry(sub.0).or_default();
 
                 let res = if mode {
-                    match interval.get_end() {
-                        None => false,
-                        Some(b) => (bound_ts as i64) < (current_ts as i64) - (b as i64)
-                    }
+                    bound_ts < current_ts && !interval.below_end(current_ts - bound_ts)
                 } else {
                     bound_ts < current_ts
                 };
@@ -733,6 +730,38 @@ mod test_ds {
         }
     }
 
+    #[test]
+    fn insert_duplicates() {
+        let correct = vec![Literal(0), Interval(3,6), Literal(9)];
+
+        let items = [0,3,4,5,6,9];
+        let mut ds = PartialSequence::empty(false);
+        for i in items.iter() {
+            ds.insert(*i);
+        }
+        for i in items.iter() {
+            ds.insert(*i);
+            assert_eq!(ds.satisfactions, correct);
+        }
+    }
+
+    #[test]
+    fn insert_duplicates_in_any_order() {
+        let correct = vec![Literal(0), Literal(2), Interval(4,6), Literal(9)];
+
+        let items = [0,2,4,5,6,9];
+        for perm in items.iter().permutations(items.len()) {
+            let mut ds = PartialSequence::empty(true);
+            let mut ds1 = PartialSequence::empty(false);
+            for i in perm.iter().chain(perm.iter().rev()) {
+                ds.insert(**i);
+                ds1.insert(**i);
+            }
+            assert_eq!(ds.satisfactions, correct);
+            assert_eq!(ds1.satisfactions, correct);
+        }
+    }
+
     #[test]
     fn insert_property_in_order_gap_6() {
         let correct = vec![Interval(0,2), Interval(4,6)];
@@ -1,5 +1,10 @@
 use std::fmt::{Formatter, self};
-use parser::formula_syntax_tree::Constant;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use parser::formula_parser::parse_formula;
+use parser::formula_syntax_tree::{Arg, Constant, Formula};
+use stream_io::socket_sink::SocketSink;
 
 #[derive(Abomonation, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FlowValues {
@@ -7,6 +12,9 @@ pub enum FlowValues {
     // meta data is bool, bool; first indicates if it is a timestamp (true) or end of stream (false),
     // second bool is either true for True stream or false for False false stream
     MetaData(bool, bool),
+    // tuple whose verdict is still pending at the end of the stream, only emitted at the output of
+    // unbounded future operators
+    Unknown(Vec<Constant>),
     // Output(ts, data)
 }
 
@@ -18,6 +26,115 @@ pub enum TimeFlowValues {
 
 pub type Record = FlowValues;
 
+// Events of the data stream. Events in a text format are parsed by the base streams, typed events
+// are matched against the predicates as they are.
+pub trait InputEvent: timely::ExchangeData + Hash + Eq {
+    // marks the end of the stream
+    fn eos() -> Self;
+
+    fn is_eos(&self) -> bool;
+
+    // the fact (or Eos) that the event stands for
+    fn to_fact(&self) -> Formula;
+
+    // the event as JSON text, only events in a text format can be queried
+    fn json(&self) -> Option<&str>;
+}
+
+impl InputEvent for String {
+    fn eos() -> Self {
+        "<eos>".to_string()
+    }
+
+    fn is_eos(&self) -> bool {
+        self == "<eos>"
+    }
+
+    fn to_fact(&self) -> Formula {
+        parse_formula(self)
+    }
+
+    fn json(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+// An event whose arguments are already typed, e.g. read from a binary log or a table. JSON events
+// are kept as text for the JSON queries.
+#[derive(Abomonation, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TypedEvent {
+    Fact(String, Vec<Constant>),
+    Json(String),
+    Eos,
+}
+
+impl InputEvent for TypedEvent {
+    fn eos() -> Self {
+        TypedEvent::Eos
+    }
+
+    fn is_eos(&self) -> bool {
+        matches!(self, TypedEvent::Eos)
+    }
+
+    fn to_fact(&self) -> Formula {
+        match self {
+            TypedEvent::Fact(name, args) => Formula::Fact(name.clone(), args.iter().cloned().map(Arg::Cst).collect()),
+            // no fact, which the base streams ignore
+            TypedEvent::Json(_) => Formula::True,
+            TypedEvent::Eos => Formula::Eos,
+        }
+    }
+
+    fn json(&self) -> Option<&str> {
+        match self {
+            TypedEvent::Json(json) => Some(json),
+            _ => None,
+        }
+    }
+}
+
+// Condition under which a valuation that is not yet satisfied by an unbounded future operator can
+// still become satisfied after the end of the stream. The indices locate the lhs variables in the
+// output tuple.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PendingCondition {
+    Eventually,
+    Until(Vec<usize>),
+    NegUntil(Vec<usize>),
+}
+
+// violation at (time point, timestamp) with its tuple
+pub type Violation = (usize, usize, Vec<Constant>);
+
+// Stops the monitor after the first `limit` violations (satisfying valuations of the policy). The
+// violations are collected in (time point, tuple) order as (tp, ts, tuple) and the stop flag is
+// shared with the ingestion, which ends the stream once it is raised.
+#[derive(Clone, Debug)]
+pub struct VerdictLimit {
+    pub(crate) limit: usize,
+    pub(crate) stop: Arc<AtomicBool>,
+    pub(crate) violations: Arc<Mutex<Vec<Violation>>>,
+}
+
+impl VerdictLimit {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            stop: Arc::new(AtomicBool::new(false)),
+            violations: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn reached(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    pub fn get_violations(&self) -> Vec<Violation> {
+        self.violations.lock().unwrap().clone()
+    }
+}
+
 pub struct Profiling {
     pub(crate) op_name: String,
     pub(crate) worker_id: u64,
@@ -44,7 +161,10 @@ pub struct OperatorOptions {
     step: usize,
     output_mode: usize,
     output_file: Option<String>,
-    output_batch: usize
+    output_batch: usize,
+    unknown_at_eos: bool,
+    verdict_limit: Option<VerdictLimit>,
+    output_sink: Option<SocketSink>
 }
 
 impl OperatorOptions {
@@ -56,7 +176,10 @@ impl OperatorOptions {
             step: 1000,
             output_mode: 1,
             output_file: None,
-            output_batch: 1
+            output_batch: 1,
+            unknown_at_eos: false,
+            verdict_limit: None,
+            output_sink: None
         }
     }
 
@@ -67,7 +190,10 @@ impl OperatorOptions {
             step: self.step,
             output_mode: self.output_mode,
             output_file: self.output_file.clone(),
-            output_batch: self.output_batch
+            output_batch: self.output_batch,
+            unknown_at_eos: self.unknown_at_eos,
+            verdict_limit: self.verdict_limit.clone(),
+            output_sink: self.output_sink.clone()
         }
     }
 
@@ -96,6 +222,21 @@ impl OperatorOptions {
         self.output_file.clone()
     }
 
+    // Unbounded future operators report pending valuations as unknown at the end of the stream
+    // instead of applying finite trace semantics
+    pub fn get_unknown_at_eos(&self) -> bool {
+        self.unknown_at_eos
+    }
+
+    pub fn get_verdict_limit(&self) -> Option<VerdictLimit> {
+        self.verdict_limit.clone()
+    }
+
+    // Socket the output is pushed to instead of stdout or the output file
+    pub fn get_output_sink(&self) -> Option<SocketSink> {
+        self.output_sink.clone()
+    }
+
     // Setters
     pub fn set_workers(&mut self, workers: usize) {
         self.workers = workers;
@@ -120,4 +261,16 @@ impl OperatorOptions {
     pub fn set_output_file(&mut self, output_file: Option<String>) {
         self.output_file = output_file;
     }
+
+    pub fn set_unknown_at_eos(&mut self, unknown_at_eos: bool) {
+        self.unknown_at_eos = unknown_at_eos;
+    }
+
+    pub fn set_verdict_limit(&mut self, verdict_limit: Option<VerdictLimit>) {
+        self.verdict_limit = verdict_limit;
+    }
+
+    pub fn set_output_sink(&mut self, output_sink: Option<SocketSink>) {
+        self.output_sink = output_sink;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,413 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use timely;
+use timely::dataflow::operators::capture::Extract;
+use timely::dataflow::operators::{Capture, UnorderedInput};
+
+use dataflow_constructor::types::FlowValues::Data;
+use dataflow_constructor::types::TimeFlowValues::{Timestamp, EOS};
+use dataflow_constructor::types::{default_options, TimeFlowValues};
+use parser::binary_log::read_csv_log;
+use parser::csv_parser::{parse_lines_to_segments, parse_verimon_results, Segment};
+use parser::formula_syntax_tree::Constant;
+use stream_io::reorder::{delay, log_events, reverse, shuffle_epochs, with_watermarks, DelayDistribution};
+use {create_dataflow, parse_formula};
+
+// lines of a diff that are reported, the rest is counted
+const MAX_DIFF_LINES: usize = 20;
+
+/// Satisfying valuations of each time point with at least one, sorted and without duplicates.
+pub type Verdicts = BTreeMap<usize, Vec<Vec<Constant>>>;
+
+/// End-to-end test cases, read from a JSON manifest
+/// ```json
+/// { "cases": [ { "name": "once", "policy": "ONCE[0,2] A(x)", "trace": "once.csv", "expected": "once.out",
+///                "workers": [1, 4], "orderings": ["in-order", "reverse"], "columns": ["x"] } ] }
+/// ```
+/// Paths are relative to the manifest.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub cases: Vec<Case>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Case {
+    pub name: String,
+    /// The policy, or a file containing it
+    #[serde(default)]
+    pub policy: Option<String>,
+    #[serde(default)]
+    pub policy_file: Option<PathBuf>,
+    /// Trace in the CSV event format
+    pub trace: PathBuf,
+    /// Verdicts in the MonPoly format `@ts (time point tp): (1,2) (3,4)`
+    pub expected: PathBuf,
+    #[serde(default = "default_workers")]
+    pub workers: Vec<usize>,
+    #[serde(default = "default_orderings")]
+    pub orderings: Vec<Ordering>,
+    /// Free variables in the order of the columns of the expected verdicts, the order of the
+    /// dataflow's output otherwise
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Time points per epoch of `reverse` and `shuffle`, the whole trace otherwise
+    #[serde(default)]
+    pub epoch: Option<usize>,
+    /// Largest delay of `delay` in time points
+    #[serde(default = "default_max_delay")]
+    pub max_delay: usize,
+    #[serde(default)]
+    pub seed: usize,
+    /// Seconds after which a run counts as stuck
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+/// Order in which the events of the trace are fed to the monitor, always with correct watermarks.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ordering {
+    /// Time point order
+    InOrder,
+    /// Order of the trace file
+    File,
+    Reverse,
+    Shuffle,
+    /// Every event delayed by up to `max_delay` time points
+    Delay,
+}
+
+impl fmt::Display for Ordering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Ordering::InOrder => "in-order",
+            Ordering::File => "file",
+            Ordering::Reverse => "reverse",
+            Ordering::Shuffle => "shuffle",
+            Ordering::Delay => "delay",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn default_workers() -> Vec<usize> {
+    vec![1]
+}
+
+fn default_orderings() -> Vec<Ordering> {
+    vec![Ordering::InOrder]
+}
+
+fn default_max_delay() -> usize {
+    2
+}
+
+fn default_timeout() -> u64 {
+    300
+}
+
+/// Outcome of one case with one number of workers and ordering: a description of the
+/// differences or of the failure if it did not pass.
+#[derive(Clone, Debug)]
+pub struct Run {
+    pub case: String,
+    pub workers: usize,
+    pub ordering: Ordering,
+    pub result: Result<(), String>,
+}
+
+impl Manifest {
+    /// Reads a manifest and makes the paths of its cases relative to the current directory.
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+        let mut manifest: Manifest = serde_json::from_str(&text).map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for case in manifest.cases.iter_mut() {
+            case.policy_file = case.policy_file.as_ref().map(|p| dir.join(p));
+            case.trace = dir.join(&case.trace);
+            case.expected = dir.join(&case.expected);
+        }
+        Ok(manifest)
+    }
+}
+
+impl Case {
+    pub fn load_policy(&self) -> Result<String, String> {
+        match (&self.policy, &self.policy_file) {
+            (Some(policy), None) => Ok(policy.clone()),
+            (None, Some(path)) => fs::read_to_string(path)
+                .map(|policy| policy.trim().to_string())
+                .map_err(|e| format!("Unable to read {}: {}", path.display(), e)),
+            _ => Err("Expected either a policy or a policy_file".to_string()),
+        }
+    }
+
+    /// Runs the case with every number of workers and ordering.
+    pub fn run(&self) -> Vec<Run> {
+        let mut runs = Vec::new();
+        let prepared = self.load_policy().and_then(|policy| self.load_expected().map(|expected| (policy, expected)));
+        for &workers in self.workers.iter() {
+            for &ordering in self.orderings.iter() {
+                let result = match prepared {
+                    Ok((ref policy, ref expected)) => self.run_once(policy, expected, workers, ordering),
+                    Err(ref e) => Err(e.clone()),
+                };
+                runs.push(Run { case: self.name.clone(), workers, ordering, result });
+            }
+        }
+        runs
+    }
+
+    fn load_expected(&self) -> Result<Verdicts, String> {
+        if !self.expected.is_file() {
+            return Err(format!("Missing expected verdicts {}", self.expected.display()));
+        }
+        Ok(normalize(parse_verimon_results(self.expected.display().to_string(), (false, 0))))
+    }
+
+    fn run_once(&self, policy: &str, expected: &Verdicts, workers: usize, ordering: Ordering) -> Result<(), String> {
+        let events = read_csv_log(&self.trace)
+            .and_then(log_events)
+            .map_err(|e| format!("Unable to read {}: {}", self.trace.display(), e))?;
+        let events = match ordering {
+            Ordering::InOrder => {
+                let mut events = events;
+                events.sort_by_key(|e| e.tp);
+                events
+            }
+            Ordering::File => events,
+            Ordering::Reverse => reverse(events, self.epoch),
+            Ordering::Shuffle => shuffle_epochs(events, self.epoch, self.seed),
+            Ordering::Delay => {
+                let distribution = DelayDistribution { mean: 0.0, std_dev: self.max_delay as f64, max_delay: self.max_delay };
+                delay(events, distribution, self.seed)
+            }
+        };
+        if events.is_empty() {
+            return Err(format!("Empty trace {}", self.trace.display()));
+        }
+        let end = events.iter().map(|e| e.tp).max().unwrap() + 1;
+        let segments = parse_lines_to_segments(with_watermarks(&events, Some(1)).map_err(|e| e.to_string())?.into_iter());
+
+        let (attrs, actual) = monitor(policy, segments, end, workers, Duration::from_secs(self.timeout))?;
+        let actual = match self.columns {
+            Some(ref columns) => reorder_columns(actual, &attrs, columns)?,
+            None => actual,
+        };
+        match diff(expected, &actual) {
+            None => Ok(()),
+            Some(diff) => Err(diff),
+        }
+    }
+}
+
+/// Runs the dataflow over the segments and returns its attributes and verdicts, or why it failed
+/// if a worker panicked or it did not terminate within the timeout. `end` is the time point of
+/// the end of the stream.
+pub fn monitor(policy: &str, segments: Vec<Segment>, end: usize, workers: usize, timeout: Duration) -> Result<(Vec<String>, Verdicts), String> {
+    let (send, recv) = mpsc::channel();
+    let (attrs_send, attrs_recv) = mpsc::channel();
+    let (done_send, done_recv) = mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+    let attrs_send = Arc::new(Mutex::new(attrs_send));
+    let segments = Arc::new(Mutex::new(Some(segments)));
+    let policy = policy.to_string();
+
+    thread::spawn(move || {
+        let guards = timely::execute(timely::Config::process(workers), move |worker| {
+            let send = send.lock().unwrap().clone();
+            let (mut input, mut cap, mut time_input, mut time_cap) = worker.dataflow::<usize, _, _>(|scope| {
+                let ((time_input, time_cap), time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+                let ((input, input_cap), stream) = scope.new_unordered_input::<String>();
+                let (attrs, output) = create_dataflow(parse_formula(&policy), stream, time_stream, default_options());
+                output.capture_into(send);
+                let _ = attrs_send.lock().unwrap().send(attrs);
+                (input, input_cap, time_input, time_cap)
+            });
+
+            if worker.index() == 0 {
+                for segment in segments.lock().unwrap().take().unwrap() {
+                    match segment {
+                        Segment::Epoch(wm) => {
+                            let t = wm.max(0) as usize;
+                            time_cap.downgrade(&t);
+                            cap.downgrade(&t);
+                        }
+                        Segment::Seg(tp, ts, events) => {
+                            time_input.session(time_cap.delayed(&tp)).give(Timestamp(ts));
+                            input.session(cap.delayed(&tp)).give_iterator(events.into_iter());
+                        }
+                    }
+                    worker.step();
+                }
+                time_input.session(time_cap.delayed(&end)).give(EOS);
+                input.session(cap.delayed(&end)).give("<eos>".to_string());
+            }
+        });
+        // dropping the guards joins the workers, which panics if one of them panicked
+        if let Ok(guards) = guards {
+            drop(guards);
+            let _ = done_send.send(());
+        }
+    });
+    match done_recv.recv_timeout(timeout) {
+        Ok(()) => {}
+        Err(RecvTimeoutError::Timeout) => return Err(format!("No termination within {}s", timeout.as_secs())),
+        Err(RecvTimeoutError::Disconnected) => return Err("The monitor panicked".to_string()),
+    }
+
+    let attrs = attrs_recv.recv().map_err(|_| "The monitor returned no attributes".to_string())?;
+    let mut verdicts: Verdicts = BTreeMap::new();
+    for (tp, records) in recv.extract() {
+        for record in records {
+            if let Data(true, row) = record {
+                verdicts.entry(tp).or_default().push(row);
+            }
+        }
+    }
+    Ok((attrs, normalize(verdicts)))
+}
+
+fn normalize(verdicts: Verdicts) -> Verdicts {
+    verdicts.into_iter()
+        .filter(|(_, rows)| !rows.is_empty())
+        .map(|(tp, mut rows)| {
+            rows.sort();
+            rows.dedup();
+            (tp, rows)
+        })
+        .collect()
+}
+
+/// Moves the columns of the verdicts from the order of `attrs` to the order of `columns`.
+pub fn reorder_columns(verdicts: Verdicts, attrs: &[String], columns: &[String]) -> Result<Verdicts, String> {
+    let mut sorted_attrs = attrs.to_vec();
+    let mut sorted_columns = columns.to_vec();
+    sorted_attrs.sort();
+    sorted_columns.sort();
+    if sorted_attrs != sorted_columns {
+        return Err(format!("Columns {:?} do not match the free variables {:?}", columns, attrs));
+    }
+    let indices: Vec<usize> = columns.iter().map(|c| attrs.iter().position(|a| a == c).unwrap()).collect();
+    let verdicts = verdicts.into_iter()
+        .map(|(tp, rows)| (tp, rows.into_iter().map(|row| indices.iter().map(|&i| row[i].clone()).collect()).collect()))
+        .collect();
+    Ok(normalize(verdicts))
+}
+
+fn format_row(row: &[Constant]) -> String {
+    format!("({})", row.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(","))
+}
+
+/// Lists the valuations that are expected but missing (`-`) and those that are unexpected (`+`)
+/// per time point, at most `MAX_DIFF_LINES` of them.
+pub fn diff(expected: &Verdicts, actual: &Verdicts) -> Option<String> {
+    let empty = Vec::new();
+    let mut lines = Vec::new();
+    let mut tps: Vec<&usize> = expected.keys().chain(actual.keys()).collect();
+    tps.sort();
+    tps.dedup();
+    for tp in tps {
+        let expected_rows = expected.get(tp).unwrap_or(&empty);
+        let actual_rows = actual.get(tp).unwrap_or(&empty);
+        for row in expected_rows.iter().filter(|row| !actual_rows.contains(row)) {
+            lines.push(format!("- time point {}: {}", tp, format_row(row)));
+        }
+        for row in actual_rows.iter().filter(|row| !expected_rows.contains(row)) {
+            lines.push(format!("+ time point {}: {}", tp, format_row(row)));
+        }
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let more = lines.len().saturating_sub(MAX_DIFF_LINES);
+    lines.truncate(MAX_DIFF_LINES);
+    if more > 0 {
+        lines.push(format!("... and {} more", more));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::formula_syntax_tree::Constant::Int;
+
+    fn manifest() -> Manifest {
+        Manifest::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data/regression/manifest.json")).unwrap()
+    }
+
+    #[test]
+    fn regression_manifest_passes() {
+        for case in manifest().cases {
+            for run in case.run() {
+                if let Err(e) = run.result {
+                    panic!("{} with {} workers and {} ordering:\n{}", run.case, run.workers, run.ordering, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn differences_are_reported() {
+        let mut case = manifest().cases.remove(0);
+        case.workers = vec![2];
+        case.orderings = vec![Ordering::Reverse];
+        case.policy = Some("A(x) AND B(x)".to_string());
+        case.policy_file = None;
+        case.columns = None;
+        let runs = case.run();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].result.as_ref().unwrap_err().starts_with("- time point"));
+
+        case.expected = PathBuf::from("missing.out");
+        assert!(case.run()[0].result.as_ref().unwrap_err().starts_with("Missing expected verdicts"));
+    }
+
+    #[test]
+    fn diffs_and_columns() {
+        let expected: Verdicts = vec![(0, vec![vec![Int(1), Int(2)]]), (2, vec![vec![Int(3), Int(4)]])].into_iter().collect();
+        assert_eq!(diff(&expected, &expected), None);
+
+        let actual = reorder_columns(expected.clone(), &["a".to_string(), "b".to_string()], &["b".to_string(), "a".to_string()]).unwrap();
+        assert_eq!(diff(&expected, &actual).unwrap(), "- time point 0: (1,2)\n+ time point 0: (2,1)\n- time point 2: (3,4)\n+ time point 2: (4,3)");
+        assert!(reorder_columns(expected.clone(), &["a".to_string(), "b".to_string()], &["a".to_string()]).is_err());
+
+        let many: Verdicts = (0..30).map(|tp| (tp, vec![vec![Int(tp as i32)]])).collect();
+        let lines: Vec<String> = diff(&many, &BTreeMap::new()).unwrap().lines().map(|l| l.to_string()).collect();
+        assert_eq!(lines.len(), MAX_DIFF_LINES + 1);
+        assert_eq!(lines[MAX_DIFF_LINES], "... and 10 more");
+    }
+
+    // Feeds the time points in order, each followed by a watermark, so that every time point is
+    // complete before the next one arrives
+    fn monitor_in_order(policy: &str, time_points: &[(usize, Vec<&str>)]) -> Verdicts {
+        let mut segments = Vec::new();
+        for (tp, (ts, events)) in time_points.iter().enumerate() {
+            segments.push(Segment::Seg(tp, *ts, events.iter().map(|e| e.to_string()).collect()));
+            segments.push(Segment::Epoch(tp as i64 + 1));
+        }
+        monitor(policy, segments, time_points.len(), 1, Duration::from_secs(60)).unwrap().1
+    }
+
+    #[test]
+    fn future_windows_stay_open_for_time_points_with_the_same_timestamp() {
+        // time point 1 is complete before the later time points, which share its timestamp and are
+        // still in the window of time point 0
+        let time_points = vec![(0, vec!["A(1)"]), (2, vec!["A(1)"]), (2, vec!["A(1)"]), (2, vec!["A(1)"]), (2, vec!["B(1)"]), (5, vec![])];
+        let expected: Verdicts = (0..5).map(|tp| (tp, vec![vec![Int(1)]])).collect();
+        for policy in ["A(x) UNTIL[0,2] B(x)", "EVENTUALLY[0,2] B(x)", "(NOT C(x)) UNTIL[0,2] B(x)"].iter() {
+            assert_eq!(monitor_in_order(policy, &time_points), expected, "policy {}", policy);
+        }
+    }
+}
@@ -25,6 +25,8 @@ pub enum Expr {
     NegUntil(Box<Expr>, Box<Expr>, TimeInterval),
     Once(Box<Expr>, TimeInterval),
     Eventually(Box<Expr>, TimeInterval),
+    Historically(Box<Expr>, TimeInterval),
+    Always(Box<Expr>, TimeInterval),
 
     Next(Box<Expr>, TimeInterval),
     Prev(Box<Expr>, TimeInterval),
@@ -71,6 +73,12 @@ impl fmt::Display for Expr {
             Expr::Eventually(lhs, _) => {
                 s.push_str(&format!("Until({})", lhs));
             }
+            Expr::Historically(lhs, _) => {
+                s.push_str(&format!("Historically({})", lhs));
+            }
+            Expr::Always(lhs, _) => {
+                s.push_str(&format!("Always({})", lhs));
+            }
             Expr::Since(lhs, rhs, _) => {
                 s.push_str(&format!("Since({}, {})", lhs, rhs));
             }
@@ -175,6 +183,20 @@ fn optimize_cases(plan: Expr) -> Expr {
                 lhs => Expr::Eventually(Box::new(lhs), interval),
             }
         }
+        Expr::Historically(lhs, interval) => {
+            let new_lhs = optimize_cases(*lhs);
+            match new_lhs {
+                FULL => FULL,
+                lhs => Expr::Historically(Box::new(lhs), interval),
+            }
+        }
+        Expr::Always(lhs, interval) => {
+            let new_lhs = optimize_cases(*lhs);
+            match new_lhs {
+                FULL => FULL,
+                lhs => Expr::Always(Box::new(lhs), interval),
+            }
+        }
         Join(lhs, rhs) => {
             let new_lhs = optimize_cases(*lhs);
             let new_rhs = optimize_cases(*rhs);
@@ -224,7 +246,7 @@ fn optimize_cases(plan: Expr) -> Expr {
             match (new_lhs, new_rhs) {
                 (_, EMPTY) => EMPTY,
                 (EMPTY, rhs) => {
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         rhs
                     } else {
                         EMPTY
@@ -240,7 +262,7 @@ fn optimize_cases(plan: Expr) -> Expr {
             match (new_lhs, new_rhs) {
                 (_, EMPTY) => EMPTY,
                 (EMPTY, rhs) => {
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         rhs
                     } else {
                         EMPTY
@@ -256,7 +278,7 @@ fn optimize_cases(plan: Expr) -> Expr {
             match (new_lhs, new_rhs) {
                 (EMPTY, EMPTY) | (FULL, EMPTY) => EMPTY,
                 (FULL, rhs) => {
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         rhs
                     } else {
                         EMPTY
@@ -271,7 +293,7 @@ fn optimize_cases(plan: Expr) -> Expr {
             match (new_lhs, new_rhs) {
                 (EMPTY, EMPTY) | (FULL, EMPTY) => EMPTY,
                 (FULL, rhs) => {
-                    if interval.get_raw_start() == 0 {
+                    if interval.contains(0) {
                         rhs
                     } else {
                         EMPTY
@@ -295,7 +317,7 @@ fn generate_boolean_evaluation_plan(f: Formula) -> Expr {
                 "Error while generating an evaluation plan for {}",
                 f.to_string()
             )),
-            _ => build_assignment(*subf),
+            _ => build_assignment(f),
         },
         Conj(lhs, rhs) => {
             let fv = free_variables(*lhs.clone());
@@ -357,6 +379,14 @@ fn generate_boolean_evaluation_plan(f: Formula) -> Expr {
             let lhs_expr = build_assignment(*lhs);
             build_eventually(lhs_expr, interval)
         }
+        Formula::Historically(lhs, interval) => {
+            let lhs_expr = build_assignment(*lhs);
+            build_historically(lhs_expr, interval)
+        }
+        Formula::Always(lhs, interval) => {
+            let lhs_expr = build_assignment(*lhs);
+            build_always(lhs_expr, interval)
+        }
         Formula::Since(lhs, rhs, interval) => {
             let lhs_expr = build_assignment(*lhs);
             let rhs_expr = build_assignment(*rhs);
@@ -447,6 +477,14 @@ fn build_eventually(lhs: Expr, interval: TimeInterval) -> Expr {
     }
 }
 
+fn build_historically(lhs: Expr, interval: TimeInterval) -> Expr {
+    Expr::Historically(Box::new(lhs), interval)
+}
+
+fn build_always(lhs: Expr, interval: TimeInterval) -> Expr {
+    Expr::Always(Box::new(lhs), interval)
+}
+
 fn build_filter(var: String, val: Arg, expr: Expr) -> Expr {
     Filter(var, val, Box::new(expr))
 }
@@ -471,8 +509,14 @@ pub fn build_assignment(f: Formula) -> Expr {
         Formula::Fact(x, y) => Expr::Fact(x, y),
         Formula::JSONQuery(query, aliases) => Expr::JSONQuery(query, aliases),
         Formula::Not(lhs) => {
+            let no_free_vars = free_variables(*lhs.clone()).iter().all(|v| !is_var(v));
             let expr_lhs = build_assignment(*lhs);
-            Expr::Not(Box::new(expr_lhs))
+            // a closed negation holds at the time points where its subformula does not
+            if no_free_vars {
+                build_antijoin(FULL, expr_lhs)
+            } else {
+                Expr::Not(Box::new(expr_lhs))
+            }
         }
         Formula::Equals(x, y) => build_expr_equals(x, *y),
         Conj(lhs, rhs) => {
@@ -522,6 +566,14 @@ pub fn build_assignment(f: Formula) -> Expr {
             let expr_lhs = build_assignment(*lhs);
             build_eventually(expr_lhs, interval)
         }
+        Formula::Historically(lhs, interval) => {
+            let expr_lhs = build_assignment(*lhs);
+            build_historically(expr_lhs, interval)
+        }
+        Formula::Always(lhs, interval) => {
+            let expr_lhs = build_assignment(*lhs);
+            build_always(expr_lhs, interval)
+        }
         Formula::Next(lhs, interval) => {
             let expr_lhs = build_assignment(*lhs);
             build_next(expr_lhs, interval)
@@ -806,6 +858,57 @@ mod tests {
         test_bool_formula(f, expected);
     }
 
+    #[test]
+    fn historically_plan() {
+        let rhs = test_formula(Some(vec![2]));
+
+        let f = formula_syntax_tree::build_historically(
+            rhs.clone(),
+            TimeInterval::new(TS::new(1), TS::new(5)),
+        );
+
+        let expected = build_historically(
+            build_assignment(rhs),
+            TimeInterval::new(TS::new(1), TS::new(5)),
+        );
+
+        test_bool_formula(f, expected);
+    }
+
+    #[test]
+    fn always_plan() {
+        let rhs = test_formula(Some(vec![2]));
+
+        let f = formula_syntax_tree::build_always(
+            rhs.clone(),
+            TimeInterval::new(TS::new(1), TS::new(5)),
+        );
+
+        let expected = build_always(
+            build_assignment(rhs),
+            TimeInterval::new(TS::new(1), TS::new(5)),
+        );
+
+        test_bool_formula(f, expected);
+    }
+
+    #[test]
+    fn historically_conj_plan() {
+        let lhs = build_fact("A", vec!["x"]);
+        let rhs = build_fact("B", vec!["x"]);
+        let f = parse_formula("A(x) AND PAST_ALWAYS[0,5] B(x)");
+
+        let expected = build_join(
+            build_assignment(lhs),
+            build_historically(
+                build_assignment(rhs),
+                TimeInterval::new(TS::new(0), TS::new(5)),
+            ),
+        );
+
+        test_bool_formula(f, expected);
+    }
+
     #[test]
     fn once_formula_test() {
         let lhs = build_fact("A", vec!["a", "b"]);
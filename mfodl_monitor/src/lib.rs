@@ -23,12 +23,19 @@ extern crate structopt;
 
 #[macro_use]
 mod constants;
+pub mod benchmark;
 pub mod dataflow_constructor;
+pub mod end_to_end;
 mod evaluation_plan_generator;
 pub mod parser;
+pub mod policy_generator;
+pub mod reference_evaluator;
+pub mod repl;
+pub mod stream_io;
 pub mod timeunits;
+pub mod trace_generator;
 
-pub use dataflow_constructor::dataflow_constructor::create_dataflow;
+pub use dataflow_constructor::dataflow_constructor::{create_dataflow, unknown_verdicts_error};
 pub use evaluation_plan_generator::evaluation_plan_generator::generate_evaluation_plan;
 pub use evaluation_plan_generator::evaluation_plan_generator::Expr;
 pub use parser::formula_syntax_tree::Formula;
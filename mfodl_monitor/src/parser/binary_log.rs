@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use dataflow_constructor::types::TypedEvent;
+use parser::csv_parser::{parser_extended_wrapper, ParserReturn, Segment};
+use parser::formula_syntax_tree::Constant::{Int, Str};
+use parser::formula_syntax_tree::{Constant, Formula};
+use parser::json_parser::find_timestamp;
+use parser::signature::{ArgType, Signature, Signatures, TypedRecords};
+
+// Binary event log:
+//   header:  magic, number of signatures, per signature: name, arity, one type tag per argument
+//   records: u32 length of the body, record kind, body
+// Integers are little endian, strings are a u32 length followed by UTF-8 bytes.
+const MAGIC: &[u8; 8] = b"TMONLOG1";
+
+const EVENT: u8 = 0;
+const WATERMARK: u8 = 1;
+const JSON: u8 = 2;
+
+impl ArgType {
+    fn of(c: &Constant) -> io::Result<ArgType> {
+        match c {
+            Int(_) => Ok(ArgType::Int),
+            Str(_) => Ok(ArgType::Str),
+            Constant::JSONValue(_) => Err(invalid("JSON values are not supported as event arguments".to_string())),
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            ArgType::Int => 0,
+            ArgType::Str => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> io::Result<ArgType> {
+        match tag {
+            0 => Ok(ArgType::Int),
+            1 => Ok(ArgType::Str),
+            _ => Err(invalid(format!("Unknown argument type {}", tag))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogRecord {
+    // time point, timestamp, predicate, arguments
+    Event(usize, usize, String, Vec<Constant>),
+    Watermark(i64),
+    // timestamp, JSON event
+    Json(usize, String),
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Signatures of all predicates in the order of their first event. Every event of a predicate has
+// to match the signature of its first event.
+fn collect_signatures(records: &[LogRecord]) -> io::Result<Vec<Signature>> {
+    let mut signatures: Vec<Signature> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for record in records {
+        if let LogRecord::Event(tp, _, name, args) = record {
+            let types = args.iter().map(ArgType::of).collect::<io::Result<Vec<_>>>()?;
+            match index.get(name.as_str()) {
+                Some(i) if signatures[*i].args != types => {
+                    return Err(invalid(format!(
+                        "Event {} at time point {} does not match the signature {:?} of its first event",
+                        name, tp, signatures[*i].args
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    index.insert(name, signatures.len());
+                    signatures.push(Signature { name: name.clone(), args: types });
+                }
+            }
+        }
+    }
+    Ok(signatures)
+}
+
+fn put_u32(buf: &mut Vec<u8>, n: usize) -> io::Result<()> {
+    let n = u32::try_from(n).map_err(|_| invalid(format!("Length {} does not fit the log format", n)))?;
+    buf.extend_from_slice(&n.to_le_bytes());
+    Ok(())
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) -> io::Result<()> {
+    put_u32(buf, s.len())?;
+    buf.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// Writes the header with the signatures of all predicates followed by the records.
+pub fn write_binary_log<W: Write>(writer: &mut W, records: &[LogRecord]) -> io::Result<()> {
+    let signatures = collect_signatures(records)?;
+    let index: HashMap<&str, usize> = signatures.iter().enumerate().map(|(i, s)| (s.name.as_str(), i)).collect();
+
+    let mut header = MAGIC.to_vec();
+    put_u32(&mut header, signatures.len())?;
+    for signature in &signatures {
+        put_str(&mut header, &signature.name)?;
+        put_u32(&mut header, signature.args.len())?;
+        header.extend(signature.args.iter().map(ArgType::tag));
+    }
+    writer.write_all(&header)?;
+
+    let mut body = Vec::new();
+    for record in records {
+        body.clear();
+        match record {
+            LogRecord::Event(tp, ts, name, args) => {
+                body.push(EVENT);
+                put_u32(&mut body, index[name.as_str()])?;
+                body.extend_from_slice(&(*tp as u64).to_le_bytes());
+                body.extend_from_slice(&(*ts as u64).to_le_bytes());
+                for arg in args {
+                    match arg {
+                        Int(i) => body.extend_from_slice(&i.to_le_bytes()),
+                        Str(s) => put_str(&mut body, s)?,
+                        Constant::JSONValue(_) => {}
+                    }
+                }
+            }
+            LogRecord::Watermark(wm) => {
+                body.push(WATERMARK);
+                body.extend_from_slice(&wm.to_le_bytes());
+            }
+            LogRecord::Json(ts, json) => {
+                body.push(JSON);
+                body.extend_from_slice(&(*ts as u64).to_le_bytes());
+                put_str(&mut body, json)?;
+            }
+        }
+        let mut length = Vec::with_capacity(4);
+        put_u32(&mut length, body.len())?;
+        writer.write_all(&length)?;
+        writer.write_all(&body)?;
+    }
+    writer.flush()
+}
+
+/// Parses a log in the CSV event format. Malformed lines are reported with their line number.
+pub fn read_csv_log(path: &Path) -> io::Result<Vec<LogRecord>> {
+    csv_log_records(path)?.collect()
+}
+
+/// The records of a log in the CSV event format, parsed as the lines are read.
+pub fn csv_log_records(path: &Path) -> io::Result<impl Iterator<Item = io::Result<LogRecord>>> {
+    let lines = BufReader::new(File::open(path)?).lines().enumerate();
+    Ok(lines.filter_map(|(line_number, line)| {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        if line.trim().is_empty() {
+            return None;
+        }
+        Some(match parser_extended_wrapper(line) {
+            ParserReturn::Data(tp, ts, Formula::CstFact(name, args)) => Ok(LogRecord::Event(tp, ts, name, args)),
+            ParserReturn::Watermark(wm) => Ok(LogRecord::Watermark(wm)),
+            ParserReturn::Data(_, _, f) => Err(invalid(format!("Line {}: unexpected event {}", line_number + 1, f))),
+            ParserReturn::Error(e) => Err(invalid(format!("Line {}: {}", line_number + 1, e))),
+        })
+    }))
+}
+
+/// Parses a log with one JSON event per line, the timestamp of an event is its "timestamp" field.
+pub fn read_json_log(path: &Path) -> io::Result<Vec<LogRecord>> {
+    json_log_records(path)?.collect()
+}
+
+/// The records of a log with one JSON event per line, parsed as the lines are read.
+pub fn json_log_records(path: &Path) -> io::Result<impl Iterator<Item = io::Result<LogRecord>>> {
+    let lines = BufReader::new(File::open(path)?).lines().enumerate();
+    Ok(lines.filter_map(|(line_number, line)| {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        if line.trim().is_empty() {
+            return None;
+        }
+        if line.starts_with('>') {
+            if let ParserReturn::Watermark(wm) = parser_extended_wrapper(line.clone()) {
+                return Some(Ok(LogRecord::Watermark(wm)));
+            }
+        }
+        let value = match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(value) => value,
+            Err(e) => return Some(Err(invalid(format!("Line {}: {}", line_number + 1, e)))),
+        };
+        Some(match find_timestamp(&value) {
+            Some(ts) => Ok(LogRecord::Json(ts as usize, value.to_string())),
+            None => Err(invalid(format!("Line {}: event without timestamp", line_number + 1))),
+        })
+    }))
+}
+
+/// Reads the records of a binary log written by `write_binary_log`.
+pub struct BinaryLogReader<R: Read> {
+    reader: R,
+    signatures: Vec<Signature>,
+}
+
+impl BinaryLogReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        BinaryLogReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> BinaryLogReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("Not a binary event log".to_string()));
+        }
+
+        let count = read_u32(&mut reader)?;
+        let mut signatures = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = read_str(&mut reader)?;
+            let arity = read_u32(&mut reader)?;
+            let mut tags = vec![0u8; arity];
+            reader.read_exact(&mut tags)?;
+            let args = tags.into_iter().map(ArgType::from_tag).collect::<io::Result<Vec<_>>>()?;
+            signatures.push(Signature { name, args });
+        }
+        Ok(BinaryLogReader { reader, signatures })
+    }
+
+    pub fn signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<LogRecord>> {
+        let mut length = [0u8; 4];
+        match self.reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut body = vec![0u8; u32::from_le_bytes(length) as usize];
+        self.reader.read_exact(&mut body)?;
+
+        let mut body = body.as_slice();
+        let mut kind = [0u8; 1];
+        body.read_exact(&mut kind)?;
+        let record = match kind[0] {
+            EVENT => {
+                let signature = self
+                    .signatures
+                    .get(read_u32(&mut body)?)
+                    .ok_or_else(|| invalid("Event of an unknown predicate".to_string()))?;
+                let tp = read_u64(&mut body)? as usize;
+                let ts = read_u64(&mut body)? as usize;
+                let mut args = Vec::with_capacity(signature.args.len());
+                for arg_type in &signature.args {
+                    args.push(match arg_type {
+                        ArgType::Int => {
+                            let mut buf = [0u8; 4];
+                            body.read_exact(&mut buf)?;
+                            Int(i32::from_le_bytes(buf))
+                        }
+                        ArgType::Str => Str(read_str(&mut body)?),
+                    });
+                }
+                LogRecord::Event(tp, ts, signature.name.clone(), args)
+            }
+            WATERMARK => {
+                let mut buf = [0u8; 8];
+                body.read_exact(&mut buf)?;
+                LogRecord::Watermark(i64::from_le_bytes(buf))
+            }
+            JSON => {
+                let ts = read_u64(&mut body)? as usize;
+                LogRecord::Json(ts, read_str(&mut body)?)
+            }
+            kind => return Err(invalid(format!("Unknown record kind {}", kind))),
+        };
+        Ok(Some(record))
+    }
+}
+
+impl<R: Read> Iterator for BinaryLogReader<R> {
+    type Item = io::Result<LogRecord>;
+
+    fn next(&mut self) -> Option<io::Result<LogRecord>> {
+        self.read_record().transpose()
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf) as usize)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_str<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut buf = vec![0u8; read_u32(reader)?];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| invalid(e.to_string()))
+}
+
+/// Groups the records of a binary log into segments like `parse_file_to_segments`. The events are
+/// handed to the dataflow as they are, without parsing any text. With signatures, events that do
+/// not match them are dropped and reported on stderr.
+pub fn binary_log_to_segments(path: &Path, signatures: Option<&Signatures>) -> io::Result<Vec<Segment<TypedEvent>>> {
+    let records = BinaryLogReader::open(path)?;
+    match signatures {
+        Some(signatures) => records_to_segments(TypedRecords::new(records, signatures.clone())),
+        None => records_to_segments(records),
+    }
+}
+
+/// Groups consecutive records of the same time point into segments of typed events.
+pub fn records_to_segments<I: IntoIterator<Item = io::Result<LogRecord>>>(records: I) -> io::Result<Vec<Segment<TypedEvent>>> {
+    let mut result: Vec<Segment<TypedEvent>> = Vec::with_capacity(1000);
+    let mut current: Option<(usize, usize, Vec<TypedEvent>)> = None;
+
+    for record in records {
+        let (tp, ts, val) = match record? {
+            LogRecord::Event(tp, ts, name, args) => (tp, ts, TypedEvent::Fact(name, args)),
+            // JSON events use their timestamp as time point
+            LogRecord::Json(ts, json) => (ts, ts, TypedEvent::Json(json)),
+            LogRecord::Watermark(wm) => {
+                if let Some((tp, ts, segment)) = current.take() {
+                    result.push(Segment::Seg(tp, ts, segment));
+                }
+                result.push(Segment::Epoch(wm));
+                continue;
+            }
+        };
+        match current.as_mut() {
+            Some((current_tp, _, segment)) if *current_tp == tp => segment.push(val),
+            _ => {
+                if let Some((tp, ts, segment)) = current.replace((tp, ts, vec![val])) {
+                    result.push(Segment::Seg(tp, ts, segment));
+                }
+            }
+        }
+    }
+
+    if let Some((tp, ts, segment)) = current {
+        result.push(Segment::Seg(tp, ts, segment));
+    }
+    Ok(result)
+}
+
+/// Converts a CSV or (if `json` is set) JSON log into a binary log.
+pub fn convert_to_binary_log(input: &Path, output: &Path, json: bool) -> io::Result<usize> {
+    let records = if json { read_json_log(input)? } else { read_csv_log(input)? };
+    let mut writer = BufWriter::new(File::create(output)?);
+    write_binary_log(&mut writer, &records)?;
+    Ok(records.len())
+}
+
+/// Monitors typed segments on one worker and returns how many formulas were parsed after the
+/// dataflow was built, along with the satisfying tuples per time point.
+#[cfg(test)]
+pub(crate) fn monitor_typed_segments(policy: &str, segments: Vec<Segment<TypedEvent>>) -> Vec<(usize, Vec<Constant>)> {
+    use std::sync::mpsc;
+    use timely::dataflow::operators::capture::{Capture, Extract};
+    use timely::dataflow::operators::UnorderedInput;
+    use dataflow_constructor::types::{default_options, TimeFlowValues};
+    use dataflow_constructor::types::FlowValues::Data;
+    use dataflow_constructor::types::TimeFlowValues::{Timestamp, EOS};
+    use {create_dataflow, parse_formula};
+
+    let policy = parse_formula(policy);
+    let (send, recv) = mpsc::channel();
+    timely::execute_directly(move |worker| {
+        let (mut input, cap, mut time_input, time_cap) = worker.dataflow::<usize, _, _>(|scope| {
+            let ((time_input, time_cap), time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+            let ((input, cap), stream) = scope.new_unordered_input::<TypedEvent>();
+            let (_attrs, output) = create_dataflow(policy, stream, time_stream, default_options());
+            output.capture_into(send);
+            (input, cap, time_input, time_cap)
+        });
+
+        let mut end = 0;
+        for segment in segments {
+            if let Segment::Seg(tp, ts, events) = segment {
+                time_input.session(time_cap.delayed(&tp)).give(Timestamp(ts));
+                input.session(cap.delayed(&tp)).give_iterator(events.into_iter());
+                end = end.max(tp + 1);
+            }
+        }
+        time_input.session(time_cap.delayed(&end)).give(EOS);
+        input.session(cap.delayed(&end)).give(TypedEvent::Eos);
+        drop((cap, time_cap));
+        while worker.step() {}
+    });
+
+    let mut verdicts = Vec::new();
+    for (tp, records) in recv.extract() {
+        for record in records {
+            if let Data(true, tuple) = record {
+                verdicts.push((tp, tuple));
+            }
+        }
+    }
+    verdicts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use parser::binary_log::{binary_log_to_segments, convert_to_binary_log, monitor_typed_segments, records_to_segments, write_binary_log, BinaryLogReader, LogRecord};
+    use dataflow_constructor::types::{InputEvent, TypedEvent};
+    use parser::csv_parser::{parse_file_to_segments, Segment};
+    use parser::formula_syntax_tree::Arg::Cst;
+    use parser::formula_syntax_tree::Constant::{Int, Str};
+    use parser::formula_syntax_tree::Formula::Fact;
+    use parser::signature::{ArgType, Signature};
+    use parse_formula;
+
+    // the typed events stand for the same facts as the parsed text events
+    fn segments_eq(a: &[Segment<TypedEvent>], b: &[Segment]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|s| match s {
+            (Segment::Epoch(x), Segment::Epoch(y)) => x == y,
+            (Segment::Seg(tp1, ts1, v1), Segment::Seg(tp2, ts2, v2)) => {
+                tp1 == tp2 && ts1 == ts2 && v1.iter().map(InputEvent::to_fact).eq(v2.iter().map(InputEvent::to_fact))
+            }
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn binary_log_round_trip() {
+        let records = vec![
+            LogRecord::Event(0, 0, "A".to_string(), vec![Int(1), Str("alice".to_string())]),
+            LogRecord::Event(0, 0, "B".to_string(), vec![]),
+            LogRecord::Watermark(-1),
+            LogRecord::Event(1, 5, "A".to_string(), vec![Int(-2), Str("".to_string())]),
+            LogRecord::Json(7, "{\"timestamp\":7}".to_string()),
+        ];
+        let mut buf = Vec::new();
+        write_binary_log(&mut buf, &records).unwrap();
+
+        let reader = BinaryLogReader::new(buf.as_slice()).unwrap();
+        assert_eq!(reader.signatures(), &[
+            Signature { name: "A".to_string(), args: vec![ArgType::Int, ArgType::Str] },
+            Signature { name: "B".to_string(), args: vec![] },
+        ]);
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), records);
+    }
+
+    #[test]
+    fn binary_log_signature_mismatch() {
+        let records = vec![
+            LogRecord::Event(0, 0, "A".to_string(), vec![Int(1)]),
+            LogRecord::Event(1, 1, "A".to_string(), vec![Str("x".to_string())]),
+        ];
+        assert!(write_binary_log(&mut Vec::new(), &records).is_err());
+        assert!(BinaryLogReader::new(&b"TMONLOG0"[..]).is_err());
+    }
+
+    #[test]
+    fn binary_log_segments_match_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("log.csv");
+        let bin = dir.path().join("log.tlog");
+        write!(File::create(&csv).unwrap(), "A, tp=0, ts=0, x0=1, x1=2\nA, tp=0, ts=0, x0=3, x1=4\n\
+            B, tp=1, ts=3, x0='abc'\n>WATERMARK 1<\nC, tp=2, ts=4\n").unwrap();
+
+        assert_eq!(convert_to_binary_log(&csv, &bin, false).unwrap(), 5);
+        assert!(segments_eq(&binary_log_to_segments(&bin, None).unwrap(), &parse_file_to_segments(csv)));
+    }
+
+    #[test]
+    fn binary_log_events_are_not_parsed() {
+        let records = vec![
+            LogRecord::Event(0, 0, "P".to_string(), vec![Int(-1), Str("O'Brien".to_string())]),
+            LogRecord::Event(1, 2, "P".to_string(), vec![Int(2), Str("x".to_string())]),
+        ];
+        let mut buf = Vec::new();
+        write_binary_log(&mut buf, &records).unwrap();
+        let segments = records_to_segments(BinaryLogReader::new(buf.as_slice()).unwrap()).unwrap();
+
+        // the quote would end the string constant of the parsed event
+        assert_ne!(parse_formula("P(-1,'O'Brien')"), Fact("P".to_string(), vec![Cst(Int(-1)), Cst(Str("O'Brien".to_string()))]));
+        assert_eq!(monitor_typed_segments("P(x,y)", segments), vec![
+            (0, vec![Int(-1), Str("O'Brien".to_string())]),
+            (1, vec![Int(2), Str("x".to_string())]),
+        ]);
+    }
+}
@@ -14,52 +14,65 @@ use parser::csv_parser::Segment::{Epoch, Seg};
 use parser::formula_syntax_tree::Constant::Str;
 use parser::formula_syntax_tree::Constant::Int;
 
-pub enum Segment {
+// Events of one time point (tp, ts, events) or a watermark. The events are text by default and
+// typed for input formats that are not parsed in the dataflow.
+#[derive(Clone, Debug)]
+pub enum Segment<E = String> {
     Epoch(i64),
-    Seg(usize, usize, Vec<String>)
+    Seg(usize, usize, Vec<E>)
 }
 
 //TODO use indicies to reduce copying/memory footprint
 pub fn parse_file_to_segments(path: PathBuf) -> Vec<Segment> {
+    if let Ok(f) = File::open(path.clone()) {
+        parse_lines_to_segments(BufReader::new(&f).lines().map(|line| line.unwrap()))
+    } else {
+        Vec::with_capacity(1000)
+    }
+}
+
+pub fn parse_lines_to_segments<I: Iterator<Item = String>>(lines: I) -> Vec<Segment> {
     let mut result : Vec<Segment> = Vec::with_capacity(1000);
     let mut current_is_set = false;
     let mut current_tp = 0;
     let mut current_ts = 0;
 
-    if let Ok(f) = File::open(path.clone()) {
-        let mut current_segment = Vec::with_capacity(1000);
-        for line in BufReader::new(&f).lines() {
-            let l = line.unwrap().clone();
-            match parser_extended_wrapper(l.clone()) {
-                ParserReturn::Data(tp, ts, val) => {
-                    if current_is_set {
-                        if tp == current_tp {
-                            current_segment.push(val.to_string())
-                        } else {
-                            if !current_segment.is_empty() {
-                                result.push(Seg(current_tp, current_ts, current_segment.clone()));
-                                current_segment.clear();
-                            }
-                            current_tp = tp;
-                            current_ts = ts;
-                            current_segment.push( val.to_string())
-                        }
+    let mut current_segment = Vec::with_capacity(1000);
+    for l in lines {
+        match parser_extended_wrapper(l.clone()) {
+            ParserReturn::Data(tp, ts, val) => {
+                if current_is_set {
+                    if tp == current_tp {
+                        current_segment.push(val.to_string())
                     } else {
+                        if !current_segment.is_empty() {
+                            result.push(Seg(current_tp, current_ts, current_segment.clone()));
+                            current_segment.clear();
+                        }
                         current_tp = tp;
                         current_ts = ts;
-                        current_is_set = true;
-                        current_segment.push(val.to_string())
+                        current_segment.push( val.to_string())
                     }
+                } else {
+                    current_tp = tp;
+                    current_ts = ts;
+                    current_is_set = true;
+                    current_segment.push(val.to_string())
                 }
-                Watermark(wm) => {
+            }
+            Watermark(wm) => {
+                // an empty segment would repeat a time point that the watermark may already cover
+                if !current_segment.is_empty() {
                     result.push(Seg(current_tp, current_ts, current_segment.clone()));
-                    result.push(Epoch(wm));
-                    current_segment.clear();
                 }
-                _ => {}
+                result.push(Epoch(wm));
+                current_segment.clear();
             }
+            _ => {}
         }
+    }
 
+    if !current_segment.is_empty() {
         result.push(Seg(current_tp, current_ts, current_segment));
     }
     result
@@ -501,16 +514,43 @@ named!(string<&str, Constant>,
 );
 
 named!(number<&str, Constant>,
+    ws!(map_res!(
+        recognize!(pair!(opt!(tag!("-")), digit)),
+        |n: &str| n.parse::<i32>().map(Int)
+    ))
+);
+
+/// Splits an event into its predicate, time point, timestamp and argument values without guessing
+/// the types of the values, quotes around a value are removed.
+pub fn parse_raw_event(line: &str) -> Option<(String, usize, usize, Vec<String>)> {
+    match raw_event(line) {
+        Done(rest, event) if rest.trim().is_empty() => Some(event),
+        _ => None,
+    }
+}
+
+named!(raw_event<&str, (String, usize, usize, Vec<String>)>,
     ws!(do_parse!(
-        n: digit >>
-        (Int(n.parse::<i32>().unwrap()))
+        opt!(digit) >> opt!(tag!("'")) >> command: take_until!(",") >>
+        char!(',') >> tag!("tp") >> char!('=') >> tp: digit >>
+        char!(',') >> tag!("ts") >> char!('=') >> ts: digit >>
+        args: many0!(complete!(preceded!(attr_name, raw_arg))) >>
+        ((command.trim().to_string(), tp.parse::<usize>().unwrap(), ts.parse::<usize>().unwrap(), args))
+    ))
+);
+
+named!(raw_arg<&str, String>,
+    ws!(alt!(
+        delimited!(tag!("'"), take_until!("'"), tag!("'")) => { |s: &str| s.to_string() } |
+        delimited!(tag!("\""), take_until!("\""), tag!("\"")) => { |s: &str| s.to_string() } |
+        take_till!(|c| c == ',' || c == '\'' || c == '"') => { |s: &str| s.trim().to_string() }
     ))
 );
 
 #[cfg(test)]
 mod tests {
     
-    use parser::csv_parser::{parse_result, tuple, tuples, parse_watermark_extended, parser_extended, ParserReturn};
+    use parser::csv_parser::{parse_lines_to_segments, parse_raw_event, parse_result, tuple, tuples, parse_watermark_extended, parser_extended, ParserReturn, Segment};
     use parser::csv_parser::ParserReturn::{Data, Watermark};
     use parser::formula_syntax_tree::Constant::Int;
     use parser::formula_syntax_tree::Formula::*;
@@ -542,6 +582,15 @@ mod tests {
         assert_eq!(Data(7, 7, CstFact("A".to_string(), vec![])), t.1);
     }
 
+    #[test]
+    fn negative_arguments() {
+        let t = parser_extended("A, tp=7, ts=7, x0=-3, x1=4").unwrap();
+        assert_eq!(Data(7, 7, CstFact("A".to_string(), vec![Int(-3), Int(4)])), t.1);
+        assert_eq!(parse_raw_event("3'A, tp=7, ts=7, x0=-3, x1='a, b', x2=007"),
+            Some(("A".to_string(), 7, 7, vec!["-3".to_string(), "a, b".to_string(), "007".to_string()])));
+        assert_eq!(parse_raw_event("A, tp=7, ts=7, x0='a"), None);
+    }
+
     #[test]
     fn tuple_test() {
         let tmp = tuple("(1,2,3)").unwrap().1;
@@ -605,4 +654,16 @@ mod tests {
         tmp1 = parse_watermark_extended("dasdfafagfsfdsad>WATErMARK 100<").unwrap();
         assert_eq!(tmp1.1, Watermark(100));
     }
+
+    #[test]
+    fn watermarks_do_not_repeat_time_points() {
+        let lines = vec!["A, tp=0, ts=0, x0=1", ">WATERMARK 1<", "B, tp=1, ts=2", ">WATERMARK 2<"];
+        let segments = parse_lines_to_segments(lines.into_iter().map(|l| l.to_string()));
+        // an empty segment of time point 1 after the last watermark would be sent below it
+        let segments: Vec<String> = segments.iter().map(|s| match s {
+            Segment::Epoch(wm) => format!("wm {}", wm),
+            Segment::Seg(tp, ts, events) => format!("{} {} {:?}", tp, ts, events),
+        }).collect();
+        assert_eq!(segments, vec!["0 0 [\"A(1)\"]", "wm 1", "1 2 [\"B()\"]", "wm 2"]);
+    }
 }
@@ -79,10 +79,17 @@ named!(clean_empty_fact<&str, (String, Vec<Constant>)>,
     ))
 );
 
+/// Parses a formula whose interval bounds with a unit are converted to timestamps in seconds.
 pub fn parse_formula(s: &str) -> Formula {
+    parse_formula_with_resolution(s, TimeUnit::Second)
+}
+
+/// Parses a formula whose interval bounds with a unit, e.g. `ONCE[0, 5m]`, are converted to
+/// timestamps of the given resolution.
+pub fn parse_formula_with_resolution(s: &str, resolution: TimeUnit) -> Formula {
     let tmp = s.clone();
     match formula(s) {
-        Done(_i, o) => o,
+        Done(_i, o) => in_resolution(o, resolution),
         IResult::Error(x) => {
             println!("Parser Error: {:?}   {:?}", x, tmp);
             FormulaError("Error".to_string())
@@ -283,9 +290,10 @@ named!(formula_l1<&str, Formula>,
 
 // formula_l2 = temporal | once | historically | always | prev | bracketted_formula |
 //              exists | forall | fact
+// always and historically are tried before fact, which would take ALWAYS(0,3) for a predicate
 named!(formula_l2<&str, Formula>,
     ws!(alt_complete!(
-        true_f | false_f | base_value | once | eventually | bracketted_formula | prev | next | forall | exists | empty_fact | fact | eos | num_equals | equals | always | historically | json_query
+        true_f | false_f | base_value | once | eventually | bracketted_formula | prev | next | always | historically | forall | exists | empty_fact | fact | eos | num_equals | equals | json_query
     ))
 );
 
@@ -579,9 +587,10 @@ named!(string<&str, Arg>,
 
 /// Numeric arguments are of the form:
 ///  - 5
+///  - -5
 named!(number<&str, Arg>,
     ws!(do_parse!(
-        n: digit >>
+        n: recognize!(pair!(opt!(tag!("-")), digit)) >>
         (Arg::Cst(Int(n.parse::<i32>().unwrap())))
     ))
 );
@@ -603,41 +612,50 @@ named!(interval<&str, TimeInterval>,
 
 named!(fin_interval<&str, TimeInterval>,
     ws!(do_parse!(
-       open_paran: take!(1) >>
-       start: digit >>
+       open_paran: alt!(tag!("[") | tag!("(")) >>
+       start: bound >>
        tag!(",") >>
-       end: digit >>
-       closing_paran: take!(1) >>
-       (if open_paran == "[" && closing_paran == "]" {
-           TimeInterval::new(TS::new(start.parse::<usize>().unwrap()), TS::new(end.parse::<usize>().unwrap()))
-       } else if open_paran == "(" && closing_paran == ")" {
-            TimeInterval::new(TS::new(start.parse::<usize>().unwrap()+1), TS::new(end.parse::<usize>().unwrap()-1))
-       } else if open_paran == "[" && closing_paran == ")" {
-            TimeInterval::new(TS::new(start.parse::<usize>().unwrap()), TS::new(end.parse::<usize>().unwrap()-1))
-       } else { //if open_paran == "(" && closing_paran == "]" {
-            TimeInterval::new(TS::new(start.parse::<usize>().unwrap()+1), TS::new(end.parse::<usize>().unwrap()))
+       end: bound >>
+       closing_paran: alt!(tag!("]") | tag!(")")) >>
+       (TimeInterval {
+            start_unit: start.1,
+            end_unit: end.1,
+            ..TimeInterval::with_bounds(TS::new(start.0), open_paran == "(", TS::new(end.0), closing_paran == ")")
        })
     ))
 );
 
 named!(inf_interval<&str, TimeInterval>,
     ws!(do_parse!(
-       open_paran: take!(1) >>
-       start: digit >>
+       open_paran: alt!(tag!("[") | tag!("(")) >>
+       start: bound >>
        tag!(",") >>
         tag!("*)") >>
-       (if open_paran == "[" {
-           TimeInterval::new(TS::new(start.parse::<usize>().unwrap()), TS::INFINITY)
-        } else {
-            TimeInterval::new(TS::new(start.parse::<usize>().unwrap()+1), TS::INFINITY)
-        })
+       (TimeInterval { start_unit: start.1, ..TimeInterval::with_bounds(TS::new(start.0), open_paran == "(", TS::INFINITY, false) })
     ))
 );
 
+/// Interval bounds are timestamps, e.g. 7, or durations with a unit, e.g. 5m, which are converted
+/// to timestamps of the resolution of the formula once it is parsed:
+///   - ms, s, m, h, d
+named!(bound<&str, (usize, Option<TimeUnit>)>,
+    map_opt!(
+        pair!(digit, opt!(alt_complete!(tag!("ms") | tag!("s") | tag!("m") | tag!("h") | tag!("d")))),
+        |(n, unit): (&str, Option<&str>)| {
+            let n = n.parse::<usize>().ok()?;
+            match unit {
+                Some(unit) => Some((n, Some(unit.parse().ok()?))),
+                None => Some((n, None)),
+            }
+        }
+    )
+);
+
 #[cfg(test)]
 mod tests {
     use constants::{default_start_time, test_formula, TEST_FACT};
     use parse_formula;
+    use parser::formula_syntax_tree::formula_error;
     use parser::formula_syntax_tree::Arg::{Cst, Var};
 
     use super::*;
@@ -668,6 +686,48 @@ mod tests {
         println!("[{},{}]", output.start, output.end);
     }
 
+    #[test]
+    fn interval_units_test() {
+        // timestamps are in seconds unless another resolution is given
+        let once = |f: &str| match parse_formula(f) {
+            Formula::Once(_, interval) => interval,
+            f => panic!("expected ONCE, got {:?}", f),
+        };
+        assert_eq!(once("ONCE[0, 5m] A(x)"), TimeInterval::new(TS::new(0), TS::new(300)));
+        assert_eq!(once("ONCE[0, 5m] A(x)").to_string(), "[0,5m]");
+
+        let output = once("ONCE(1h,2d] A(x)");
+        assert_eq!(output, TimeInterval::new(TS::new(3601), TS::new(172800)));
+        assert_eq!(output.to_string(), "(1h,2d]");
+        assert_eq!(once(&format!("ONCE{} A(x)", output)), output);
+
+        let output = once("ONCE[2000ms,*) A(x)");
+        assert_eq!(output, TimeInterval::new(TS::new(2), TS::INFINITY));
+        assert_eq!(output.to_string(), "[2s,*)");
+
+        assert!(matches!(parse_formula("ONCE[0,1500ms] A(x)"), FormulaError(_)));
+        assert!(matches!(parse_formula("ONCE[60,1m] A(x)"), Formula::Once(_, _)));
+        assert_eq!(parse_formula("ONCE[0,1m] A(x)"), parse_formula("ONCE[0,60] A(x)"));
+        assert_eq!(parse_formula_with_resolution("ONCE[0,1m] A(x)", TimeUnit::Millisecond), parse_formula("ONCE[0,60000] A(x)"));
+        assert!(matches!(parse_formula_with_resolution("ONCE[0,1s] A(x)", TimeUnit::Minute), FormulaError(_)));
+        assert_eq!(parse_formula_with_resolution("ONCE[0,1m] A(x)", TimeUnit::Millisecond).to_string(), "(<O>[0,1m]A(x))");
+    }
+
+    #[test]
+    fn empty_interval_test() {
+        let (_, output) = interval("(0,5]").unwrap();
+        assert_eq!(output, TimeInterval::with_bounds(TS::new(0), true, TS::new(5), false));
+
+        // reported as written
+        let error = formula_error(&parse_formula("ONCE(3,4) A(x)")).unwrap();
+        assert!(error.contains("(3,4)"), "{}", error);
+        let error = formula_error(&parse_formula("A(x) SINCE[5,2) B(x)")).unwrap();
+        assert!(error.contains("[5,2)"), "{}", error);
+        let error = formula_error(&parse_formula("ONCE[2m,60] A(x)")).unwrap();
+        assert!(error.contains("[2m,60]"), "{}", error);
+        assert!(formula_error(&parse_formula("ONCE[0,0] A(x)")).is_none());
+    }
+
     #[test]
     fn interval_test() {
         let x = "[0,*)";
@@ -823,11 +883,10 @@ mod tests {
     fn historically_basic() {
         let input = &format!("PAST_ALWAYS [0,1] {s}", s = TEST_FACT);
         let output = parse_formula(input);
-        //let expected_output = build_historically( TEST_FORMULA!(), TimeInterval::new(TS::new(0), TS::new(1)));
-        let expected_output = build_not(build_once(
-            build_not(test_formula(None)),
+        let expected_output = build_historically(
+            test_formula(None),
             TimeInterval::new(TS::new(0), TS::new(1)),
-        ));
+        );
         assert_eq!(expected_output, output);
     }
 
@@ -835,23 +894,49 @@ mod tests {
     fn always_basic() {
         let input = &format!("ALWAYS [0,1] {s}", s = TEST_FACT);
         let output = parse_formula(input);
-        //let expected_output = build_historically( TEST_FORMULA!(), TimeInterval::new(TS::new(0), TS::new(1)));
-        let expected_output = build_not(build_eventually(
-            build_not(test_formula(None)),
+        let expected_output = build_always(
+            test_formula(None),
             TimeInterval::new(TS::new(0), TS::new(1)),
-        ));
+        );
         assert_eq!(expected_output, output);
 
         let input = &format!("ALWAYS [0,1) {s}", s = TEST_FACT);
         let output = parse_formula(input);
-        //let expected_output = build_historically( TEST_FORMULA!(), TimeInterval::new(TS::new(0), TS::new(1)));
-        let expected_output = build_not(build_eventually(
-            build_not(test_formula(None)),
+        let expected_output = build_always(
+            test_formula(None),
             TimeInterval::new(TS::new(0), TS::new(0)),
-        ));
+        );
         assert_eq!(expected_output, output);
     }
 
+    #[test]
+    fn always_is_no_fact() {
+        // the keywords are tried before facts, which would take them for a predicate name
+        let expected_output = build_conj(
+            build_fact("q", vec!["x"]),
+            build_always(
+                test_formula(None),
+                TimeInterval::new(TS::new(1), TS::new(2)),
+            ),
+        );
+        let input = &format!("q(x) AND ALWAYS(0,3) {s}", s = TEST_FACT);
+        assert_eq!(expected_output, parse_formula(input));
+
+        let input = &format!("ALWAYS(0,3) {s}", s = TEST_FACT);
+        let expected_output = build_always(
+            test_formula(None),
+            TimeInterval::new(TS::new(1), TS::new(2)),
+        );
+        assert_eq!(expected_output, parse_formula(input));
+
+        let input = &format!("PAST_ALWAYS(0,3) {s}", s = TEST_FACT);
+        let expected_output = build_historically(
+            test_formula(None),
+            TimeInterval::new(TS::new(1), TS::new(2)),
+        );
+        assert_eq!(expected_output, parse_formula(input));
+    }
+
     #[test]
     fn arg_string() {
         let output = parse_formula("p('foo')");
@@ -860,6 +945,14 @@ mod tests {
         assert_eq!(expected, output);
     }
 
+    #[test]
+    fn arg_negative() {
+        let output = parse_formula("p(-5)");
+        let expected = Formula::Fact("p".to_string(), vec![Arg::Cst(Int(-5))]);
+
+        assert_eq!(expected, output);
+    }
+
     #[test]
     fn fact_multiple_arguments_integers() {
         let output = parse_formula("p(4, 3, 2, 1)");
@@ -1,10 +1,10 @@
 use std::{cmp, fmt};
 
-use constants::CONJ_NEG_ERROR;
+use constants::{CONJ_NEG_ERROR, EMPTY_INTERVAL_ERROR, INTERVAL_UNIT_ERROR};
 use parser::formula_syntax_tree::Constant::{Int, Str};
 use parser::formula_syntax_tree::Formula::*;
 use serde_json::Value;
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use abomonation::Abomonation;
 use std::cmp::Ordering;
@@ -37,6 +37,8 @@ pub enum Formula {
     NegUntil(Box<Formula>, Box<Formula>, TimeInterval),
     Once(Box<Formula>, TimeInterval),
     Eventually(Box<Formula>, TimeInterval),
+    Historically(Box<Formula>, TimeInterval),
+    Always(Box<Formula>, TimeInterval),
 
     Next(Box<Formula>, TimeInterval),
     Prev(Box<Formula>, TimeInterval),
@@ -359,22 +361,28 @@ impl fmt::Display for Formula {
             Once(lhs, time) => {
                 let mut tmp = String::new();
                 tmp.push_str(&"<O>");
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 tmp.push_str(&(*lhs).to_string());
                 str.push_str(&bracket_string(tmp))
             }
             Eventually(lhs, time) => {
                 let mut tmp = String::new();
                 tmp.push_str(&"<E>");
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
+                tmp.push_str(&(*lhs).to_string());
+                str.push_str(&bracket_string(tmp))
+            }
+            Historically(lhs, time) => {
+                let mut tmp = String::new();
+                tmp.push_str(&"<H>");
+                tmp.push_str(&time.to_string());
+                tmp.push_str(&(*lhs).to_string());
+                str.push_str(&bracket_string(tmp))
+            }
+            Always(lhs, time) => {
+                let mut tmp = String::new();
+                tmp.push_str(&"<A>");
+                tmp.push_str(&time.to_string());
                 tmp.push_str(&(*lhs).to_string());
                 str.push_str(&bracket_string(tmp))
             }
@@ -383,11 +391,7 @@ impl fmt::Display for Formula {
                 tmp.push_str(&(*lhs).to_string());
                 tmp.push_str(&"<S>");
                 tmp.push_str(&(rhs).to_string());
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 str.push_str(&bracket_string(tmp))
             }
             Until(lhs, rhs, time) => {
@@ -395,11 +399,7 @@ impl fmt::Display for Formula {
                 tmp.push_str(&(*lhs).to_string());
                 tmp.push_str(&"<U>");
                 tmp.push_str(&(rhs).to_string());
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 str.push_str(&bracket_string(tmp))
             }
             NegSince(lhs, rhs, time) => {
@@ -407,11 +407,7 @@ impl fmt::Display for Formula {
                 tmp.push_str(&(*lhs).to_string());
                 tmp.push_str(&"<Neg_S>");
                 tmp.push_str(&(rhs).to_string());
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 str.push_str(&bracket_string(tmp))
             }
             NegUntil(lhs, rhs, time) => {
@@ -419,11 +415,7 @@ impl fmt::Display for Formula {
                 tmp.push_str(&(*lhs).to_string());
                 tmp.push_str(&"<Neg_U>");
                 tmp.push_str(&(rhs).to_string());
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 str.push_str(&bracket_string(tmp))
             }
             Equals(var, val) => {
@@ -472,22 +464,14 @@ impl fmt::Display for Formula {
             Next(lhs, time) => {
                 let mut tmp = String::new();
                 tmp.push_str(&"next");
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 tmp.push_str(&(*lhs).to_string());
                 str.push_str(&bracket_string(tmp))
             }
             Prev(lhs, time) => {
                 let mut tmp = String::new();
                 tmp.push_str(&"prev");
-                tmp.push_str(&"[");
-                tmp.push_str(&*time.start.to_string());
-                tmp.push_str(&", ");
-                tmp.push_str(&*time.end.to_string());
-                tmp.push_str(&"]");
+                tmp.push_str(&time.to_string());
                 tmp.push_str(&(*lhs).to_string());
                 str.push_str(&bracket_string(tmp))
             }
@@ -520,6 +504,7 @@ pub fn free_variables(f: Formula) -> BTreeSet<Arg> {
             let l = free_variables(*lhs);
             return l;
         }
+        Historically(lhs, _time) | Always(lhs, _time) => free_variables(*lhs),
         Since(lhs, rhs, _time)
         | Until(lhs, rhs, _time)
         | NegSince(lhs, rhs, _time)
@@ -593,6 +578,7 @@ pub fn free_variables_original_order(f: Formula) -> Vec<Arg> {
         Fact(_name, args) => args.clone(),
         Next(lhs, _time) | Prev(lhs, _time) => free_variables_original_order(*lhs),
         Once(lhs, _time) | Eventually(lhs, _time) => free_variables_original_order(*lhs),
+        Historically(lhs, _time) | Always(lhs, _time) => free_variables_original_order(*lhs),
         Since(_lhs, rhs, _time)
         | Until(_lhs, rhs, _time)
         | NegSince(_lhs, rhs, _time)
@@ -620,6 +606,107 @@ pub fn free_variables_original_order(f: Formula) -> Vec<Arg> {
     }
 }
 
+// Arities with which each predicate is used in the formula
+pub fn predicate_arities(f: &Formula) -> BTreeMap<String, BTreeSet<usize>> {
+    let mut arities: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+    collect_arities(f, &mut arities);
+    arities
+}
+
+fn collect_arities(f: &Formula, arities: &mut BTreeMap<String, BTreeSet<usize>>) {
+    match f {
+        Fact(name, args) => {
+            arities.entry(name.clone()).or_default().insert(args.len());
+        }
+        CstFact(name, args) => {
+            arities.entry(name.clone()).or_default().insert(args.len());
+        }
+        Not(f)
+        | Exists(_, f)
+        | Once(f, _)
+        | Eventually(f, _)
+        | Historically(f, _)
+        | Always(f, _)
+        | Next(f, _)
+        | Prev(f, _) => collect_arities(f, arities),
+        Conj(lhs, rhs)
+        | Disj(lhs, rhs)
+        | AntiConj(lhs, rhs)
+        | Since(lhs, rhs, _)
+        | Until(lhs, rhs, _)
+        | NegSince(lhs, rhs, _)
+        | NegUntil(lhs, rhs, _) => {
+            collect_arities(lhs, arities);
+            collect_arities(rhs, arities);
+        }
+        _ => {}
+    }
+}
+
+// First error the builders left in the formula, e.g. for an empty interval
+pub fn formula_error(f: &Formula) -> Option<String> {
+    match f {
+        FormulaError(message) => Some(message.clone()),
+        Not(f)
+        | Exists(_, f)
+        | Once(f, _)
+        | Eventually(f, _)
+        | Historically(f, _)
+        | Always(f, _)
+        | Next(f, _)
+        | Prev(f, _) => formula_error(f),
+        Conj(lhs, rhs)
+        | Disj(lhs, rhs)
+        | AntiConj(lhs, rhs)
+        | Since(lhs, rhs, _)
+        | Until(lhs, rhs, _)
+        | NegSince(lhs, rhs, _)
+        | NegUntil(lhs, rhs, _) => formula_error(lhs).or_else(|| formula_error(rhs)),
+        _ => None,
+    }
+}
+
+// Whether the verdict of a time point can depend on later time points
+pub fn has_future_operator(f: &Formula) -> bool {
+    match f {
+        Until(_, _, _) | NegUntil(_, _, _) | Eventually(_, _) | Always(_, _) | Next(_, _) => true,
+        Not(f) | Exists(_, f) | Once(f, _) | Historically(f, _) | Prev(f, _) => has_future_operator(f),
+        Conj(lhs, rhs) | Disj(lhs, rhs) | AntiConj(lhs, rhs) | Since(lhs, rhs, _) | NegSince(lhs, rhs, _) => {
+            has_future_operator(lhs) || has_future_operator(rhs)
+        }
+        _ => false,
+    }
+}
+
+// Converts the interval bounds written with a unit to timestamps of the given resolution
+pub fn in_resolution(f: Formula, resolution: TimeUnit) -> Formula {
+    fn convert<B: FnOnce(TimeInterval) -> Formula>(interval: TimeInterval, resolution: TimeUnit, build: B) -> Formula {
+        match interval.in_resolution(resolution) {
+            Ok(interval) => build(interval),
+            Err(bound) => FormulaError(format!("{}: {}", INTERVAL_UNIT_ERROR, bound)),
+        }
+    }
+    let sub = |f: Box<Formula>| in_resolution(*f, resolution);
+    match f {
+        Not(f) => Not(Box::new(sub(f))),
+        Exists(vars, f) => Exists(vars, Box::new(sub(f))),
+        Conj(lhs, rhs) => Conj(Box::new(sub(lhs)), Box::new(sub(rhs))),
+        Disj(lhs, rhs) => Disj(Box::new(sub(lhs)), Box::new(sub(rhs))),
+        AntiConj(lhs, rhs) => AntiConj(Box::new(sub(lhs)), Box::new(sub(rhs))),
+        Since(lhs, rhs, i) => convert(i, resolution, |i| build_since(sub(lhs), sub(rhs), i)),
+        Until(lhs, rhs, i) => convert(i, resolution, |i| build_until(sub(lhs), sub(rhs), i)),
+        NegSince(lhs, rhs, i) => convert(i, resolution, |i| build_neg_since(sub(lhs), sub(rhs), i)),
+        NegUntil(lhs, rhs, i) => convert(i, resolution, |i| build_neg_until(sub(lhs), sub(rhs), i)),
+        Once(f, i) => convert(i, resolution, |i| build_once(sub(f), i)),
+        Eventually(f, i) => convert(i, resolution, |i| build_eventually(sub(f), i)),
+        Historically(f, i) => convert(i, resolution, |i| build_historically(sub(f), i)),
+        Always(f, i) => convert(i, resolution, |i| build_always(sub(f), i)),
+        Next(f, i) => convert(i, resolution, |i| build_next(sub(f), i)),
+        Prev(f, i) => convert(i, resolution, |i| build_prev(sub(f), i)),
+        f => f,
+    }
+}
+
 // ====================== FORMULA BUILDERS ========================
 
 pub fn build_true() -> Formula {
@@ -730,44 +817,55 @@ pub fn build_forall(var: Vec<&str>, f: Formula) -> Formula {
     build_not(build_exists(var, build_not(f)))
 }
 
+// temporal operators with an interval that contains no distance are rejected, bounds with a unit
+// are checked once they are converted to timestamps
+fn check_interval(interval: TimeInterval, f: Formula) -> Formula {
+    let converted = interval.resolution.is_some() || (interval.start_unit.is_none() && interval.end_unit.is_none());
+    if converted && interval.is_empty() {
+        FormulaError(format!("{}: {}", EMPTY_INTERVAL_ERROR, interval))
+    } else {
+        f
+    }
+}
+
 pub fn build_since(lhs: Formula, rhs: Formula, interval: TimeInterval) -> Formula {
-    Since(Box::new(lhs), Box::new(rhs), interval)
+    check_interval(interval, Since(Box::new(lhs), Box::new(rhs), interval))
 }
 
 pub fn build_neg_since(lhs: Formula, rhs: Formula, interval: TimeInterval) -> Formula {
-    NegSince(Box::new(lhs), Box::new(rhs), interval)
+    check_interval(interval, NegSince(Box::new(lhs), Box::new(rhs), interval))
 }
 
 pub fn build_until(lhs: Formula, rhs: Formula, interval: TimeInterval) -> Formula {
-    Until(Box::new(lhs), Box::new(rhs), interval)
+    check_interval(interval, Until(Box::new(lhs), Box::new(rhs), interval))
 }
 
 pub fn build_neg_until(lhs: Formula, rhs: Formula, interval: TimeInterval) -> Formula {
-    NegUntil(Box::new(lhs), Box::new(rhs), interval)
+    check_interval(interval, NegUntil(Box::new(lhs), Box::new(rhs), interval))
 }
 
 pub fn build_once(rhs: Formula, interval: TimeInterval) -> Formula {
-    Once(Box::new(rhs), interval)
+    check_interval(interval, Once(Box::new(rhs), interval))
 }
 
 pub fn build_eventually(rhs: Formula, interval: TimeInterval) -> Formula {
-    Eventually(Box::new(rhs), interval)
+    check_interval(interval, Eventually(Box::new(rhs), interval))
 }
 
 pub fn build_historically(rhs: Formula, interval: TimeInterval) -> Formula {
-    build_not(build_once(build_not(rhs), interval))
+    check_interval(interval, Historically(Box::new(rhs), interval))
 }
 
 pub fn build_always(rhs: Formula, interval: TimeInterval) -> Formula {
-    build_not(build_eventually(build_not(rhs), interval))
+    check_interval(interval, Always(Box::new(rhs), interval))
 }
 
 pub fn build_next(rhs: Formula, interval: TimeInterval) -> Formula {
-    Next(Box::new(rhs), interval)
+    check_interval(interval, Next(Box::new(rhs), interval))
 }
 
 pub fn build_prev(rhs: Formula, interval: TimeInterval) -> Formula {
-    Prev(Box::new(rhs), interval)
+    check_interval(interval, Prev(Box::new(rhs), interval))
 }
 
 #[cfg(test)]
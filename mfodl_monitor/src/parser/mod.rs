@@ -2,3 +2,8 @@ pub mod csv_parser;
 pub mod json_parser;
 pub mod formula_syntax_tree;
 pub mod formula_parser;
+pub mod binary_log;
+pub mod table_csv;
+pub mod validation;
+pub mod signature;
+pub mod monpoly;
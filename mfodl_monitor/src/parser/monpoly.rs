@@ -0,0 +1,675 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+
+use dataflow_constructor::types::TypedEvent;
+use parser::csv_parser::Segment;
+use parser::formula_syntax_tree::Constant::{Int, Str};
+use parser::formula_syntax_tree::*;
+use parser::signature::{to_constant, ArgType, Signatures};
+use timeunits::{convert_to_timestamps, TimeInterval, TimeUnit, TS};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    // digits, in intervals possibly followed by a time unit, e.g. 5m or 10ms
+    Num(String),
+    Str(String),
+    Sym(char),
+}
+
+// tokens with the line they start on
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, String> {
+    tokenize_from(input, 1)
+}
+
+// tokens of an input that starts on the given line
+fn tokenize_from(input: &str, first_line: usize) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = first_line;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' && chars.get(i + 1) == Some(&'*') {
+            // comment (* ... *)
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&')')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(format!("Line {}: unterminated comment", line));
+            }
+            i += 2;
+        } else if c == '"' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != '"' {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(format!("Line {}: unterminated string", line));
+            }
+            tokens.push((Token::Str(chars[start..i].iter().collect()), line));
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            let is_num = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) && word.len() <= digits.len() + 2;
+            tokens.push((if is_num { Token::Num(word) } else { Token::Ident(word) }, line));
+        } else {
+            tokens.push((Token::Sym(c), line));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+// precedence of the binary operators, SINCE and UNTIL bind weakest
+fn binary_precedence(token: &Token) -> Option<(u8, bool)> {
+    match token {
+        Token::Ident(op) => match op.as_str() {
+            "SINCE" | "UNTIL" => Some((1, true)),
+            "EQUIV" => Some((4, false)),
+            "IMPLIES" => Some((5, true)),
+            "OR" => Some((6, false)),
+            "AND" => Some((7, false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+const TEMPORAL_PRECEDENCE: u8 = 2;
+const QUANTIFIER_PRECEDENCE: u8 = 3;
+
+enum Term {
+    Cst(Constant),
+    Var(String),
+}
+
+struct FormulaParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    // variables with characters the policy syntax does not allow get a new name
+    renamed: HashMap<String, String>,
+    identifiers: HashSet<String>,
+    // unit of the timestamps that interval bounds with a unit are converted to
+    resolution: TimeUnit,
+}
+
+impl FormulaParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, msg: &str) -> String {
+        match self.tokens.get(self.pos) {
+            Some((token, line)) => format!("Line {}: {} at {:?}", line, msg, token),
+            None => format!("{} at the end of the formula", msg),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(&Token::Sym(c)) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected {}", c)))
+        }
+    }
+
+    fn is_sym(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Sym(c))
+    }
+
+    fn variable(&mut self, name: &str) -> String {
+        if name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return name.to_string();
+        }
+        if let Some(v) = self.renamed.get(name) {
+            return v.clone();
+        }
+        let base: String = name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        let base = if base.is_empty() { "v".to_string() } else { base };
+        let mut v = base.clone();
+        let mut i = 0;
+        while self.identifiers.contains(&v) {
+            v = format!("{}{}", base, i);
+            i += 1;
+        }
+        self.identifiers.insert(v.clone());
+        self.renamed.insert(name.to_string(), v.clone());
+        v
+    }
+
+    fn formula(&mut self, min_precedence: u8) -> Result<Formula, String> {
+        let mut lhs = self.unary()?;
+        while let Some((precedence, right_assoc)) = self.peek().and_then(binary_precedence) {
+            if precedence < min_precedence {
+                break;
+            }
+            let op = match self.next() {
+                Some(Token::Ident(op)) => op,
+                _ => unreachable!(),
+            };
+            let interval = if op == "SINCE" || op == "UNTIL" { self.interval()? } else { None };
+            let rhs = self.formula(if right_assoc { precedence } else { precedence + 1 })?;
+            let interval = interval.unwrap_or_else(unbounded);
+            lhs = match (op.as_str(), lhs) {
+                ("SINCE", Formula::Not(lhs)) => build_neg_since(*lhs, rhs, interval),
+                ("SINCE", lhs) => build_since(lhs, rhs, interval),
+                ("UNTIL", Formula::Not(lhs)) => build_neg_until(*lhs, rhs, interval),
+                ("UNTIL", lhs) => build_until(lhs, rhs, interval),
+                ("EQUIV", lhs) => build_iff(lhs, rhs),
+                ("IMPLIES", lhs) => build_implication(lhs, rhs),
+                ("OR", lhs) => build_disj(lhs, rhs),
+                (_, lhs) => build_conj(lhs, rhs),
+            };
+            if let Formula::FormulaError(e) = lhs {
+                return Err(e);
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Formula, String> {
+        let keyword = match self.peek() {
+            Some(Token::Ident(k)) => k.clone(),
+            _ => return self.atom(),
+        };
+        let temporal: Option<fn(Formula, TimeInterval) -> Formula> = match keyword.as_str() {
+            "ONCE" => Some(build_once),
+            "EVENTUALLY" => Some(build_eventually),
+            "ALWAYS" => Some(build_always),
+            "PAST_ALWAYS" | "HISTORICALLY" => Some(build_historically),
+            "PREVIOUS" | "PREV" => Some(build_prev),
+            "NEXT" => Some(build_next),
+            _ => None,
+        };
+        if let Some(build) = temporal {
+            self.pos += 1;
+            let interval = self.interval()?.unwrap_or_else(unbounded);
+            let f = self.formula(TEMPORAL_PRECEDENCE + 1)?;
+            return match build(f, interval) {
+                Formula::FormulaError(e) => Err(e),
+                f => Ok(f),
+            };
+        }
+        match keyword.as_str() {
+            "NOT" => {
+                self.pos += 1;
+                Ok(build_not(self.unary()?))
+            }
+            "EXISTS" | "FORALL" => {
+                self.pos += 1;
+                let mut vars = Vec::new();
+                loop {
+                    match self.next() {
+                        Some(Token::Ident(v)) => vars.push(self.variable(&v)),
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("Expected a variable"));
+                        }
+                    }
+                    if !self.is_sym(',') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                self.expect('.')?;
+                let f = self.formula(QUANTIFIER_PRECEDENCE + 1)?;
+                let vars: Vec<&str> = vars.iter().map(|v| v.as_str()).collect();
+                Ok(if keyword == "EXISTS" { build_exists(vars, f) } else { build_forall(vars, f) })
+            }
+            _ => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Formula, String> {
+        match self.peek().cloned() {
+            Some(Token::Sym('(')) => {
+                self.pos += 1;
+                let f = self.formula(0)?;
+                self.expect(')')?;
+                Ok(f)
+            }
+            Some(Token::Ident(ref k)) if k == "TRUE" => {
+                self.pos += 1;
+                Ok(build_true())
+            }
+            Some(Token::Ident(ref k)) if k == "FALSE" => {
+                self.pos += 1;
+                Ok(build_false())
+            }
+            Some(Token::Ident(name)) if self.tokens.get(self.pos + 1).map(|(t, _)| t) == Some(&Token::Sym('(')) => {
+                self.pos += 2;
+                let mut args = Vec::new();
+                while !self.is_sym(')') {
+                    if !args.is_empty() {
+                        self.expect(',')?;
+                    }
+                    args.push(match self.term()? {
+                        Term::Cst(c) => Arg::Cst(c),
+                        Term::Var(v) => Arg::Var(v),
+                    });
+                }
+                self.pos += 1;
+                Ok(build_fact_args(&name, args))
+            }
+            _ => {
+                let lhs = self.term()?;
+                if !self.is_sym('=') {
+                    return Err(self.error("Expected = (only equality is supported)"));
+                }
+                self.pos += 1;
+                match (lhs, self.term()?) {
+                    (Term::Var(v), Term::Var(w)) => Ok(build_equals(&v, Arg::Var(w))),
+                    (Term::Var(v), Term::Cst(c)) | (Term::Cst(c), Term::Var(v)) => Ok(build_equals(&v, Arg::Cst(c))),
+                    (Term::Cst(a), Term::Cst(b)) => Ok(if a == b { build_true() } else { build_false() }),
+                }
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        match self.next() {
+            Some(Token::Ident(v)) => Ok(Term::Var(self.variable(&v))),
+            Some(Token::Num(n)) => match n.parse::<i32>() {
+                Ok(i) => Ok(Term::Cst(Int(i))),
+                Err(_) => {
+                    self.pos -= 1;
+                    Err(self.error("Expected an int"))
+                }
+            },
+            Some(Token::Str(s)) if !s.contains('\'') => Ok(Term::Cst(Str(s))),
+            _ => {
+                self.pos -= 1;
+                Err(self.error("Expected a variable or constant"))
+            }
+        }
+    }
+
+    // A bound in timestamps and the unit it was written in
+    fn bound(&mut self) -> Result<(usize, Option<TimeUnit>), String> {
+        match self.next() {
+            Some(Token::Num(n)) => {
+                let digits = n.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                let bound = match (digits.parse::<usize>(), &n[digits.len()..]) {
+                    (Ok(b), "") => Some((b, None)),
+                    (Ok(b), unit) => unit
+                        .parse::<TimeUnit>()
+                        .ok()
+                        .and_then(|unit| Some((convert_to_timestamps(b, unit, self.resolution)?, Some(unit)))),
+                    _ => None,
+                };
+                bound.ok_or_else(|| {
+                    self.pos -= 1;
+                    self.error("Invalid bound, expected a whole number of timestamps with an optional unit ms, s, m, h or d")
+                })
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.error("Expected a bound"))
+            }
+        }
+    }
+
+    // an optional interval, e.g. [0,5], (1,10s] or [2,*)
+    fn interval(&mut self) -> Result<Option<TimeInterval>, String> {
+        let left_open = match self.peek() {
+            Some(Token::Sym('[')) => false,
+            // a bracket directly after an operator starts an interval only if it is followed by a bound
+            Some(Token::Sym('(')) if matches!(self.tokens.get(self.pos + 1), Some((Token::Num(_), _))) => true,
+            _ => return Ok(None),
+        };
+        self.pos += 1;
+        let start = self.bound()?;
+        self.expect(',')?;
+        if self.is_sym('*') {
+            self.pos += 1;
+            self.expect(')')?;
+            let interval = TimeInterval::with_bounds(TS::new(start.0), left_open, TS::INFINITY, false);
+            return Ok(Some(TimeInterval { start_unit: start.1, resolution: Some(self.resolution), ..interval }));
+        }
+        let end = self.bound()?;
+        let right_open = match self.next() {
+            Some(Token::Sym(']')) => false,
+            Some(Token::Sym(')')) => true,
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("Expected ] or )"));
+            }
+        };
+        let interval = TimeInterval::with_bounds(TS::new(start.0), left_open, TS::new(end.0), right_open);
+        Ok(Some(TimeInterval { start_unit: start.1, end_unit: end.1, resolution: Some(self.resolution), ..interval }))
+    }
+}
+
+fn unbounded() -> TimeInterval {
+    TimeInterval::new(TS::new(0), TS::INFINITY)
+}
+
+/// Parses a formula in MonPoly syntax: `AND`, `OR`, `NOT`, `IMPLIES`, `EQUIV`, `EXISTS x.`,
+/// `FORALL x.`, the temporal operators with optional intervals (`[0,*)` if omitted) whose bounds
+/// may have time units like in `parse_formula`, string constants in double quotes, equalities and
+/// `(* *)` comments. The operators have MonPoly's precedence, i.e. `SINCE` and `UNTIL` bind weakest and the bodies of quantifiers and unary
+/// temporal operators extend as far as possible. Bounds with a unit are converted to timestamps of
+/// the given resolution.
+pub fn parse_monpoly_formula(input: &str, resolution: TimeUnit) -> Result<Formula, String> {
+    let tokens = tokenize(input)?;
+    let identifiers = tokens
+        .iter()
+        .filter_map(|(t, _)| match t {
+            Token::Ident(i) => Some(i.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut parser = FormulaParser { tokens, pos: 0, renamed: HashMap::new(), identifiers, resolution };
+    let f = parser.formula(0)?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.error("Unexpected token"));
+    }
+    Ok(f)
+}
+
+fn native_interval(i: &TimeInterval) -> String {
+    let open = if i.start_open { '(' } else { '[' };
+    match (i.start, i.end) {
+        (TS::FINITE(a), TS::FINITE(b)) => format!("{}{},{}{}", open, a, b, if i.end_open { ')' } else { ']' }),
+        (TS::FINITE(a), _) => format!("{}{},*)", open, a),
+        _ => i.to_string(),
+    }
+}
+
+/// Writes a formula in the policy syntax of `parse_formula`, fully bracketed.
+pub fn to_native_policy(f: &Formula) -> String {
+    match f {
+        Formula::True => "TRUE".to_string(),
+        Formula::False => "FALSE".to_string(),
+        Formula::Fact(name, args) => {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            format!("{}({})", name, args.join(","))
+        }
+        Formula::Equals(v, arg) => format!("({} = {})", v, arg),
+        Formula::Not(f) => format!("NOT ({})", to_native_policy(f)),
+        Formula::Conj(l, r) => format!("(({}) AND ({}))", to_native_policy(l), to_native_policy(r)),
+        Formula::AntiConj(l, r) => format!("(({}) AND NOT ({}))", to_native_policy(l), to_native_policy(r)),
+        Formula::Disj(l, r) => format!("(({}) OR ({}))", to_native_policy(l), to_native_policy(r)),
+        Formula::Exists(vars, f) => format!("(EXISTS {}. ({}))", vars.join(","), to_native_policy(f)),
+        Formula::Since(l, r, i) => format!("(({}) SINCE{} ({}))", to_native_policy(l), native_interval(i), to_native_policy(r)),
+        Formula::NegSince(l, r, i) => format!("(NOT ({}) SINCE{} ({}))", to_native_policy(l), native_interval(i), to_native_policy(r)),
+        Formula::Until(l, r, i) => format!("(({}) UNTIL{} ({}))", to_native_policy(l), native_interval(i), to_native_policy(r)),
+        Formula::NegUntil(l, r, i) => format!("(NOT ({}) UNTIL{} ({}))", to_native_policy(l), native_interval(i), to_native_policy(r)),
+        Formula::Once(f, i) => format!("(ONCE{} ({}))", native_interval(i), to_native_policy(f)),
+        Formula::Eventually(f, i) => format!("(EVENTUALLY{} ({}))", native_interval(i), to_native_policy(f)),
+        Formula::Historically(f, i) => format!("(PAST_ALWAYS{} ({}))", native_interval(i), to_native_policy(f)),
+        Formula::Always(f, i) => format!("(ALWAYS{} ({}))", native_interval(i), to_native_policy(f)),
+        Formula::Prev(f, i) => format!("(PREVIOUS{} ({}))", native_interval(i), to_native_policy(f)),
+        Formula::Next(f, i) => format!("(NEXT{} ({}))", native_interval(i), to_native_policy(f)),
+        f => f.to_string(),
+    }
+}
+
+fn log_value(token: Token, arg_type: Option<ArgType>) -> Result<Constant, String> {
+    match (token, arg_type) {
+        (Token::Num(n), _) => to_constant(&n, arg_type),
+        (t, Some(ArgType::Int)) => Err(format!("{:?} is not an int", t)),
+        (Token::Ident(s), _) | (Token::Str(s), _) if !s.contains('\'') => Ok(Str(s)),
+        (t, _) => Err(format!("Unexpected {:?}", t)),
+    }
+}
+
+// time points of a log with the events (predicate, arguments) of each
+type LogTimePoints = Vec<(usize, Vec<(String, Vec<Constant>)>)>;
+
+// Adds the time points and events of the tokens to the ones read so far, the events before the
+// first timestamp of the tokens belong to the last time point read.
+fn read_log_tokens<T: Iterator<Item = (Token, usize)>>(tokens: T, signatures: Option<&Signatures>, time_points: &mut LogTimePoints) -> Result<(), String> {
+    let mut tokens = tokens.peekable();
+
+    while let Some((token, line)) = tokens.next() {
+        let error = |msg: String| format!("Line {}: {}", line, msg);
+        match token {
+            Token::Sym('@') => match tokens.next() {
+                Some((Token::Num(ts), _)) => {
+                    let ts = ts.parse::<usize>().map_err(|_| error(format!("invalid timestamp {}", ts)))?;
+                    if time_points.last().is_some_and(|(last, _)| *last > ts) {
+                        return Err(error(format!("timestamp {} is smaller than the previous one", ts)));
+                    }
+                    time_points.push((ts, Vec::new()));
+                }
+                _ => return Err(error("expected a timestamp after @".to_string())),
+            },
+            Token::Sym(';') => {}
+            Token::Ident(name) => {
+                let events = match time_points.last_mut() {
+                    Some((_, events)) => events,
+                    None => return Err(error(format!("event {} before the first timestamp", name))),
+                };
+                let types = match signatures {
+                    Some(signatures) => Some(signatures.get(&name).ok_or_else(|| error(format!("predicate {} is not declared in the signature", name)))?),
+                    None => None,
+                };
+                // one or more tuples
+                let mut tuples = 0;
+                while let Some((Token::Sym('('), _)) = tokens.peek() {
+                    tokens.next();
+                    let mut args = Vec::new();
+                    loop {
+                        let value = match tokens.next() {
+                            Some((Token::Sym(')'), _)) if args.is_empty() => break,
+                            // a signed int
+                            Some((Token::Sym('-'), _)) => match tokens.next() {
+                                Some((Token::Num(n), _)) => Token::Num(format!("-{}", n)),
+                                _ => return Err(error(format!("malformed tuple of {}", name))),
+                            },
+                            Some((Token::Sym(_), _)) | None => return Err(error(format!("malformed tuple of {}", name))),
+                            Some((value, _)) => value,
+                        };
+                        let arg_type = types.and_then(|types| types.get(args.len()).cloned());
+                        args.push(log_value(value, arg_type).map_err(&error)?);
+                        match tokens.next() {
+                            Some((Token::Sym(','), _)) => {}
+                            Some((Token::Sym(')'), _)) => break,
+                            _ => return Err(error(format!("malformed tuple of {}", name))),
+                        }
+                    }
+                    if types.is_some_and(|types| types.len() != args.len()) {
+                        return Err(error(format!("{} is declared with {} arguments, found {}", name, types.unwrap().len(), args.len())));
+                    }
+                    events.push((name.clone(), args));
+                    tuples += 1;
+                }
+                if tuples == 0 {
+                    return Err(error(format!("expected a tuple after {}", name)));
+                }
+            }
+            t => return Err(error(format!("unexpected {:?}", t))),
+        }
+    }
+    Ok(())
+}
+
+/// Parses a MonPoly log, e.g. `@10 A(1,2)(3,-4) B() @12 C("x")`. Every `@ts` starts a new time
+/// point, which may have no events. Argument types follow the signatures if given and are guessed
+/// from the values otherwise, where quoted values are always strings.
+pub fn parse_monpoly_log(input: &str, signatures: Option<&Signatures>) -> Result<Vec<(usize, Vec<Formula>)>, String> {
+    let mut time_points = Vec::new();
+    read_log_tokens(tokenize(input)?.into_iter(), signatures, &mut time_points)?;
+    Ok(time_points
+        .into_iter()
+        .map(|(ts, events)| (ts, events.into_iter().map(|(name, args)| build_fact_const(&name, args)).collect()))
+        .collect())
+}
+
+/// Reads a MonPoly log line by line into one segment of typed events per time point. A time point
+/// is complete once the next timestamp is read, so that segments are produced while the lines of a
+/// file, stdin or a socket arrive. Events, comments and strings end on the line they start on. The first
+/// invalid line ends the segments with its error.
+pub struct MonpolySegments<I> {
+    lines: I,
+    signatures: Option<Signatures>,
+    line: usize,
+    // the last time point can still get events from the next lines
+    time_points: LogTimePoints,
+    complete: VecDeque<(usize, Vec<(String, Vec<Constant>)>)>,
+    tp: usize,
+    done: bool,
+}
+
+impl<I: Iterator<Item = io::Result<String>>> MonpolySegments<I> {
+    pub fn new(lines: I, signatures: Option<Signatures>) -> Self {
+        MonpolySegments {
+            lines,
+            signatures,
+            line: 0,
+            time_points: Vec::new(),
+            complete: VecDeque::new(),
+            tp: 0,
+            done: false,
+        }
+    }
+
+    fn read_line(&mut self, text: &str) -> Result<(), String> {
+        let tokens = tokenize_from(text, self.line)?;
+        read_log_tokens(tokens.into_iter(), self.signatures.as_ref(), &mut self.time_points)?;
+        let last = self.time_points.len().saturating_sub(1);
+        self.complete.extend(self.time_points.drain(..last));
+        Ok(())
+    }
+}
+
+impl<I: Iterator<Item = io::Result<String>>> Iterator for MonpolySegments<I> {
+    type Item = Result<Segment<TypedEvent>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((ts, events)) = self.complete.pop_front() {
+                self.tp += 1;
+                let events = events.into_iter().map(|(name, args)| TypedEvent::Fact(name, args)).collect();
+                return Some(Ok(Segment::Seg(self.tp - 1, ts, events)));
+            }
+            if self.done {
+                return None;
+            }
+            match self.lines.next() {
+                Some(text) => {
+                    self.line += 1;
+                    if let Err(e) = text.map_err(|e| format!("Line {}: {}", self.line, e)).and_then(|text| self.read_line(&text)) {
+                        self.done = true;
+                        return Some(Err(e));
+                    }
+                }
+                None => {
+                    self.done = true;
+                    self.complete.extend(self.time_points.drain(..));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::formula_parser::parse_formula;
+    use parser::formula_syntax_tree::build_fact_const;
+    use parser::formula_syntax_tree::Constant::{Int, Str};
+    use dataflow_constructor::types::TypedEvent;
+    use parser::csv_parser::Segment;
+    use parser::monpoly::{parse_monpoly_formula, parse_monpoly_log, to_native_policy, MonpolySegments};
+    use parser::signature::Signatures;
+    use timeunits::TimeUnit;
+
+    fn same(monpoly: &str, native: &str) {
+        let f = parse_monpoly_formula(monpoly, TimeUnit::Second).unwrap();
+        assert_eq!(f, parse_formula(native), "{}", monpoly);
+        assert_eq!(parse_formula(&to_native_policy(&f)), f, "{}", to_native_policy(&f));
+    }
+
+    #[test]
+    fn monpoly_formulas() {
+        same("A(x,y) AND ONCE[0,5] B(y)", "A(x,y) AND ONCE[0,5] B(y)");
+        same("(* withdrawals *) A(x) AND NOT B(x)", "A(x) AND NOT B(x)");
+        same("NOT A(x) SINCE (0,10] B(x)", "NOT A(x) SINCE(0,10] B(x)");
+        same("A(x) AND B(x) SINCE C(x)", "(A(x) AND B(x)) SINCE[0,*) C(x)");
+        same("ONCE A(x) OR B(x)", "ONCE[0,*) (A(x) OR B(x))");
+        same("EXISTS u, v. A(u,v) AND B(v) AND u = \"a\"", "EXISTS u,v. ((A(u,v) AND B(v)) AND u = 'a')");
+        same("PREV [0,1m] A(x) IMPLIES ALWAYS [0,2h) B(x)", "PREVIOUS[0,60] (A(x) IMPLIES ALWAYS[0,7200) B(x))");
+        same("FORALL x. A(x) IMPLIES B(x)", "FORALL x. (A(x) IMPLIES B(x))");
+        same("A(user_id) AND B(userid)", "A(userid0) AND B(userid)");
+
+        assert!(parse_monpoly_formula("A(x) AND", TimeUnit::Second).is_err());
+        assert!(parse_monpoly_formula("A(x) AND x < 3", TimeUnit::Second).is_err());
+        assert!(parse_monpoly_formula("ONCE[0,5y] A(x)", TimeUnit::Second).is_err());
+        assert_eq!(parse_monpoly_formula("ONCE[0,2s] A(x)", TimeUnit::Millisecond), Ok(parse_formula("ONCE[0,2000] A(x)")));
+        assert!(parse_monpoly_formula("A(x) SINCE(3,4) B(x)", TimeUnit::Second).is_err());
+    }
+
+    #[test]
+    fn monpoly_log() {
+        let log = "@10 A(1,2)(3,\"4\") B()\n@10;\n@12 C(abc, 007)";
+        assert_eq!(parse_monpoly_log(log, None).unwrap(), vec![
+            (10, vec![build_fact_const("A", vec![Int(1), Int(2)]), build_fact_const("A", vec![Int(3), Str("4".to_string())]), build_fact_const("B", vec![])]),
+            (10, vec![]),
+            (12, vec![build_fact_const("C", vec![Str("abc".to_string()), Int(7)])]),
+        ]);
+
+        let sig = Signatures::parse("A(int,int) B() C(string,string)").unwrap();
+        let typed = parse_monpoly_log(log, Some(&sig));
+        assert!(typed.unwrap_err().starts_with("Line 1:"));
+        let typed = parse_monpoly_log("@1 C(abc, 007)", Some(&sig)).unwrap();
+        assert_eq!(typed[0].1, vec![build_fact_const("C", vec![Str("abc".to_string()), Str("007".to_string())])]);
+        assert!(parse_monpoly_log("@3 B() @2 B()", None).unwrap_err().starts_with("Line 1:"));
+        assert!(parse_monpoly_log("@3 D(1)", Some(&sig)).is_err());
+    }
+
+    #[test]
+    fn monpoly_log_lines() {
+        let fact = |name: &str, args: Vec<i32>| TypedEvent::Fact(name.to_string(), args.into_iter().map(Int).collect());
+        let lines = vec!["@10 A(1,-2)", "B(3,4) @10", "", "@12 B(-7) (* done *)"];
+        let mut segments = MonpolySegments::new(lines.into_iter().map(|line| Ok(line.to_string())), None);
+
+        // a time point is produced once the next one starts
+        match segments.next() {
+            Some(Ok(Segment::Seg(0, 10, events))) => assert_eq!(events, vec![fact("A", vec![1, -2]), fact("B", vec![3, 4])]),
+            s => panic!("unexpected {:?}", s),
+        }
+        assert!(matches!(segments.next(), Some(Ok(Segment::Seg(1, 10, events))) if events.is_empty()));
+        match segments.next() {
+            Some(Ok(Segment::Seg(2, 12, events))) => assert_eq!(events, vec![fact("B", vec![-7])]),
+            s => panic!("unexpected {:?}", s),
+        }
+        assert!(segments.next().is_none());
+
+        // the complete time points come before the error, which names the line
+        let lines = vec!["@1 A(1)", "@2 A(1)", "@3 A(- 1", "@4 A(1)"];
+        let results: Vec<_> = MonpolySegments::new(lines.into_iter().map(|line| Ok(line.to_string())), None).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].as_ref().unwrap_err().starts_with("Line 3:"), "{:?}", results[1]);
+
+        let sig = Signatures::parse("A(int)").unwrap();
+        let mut typed = MonpolySegments::new(vec![Ok("@1 A(x)".to_string())].into_iter(), Some(sig));
+        assert!(typed.next().unwrap().unwrap_err().starts_with("Line 1:"));
+        assert!(typed.next().is_none());
+        assert_eq!(parse_monpoly_log("@1 A(-3)", None).unwrap()[0].1, vec![build_fact_const("A", vec![Int(-3)])]);
+    }
+}
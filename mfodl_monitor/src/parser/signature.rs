@@ -0,0 +1,422 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use parser::binary_log::LogRecord;
+use parser::csv_parser::{parse_raw_event, parser_extended_wrapper, ParserReturn};
+use parser::formula_syntax_tree::Constant::{Int, Str};
+use parser::formula_syntax_tree::{Arg, Constant, Formula};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgType {
+    Int,
+    Str,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub args: Vec<ArgType>,
+}
+
+/// Declared predicates with the types of their arguments, read from a MonPoly-style signature
+/// such as `A(int,int) B(string) C()`. Arguments can also be named, e.g. `A(x:int, y:int)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signatures {
+    predicates: HashMap<String, Vec<ArgType>>,
+}
+
+fn parse_type(s: &str) -> Result<ArgType, String> {
+    // the type follows the name of a named argument
+    let t = s.rsplit(':').next().unwrap_or(s).trim();
+    match t {
+        "int" => Ok(ArgType::Int),
+        "string" => Ok(ArgType::Str),
+        _ => Err(format!("Unknown type {}, expected int or string", t)),
+    }
+}
+
+impl Signatures {
+    pub fn parse(input: &str) -> Result<Signatures, String> {
+        let mut predicates = HashMap::new();
+        let mut rest = input.trim();
+        while !rest.is_empty() {
+            let open = rest.find('(').ok_or_else(|| format!("Expected ( after {}", rest))?;
+            let close = rest.find(')').ok_or_else(|| format!("Expected ) after {}", rest))?;
+            let name = rest[..open].trim();
+            if name.is_empty() || close < open || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("Invalid signature {}", &rest[..close + 1]));
+            }
+            let args = rest[open + 1..close].trim();
+            let types = if args.is_empty() {
+                vec![]
+            } else {
+                args.split(',').map(parse_type).collect::<Result<Vec<_>, _>>()?
+            };
+            if predicates.insert(name.to_string(), types).is_some() {
+                return Err(format!("Predicate {} is declared twice", name));
+            }
+            rest = rest[close + 1..].trim_start();
+        }
+        Ok(Signatures { predicates })
+    }
+
+    pub fn new(signatures: Vec<Signature>) -> Signatures {
+        Signatures { predicates: signatures.into_iter().map(|s| (s.name, s.args)).collect() }
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Signatures> {
+        Signatures::parse(&fs::read_to_string(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vec<ArgType>> {
+        self.predicates.get(name)
+    }
+
+    pub fn signatures(&self) -> Vec<Signature> {
+        let sorted: BTreeMap<&String, &Vec<ArgType>> = self.predicates.iter().collect();
+        sorted.into_iter().map(|(name, args)| Signature { name: name.clone(), args: args.clone() }).collect()
+    }
+
+    /// Checks that the policy only uses declared predicates with their arity, that constants have
+    /// the declared types and that every variable has a single type.
+    pub fn check_policy(&self, policy: &Formula) -> Result<(), String> {
+        let mut var_types = HashMap::new();
+        self.check_facts(policy, &mut var_types)?;
+        check_equals(policy, &var_types)
+    }
+
+    fn check_facts(&self, f: &Formula, var_types: &mut HashMap<String, ArgType>) -> Result<(), String> {
+        match f {
+            Formula::Fact(name, args) => {
+                let types = self.get(name).ok_or_else(|| format!("Predicate {} is not declared in the signature", name))?;
+                if types.len() != args.len() {
+                    return Err(format!("Predicate {} is declared with {} arguments but used with {}", name, types.len(), args.len()));
+                }
+                for (arg, arg_type) in args.iter().zip(types) {
+                    match arg {
+                        Arg::Cst(c) => {
+                            if !has_type(c, *arg_type) {
+                                return Err(format!("Argument {} of {} is not of type {:?}", c, name, arg_type));
+                            }
+                        }
+                        Arg::Var(v) => match var_types.get(v) {
+                            Some(t) if t != arg_type => {
+                                return Err(format!("Variable {} is used as {:?} and as {:?}", v, t, arg_type));
+                            }
+                            Some(_) => {}
+                            None => {
+                                var_types.insert(v.clone(), *arg_type);
+                            }
+                        },
+                    }
+                }
+                Ok(())
+            }
+            Formula::Not(f)
+            | Formula::Exists(_, f)
+            | Formula::Once(f, _)
+            | Formula::Eventually(f, _)
+            | Formula::Historically(f, _)
+            | Formula::Always(f, _)
+            | Formula::Next(f, _)
+            | Formula::Prev(f, _) => self.check_facts(f, var_types),
+            Formula::Conj(lhs, rhs)
+            | Formula::Disj(lhs, rhs)
+            | Formula::AntiConj(lhs, rhs)
+            | Formula::Since(lhs, rhs, _)
+            | Formula::Until(lhs, rhs, _)
+            | Formula::NegSince(lhs, rhs, _)
+            | Formula::NegUntil(lhs, rhs, _) => {
+                self.check_facts(lhs, var_types)?;
+                self.check_facts(rhs, var_types)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Parses the argument values of an event of the predicate with their declared types.
+    pub fn type_args(&self, name: &str, values: &[String]) -> Result<Vec<Constant>, String> {
+        let types = self.get(name).ok_or_else(|| format!("predicate {} is not declared in the signature", name))?;
+        if values.len() != types.len() {
+            return Err(format!("{} is declared with {} arguments, found {}", name, types.len(), values.len()));
+        }
+        values
+            .iter()
+            .zip(types)
+            .map(|(value, arg_type)| to_constant(value, Some(*arg_type)).map_err(|_| format!("argument {} of {} is not an int", value, name)))
+            .collect()
+    }
+
+    /// Checks the arguments of an event that already are typed, e.g. read from a binary log.
+    pub fn check_args(&self, name: &str, args: &[Constant]) -> Result<(), String> {
+        let types = self.get(name).ok_or_else(|| format!("predicate {} is not declared in the signature", name))?;
+        if args.len() != types.len() {
+            return Err(format!("{} is declared with {} arguments, found {}", name, types.len(), args.len()));
+        }
+        match args.iter().zip(types).find(|(arg, arg_type)| !has_type(arg, **arg_type)) {
+            Some((arg, arg_type)) => Err(format!("argument {} of {} is not of type {:?}", arg, name, arg_type)),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses an event with the declared argument types and writes it back in the event format,
+    /// with string arguments quoted so that they are not taken for integers. Watermarks are
+    /// returned as they are.
+    pub fn normalize_event(&self, line: &str) -> Result<String, String> {
+        if let ParserReturn::Watermark(_) = parser_extended_wrapper(line.to_string()) {
+            return Ok(line.to_string());
+        }
+
+        let (name, tp, ts, values) = parse_raw_event(line).ok_or("malformed event")?;
+        let args = self.type_args(&name, &values)?;
+
+        let mut event = format!("{}, tp={}, ts={}", name, tp, ts);
+        for (i, arg) in args.iter().enumerate() {
+            match arg {
+                Str(s) if s.contains('\'') && s.contains('"') => {
+                    return Err(format!("argument {} of {} contains both quote characters", s, name));
+                }
+                Str(s) if s.contains('\'') => event.push_str(&format!(", x{}=\"{}\"", i, s)),
+                arg => event.push_str(&format!(", x{}={}", i, arg)),
+            }
+        }
+        Ok(event)
+    }
+}
+
+/// Parses a value with the given type. Without a type, values that are integers become `Int` and
+/// all others `Str`, like in the event format.
+pub fn to_constant(value: &str, arg_type: Option<ArgType>) -> Result<Constant, String> {
+    match arg_type {
+        Some(ArgType::Int) => value.parse::<i32>().map(Int).map_err(|_| format!("{} is not an int", value)),
+        Some(ArgType::Str) => Ok(Str(value.to_string())),
+        None => Ok(value.parse::<i32>().map(Int).unwrap_or_else(|_| Str(value.to_string()))),
+    }
+}
+
+/// One declaration per line in the syntax of `parse`, e.g. `A(int,string)`.
+impl fmt::Display for Signatures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for signature in self.signatures() {
+            let args: Vec<&str> = signature.args.iter().map(|arg| match arg {
+                ArgType::Int => "int",
+                ArgType::Str => "string",
+            }).collect();
+            writeln!(f, "{}({})", signature.name, args.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn has_type(c: &Constant, arg_type: ArgType) -> bool {
+    matches!((c, arg_type), (Int(_), ArgType::Int) | (Str(_), ArgType::Str))
+}
+
+fn check_equals(f: &Formula, var_types: &HashMap<String, ArgType>) -> Result<(), String> {
+    match f {
+        Formula::Equals(v, arg) => match (arg.as_ref(), var_types.get(v)) {
+            (Arg::Cst(c), Some(t)) if !has_type(c, *t) => Err(format!("Variable {} of type {:?} is compared to {}", v, t, c)),
+            (Arg::Var(w), Some(t)) if var_types.get(w).is_some_and(|u| u != t) => {
+                Err(format!("Variable {} of type {:?} is compared to variable {} of type {:?}", v, t, w, var_types[w]))
+            }
+            _ => Ok(()),
+        },
+        Formula::Not(f)
+        | Formula::Exists(_, f)
+        | Formula::Once(f, _)
+        | Formula::Eventually(f, _)
+        | Formula::Historically(f, _)
+        | Formula::Always(f, _)
+        | Formula::Next(f, _)
+        | Formula::Prev(f, _) => check_equals(f, var_types),
+        Formula::Conj(lhs, rhs)
+        | Formula::Disj(lhs, rhs)
+        | Formula::AntiConj(lhs, rhs)
+        | Formula::Since(lhs, rhs, _)
+        | Formula::Until(lhs, rhs, _)
+        | Formula::NegSince(lhs, rhs, _)
+        | Formula::NegUntil(lhs, rhs, _) => {
+            check_equals(lhs, var_types)?;
+            check_equals(rhs, var_types)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Passes on the events normalized to the declared types and drops events that do not match the
+/// signature, reporting them on stderr.
+pub struct TypedLines<I> {
+    lines: I,
+    signatures: Signatures,
+    line: usize,
+}
+
+impl<I: Iterator<Item = String>> TypedLines<I> {
+    pub fn new(lines: I, signatures: Signatures) -> Self {
+        TypedLines { lines, signatures, line: 0 }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for TypedLines<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            match self.signatures.normalize_event(&line) {
+                Ok(event) => return Some(event),
+                Err(reason) => eprintln!("Line {}: {} ({})", self.line, reason, line),
+            }
+        }
+        None
+    }
+}
+
+/// Passes on the records of a typed log, e.g. a binary log, and drops the events that do not match
+/// the signature, reporting them on stderr like `TypedLines`.
+pub struct TypedRecords<I> {
+    records: I,
+    signatures: Signatures,
+    record: usize,
+}
+
+impl<I: Iterator<Item = io::Result<LogRecord>>> TypedRecords<I> {
+    pub fn new(records: I, signatures: Signatures) -> Self {
+        TypedRecords { records, signatures, record: 0 }
+    }
+}
+
+impl<I: Iterator<Item = io::Result<LogRecord>>> Iterator for TypedRecords<I> {
+    type Item = io::Result<LogRecord>;
+
+    fn next(&mut self) -> Option<io::Result<LogRecord>> {
+        for record in self.records.by_ref() {
+            self.record += 1;
+            if let Ok(LogRecord::Event(_, _, name, args)) = &record {
+                if let Err(reason) = self.signatures.check_args(name, args) {
+                    eprintln!("Record {}: {}", self.record, reason);
+                    continue;
+                }
+            }
+            return Some(record);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use dataflow_constructor::types::TypedEvent;
+    use parser::binary_log::{binary_log_to_segments, write_binary_log, LogRecord};
+    use parser::csv_parser::{parser_extended_wrapper, ParserReturn, Segment};
+    use parser::formula_parser::parse_formula;
+    use parser::formula_syntax_tree::Constant::{Int, Str};
+    use parser::formula_syntax_tree::Formula::CstFact;
+    use parser::signature::{ArgType, Signature, Signatures, TypedLines};
+    use parser::table_csv::{read_table_log, PredicateSource, TableFormat};
+
+    #[test]
+    fn parse_signatures() {
+        let sig = Signatures::parse("A(int,int)\nB(user:string) C()").unwrap();
+        assert_eq!(sig.signatures(), vec![
+            Signature { name: "A".to_string(), args: vec![ArgType::Int, ArgType::Int] },
+            Signature { name: "B".to_string(), args: vec![ArgType::Str] },
+            Signature { name: "C".to_string(), args: vec![] },
+        ]);
+        assert!(Signatures::parse("A(int, float)").is_err());
+        assert!(Signatures::parse("A(int) A(string)").is_err());
+        assert!(Signatures::parse("A(int").is_err());
+        assert_eq!(Signatures::parse(&sig.to_string()), Ok(sig));
+    }
+
+    #[test]
+    fn type_check_policy() {
+        let sig = Signatures::parse("A(int,int) B(string) C()").unwrap();
+        assert_eq!(sig.check_policy(&parse_formula("A(x,y) AND ONCE[0,5] A(y,3)")), Ok(()));
+        assert!(sig.check_policy(&parse_formula("A(x,y) AND B(x)")).is_err());
+        assert!(sig.check_policy(&parse_formula("A(x,y) AND D(x)")).is_err());
+        assert!(sig.check_policy(&parse_formula("A(x)")).is_err());
+        assert!(sig.check_policy(&parse_formula("B(3)")).is_err());
+    }
+
+    #[test]
+    fn typed_events() {
+        let sig = Signatures::parse("A(int,string) C()").unwrap();
+        let lines = vec![
+            "A, tp=0, ts=0, x0=1, x1=007",
+            "A, tp=1, ts=1, x0=2, x1='a, b'",
+            "B, tp=1, ts=1, x0=2",
+            "A, tp=1, ts=1, x0=abc, x1=x",
+            ">WATERMARK 1<",
+            "12'C, tp=2, ts=3",
+        ];
+        let typed: Vec<String> = TypedLines::new(lines.into_iter().map(|l| l.to_string()), sig).collect();
+        assert_eq!(typed, vec![
+            "A, tp=0, ts=0, x0=1, x1='007'",
+            "A, tp=1, ts=1, x0=2, x1='a, b'",
+            ">WATERMARK 1<",
+            "C, tp=2, ts=3",
+        ]);
+        assert_eq!(parser_extended_wrapper(typed[0].clone()), ParserReturn::Data(0, 0, CstFact("A".to_string(), vec![Int(1), Str("007".to_string())])));
+    }
+
+    #[test]
+    fn typed_negative_values() {
+        let sig = Signatures::parse("A(int,string)").unwrap();
+        let lines = vec![
+            "A, tp=0, ts=0, x0=-3, x1=-4",
+            "A, tp=1, ts=1, x0=- 3, x1=x",
+            "A, tp=2, ts=2, x0=-2147483648, x1=\"O'Brien\"",
+        ];
+        let typed: Vec<String> = TypedLines::new(lines.into_iter().map(|l| l.to_string()), sig).collect();
+        assert_eq!(typed, vec![
+            "A, tp=0, ts=0, x0=-3, x1='-4'",
+            "A, tp=2, ts=2, x0=-2147483648, x1=\"O'Brien\"",
+        ]);
+        assert_eq!(parser_extended_wrapper(typed[0].clone()), ParserReturn::Data(0, 0, CstFact("A".to_string(), vec![Int(-3), Str("-4".to_string())])));
+        assert_eq!(parser_extended_wrapper(typed[1].clone()), ParserReturn::Data(2, 2, CstFact("A".to_string(), vec![Int(i32::MIN), Str("O'Brien".to_string())])));
+    }
+
+    #[test]
+    fn typed_table_and_binary_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let sig = Signatures::parse("A(int,string)").unwrap();
+
+        let table = dir.path().join("table.csv");
+        write!(File::create(&table).unwrap(), "ts,p,x,y\n1,A,-3,007\n2,A,x,y\n3,B,1,2\n4,A,5,-6\n").unwrap();
+        let format = TableFormat {
+            timestamp: "ts".to_string(),
+            predicate: PredicateSource::Column("p".to_string()),
+            columns: None,
+            delimiter: ',',
+        };
+        assert_eq!(read_table_log(&table, &format, Some(&sig)).unwrap(), vec![
+            LogRecord::Event(0, 1, "A".to_string(), vec![Int(-3), Str("007".to_string())]),
+            LogRecord::Event(1, 4, "A".to_string(), vec![Int(5), Str("-6".to_string())]),
+        ]);
+
+        let bin = dir.path().join("log.tlog");
+        let records = vec![
+            LogRecord::Event(0, 0, "A".to_string(), vec![Int(-1), Str("x".to_string())]),
+            LogRecord::Event(1, 1, "B".to_string(), vec![Int(2)]),
+            LogRecord::Event(2, 2, "A".to_string(), vec![Int(-3), Str("y".to_string())]),
+        ];
+        write_binary_log(&mut File::create(&bin).unwrap(), &records).unwrap();
+        let events: Vec<Vec<TypedEvent>> = binary_log_to_segments(&bin, Some(&sig)).unwrap().into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Seg(_, _, events) => Some(events),
+                Segment::Epoch(_) => None,
+            })
+            .collect();
+        assert_eq!(events, vec![
+            vec![TypedEvent::Fact("A".to_string(), vec![Int(-1), Str("x".to_string())])],
+            vec![TypedEvent::Fact("A".to_string(), vec![Int(-3), Str("y".to_string())])],
+        ]);
+    }
+}
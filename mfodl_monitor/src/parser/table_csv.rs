@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use dataflow_constructor::types::TypedEvent;
+use parser::binary_log::{records_to_segments, LogRecord};
+use parser::csv_parser::Segment;
+use parser::signature::{to_constant, ArgType, Signatures};
+
+/// Where the predicate of the events of a table comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PredicateSource {
+    Column(String),
+    Constant(String),
+}
+
+/// Maps a column to an argument, given as `name`, `name:int` or `name:string`. Without a type,
+/// values that are integers become `Int` and all others `Str`, like in the event format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub column: String,
+    pub arg_type: Option<ArgType>,
+}
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (column, arg_type) = match s.split_once(':') {
+            None => (s, None),
+            Some((column, "int")) => (column, Some(ArgType::Int)),
+            Some((column, "string")) => (column, Some(ArgType::Str)),
+            Some((_, t)) => return Err(format!("Invalid type {} of column {}, expected int or string", t, s)),
+        };
+        if column.trim().is_empty() {
+            return Err(format!("Invalid column mapping {}", s));
+        }
+        Ok(ColumnMapping { column: column.trim().to_string(), arg_type })
+    }
+}
+
+/// Layout of a CSV file with a header row. Rows are ordered by their timestamp and all rows with
+/// the same timestamp form one time point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableFormat {
+    pub timestamp: String,
+    pub predicate: PredicateSource,
+    // None maps all columns except the timestamp and predicate columns, in header order
+    pub columns: Option<Vec<ColumnMapping>>,
+    pub delimiter: char,
+}
+
+/// Splits a row at the delimiter. Fields can be enclosed in double quotes, in which a quote is
+/// written as two quotes.
+pub fn split_row(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn column_index(header: &HashMap<String, usize>, column: &str) -> io::Result<usize> {
+    header.get(column).cloned().ok_or_else(|| invalid(format!("Column {} is not in the header", column)))
+}
+
+/// Reads the events of a CSV file with a header row, ordered by time point. With signatures, the
+/// cells are parsed with the declared types and rows that do not match them are dropped and
+/// reported on stderr.
+pub fn read_table_log(path: &Path, format: &TableFormat, signatures: Option<&Signatures>) -> io::Result<Vec<LogRecord>> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header_row = split_row(&lines.next().unwrap_or_else(|| Ok(String::new()))?, format.delimiter);
+    let header: HashMap<String, usize> = header_row.iter().enumerate().map(|(i, c)| (c.clone(), i)).collect();
+
+    let ts_index = column_index(&header, &format.timestamp)?;
+    let pred_index = match &format.predicate {
+        PredicateSource::Column(column) => Some(column_index(&header, column)?),
+        PredicateSource::Constant(_) => None,
+    };
+    let columns = match &format.columns {
+        Some(columns) => columns
+            .iter()
+            .map(|m| Ok((column_index(&header, &m.column)?, m.arg_type)))
+            .collect::<io::Result<Vec<_>>>()?,
+        None => (0..header_row.len())
+            .filter(|i| *i != ts_index && Some(*i) != pred_index)
+            .map(|i| (i, None))
+            .collect(),
+    };
+
+    let mut rows = Vec::new();
+    for (line_number, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // the header is line 1
+        let error = |msg: String| invalid(format!("Line {}: {}", line_number + 2, msg));
+        let fields = split_row(&line, format.delimiter);
+        if fields.len() != header_row.len() {
+            return Err(error(format!("expected {} columns, found {}", header_row.len(), fields.len())));
+        }
+
+        let ts = fields[ts_index].parse::<usize>().map_err(|_| error(format!("invalid timestamp {}", fields[ts_index])))?;
+        let name = match (&format.predicate, pred_index) {
+            (PredicateSource::Constant(name), _) => name.clone(),
+            (_, Some(i)) if !fields[i].is_empty() => fields[i].clone(),
+            _ => return Err(error("missing predicate".to_string())),
+        };
+        let args = match signatures {
+            Some(signatures) => {
+                let values: Vec<String> = columns.iter().map(|(i, _)| fields[*i].clone()).collect();
+                match signatures.type_args(&name, &values) {
+                    Ok(args) => args,
+                    Err(reason) => {
+                        eprintln!("Line {}: {} ({})", line_number + 2, reason, line);
+                        continue;
+                    }
+                }
+            }
+            None => columns
+                .iter()
+                .map(|(i, arg_type)| to_constant(&fields[*i], *arg_type))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error)?,
+        };
+        rows.push((ts, name, args));
+    }
+
+    // time points in increasing timestamp order, rows of the same time point keep their order
+    let tps: BTreeMap<usize, usize> = rows.iter().map(|(ts, _, _)| (*ts, 0)).collect();
+    let tps: BTreeMap<usize, usize> = tps.keys().enumerate().map(|(tp, ts)| (*ts, tp)).collect();
+    rows.sort_by_key(|(ts, _, _)| *ts);
+    Ok(rows.into_iter().map(|(ts, name, args)| LogRecord::Event(tps[&ts], ts, name, args)).collect())
+}
+
+/// Groups the events of a CSV file with a header row into segments like `parse_file_to_segments`.
+/// The cells become typed events, so quotes and commas in them reach the dataflow unchanged.
+pub fn table_to_segments(path: &Path, format: &TableFormat, signatures: Option<&Signatures>) -> io::Result<Vec<Segment<TypedEvent>>> {
+    records_to_segments(read_table_log(path, format, signatures)?.into_iter().map(Ok))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use parser::binary_log::{monitor_typed_segments, LogRecord};
+    use parser::signature::ArgType;
+    use parser::formula_syntax_tree::Constant::{Int, Str};
+    use parser::table_csv::{read_table_log, split_row, table_to_segments, ColumnMapping, PredicateSource, TableFormat};
+
+    #[test]
+    fn split_quoted_row() {
+        assert_eq!(split_row("1, alice ,\"a, \"\"b\"\"\",", ','), vec!["1", "alice", "a, \"b\"", ""]);
+        assert_eq!(split_row("1;2", ';'), vec!["1", "2"]);
+        assert_eq!("amount:int".parse(), Ok(ColumnMapping { column: "amount".to_string(), arg_type: Some(ArgType::Int) }));
+        assert!("amount:float".parse::<ColumnMapping>().is_err());
+    }
+
+    #[test]
+    fn table_with_mapping() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table.csv");
+        write!(File::create(&path).unwrap(), "id,time,user,amount\n1,20,bob,7\n2,10,alice,5\n\n3,20,carol,42\n").unwrap();
+
+        let mut format = TableFormat {
+            timestamp: "time".to_string(),
+            predicate: PredicateSource::Constant("withdraw".to_string()),
+            columns: Some(vec!["user:string".parse().unwrap(), "amount:int".parse().unwrap()]),
+            delimiter: ',',
+        };
+        assert_eq!(read_table_log(&path, &format, None).unwrap(), vec![
+            LogRecord::Event(0, 10, "withdraw".to_string(), vec![Str("alice".to_string()), Int(5)]),
+            LogRecord::Event(1, 20, "withdraw".to_string(), vec![Str("bob".to_string()), Int(7)]),
+            LogRecord::Event(1, 20, "withdraw".to_string(), vec![Str("carol".to_string()), Int(42)]),
+        ]);
+
+        // all other columns with guessed types
+        format.predicate = PredicateSource::Column("user".to_string());
+        format.columns = None;
+        assert_eq!(read_table_log(&path, &format, None).unwrap()[0], LogRecord::Event(0, 10, "alice".to_string(), vec![Int(2), Int(5)]));
+
+        format.columns = Some(vec!["user:int".parse().unwrap()]);
+        let err = read_table_log(&path, &format, None).unwrap_err().to_string();
+        assert!(err.contains("Line 2"), "{}", err);
+    }
+
+    #[test]
+    fn table_cells_with_quotes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table.csv");
+        write!(File::create(&path).unwrap(), "time,user,note\n1,O'Brien,\"say \"\"hi\"\"\"\n2,alice,-3\n").unwrap();
+
+        let format = TableFormat {
+            timestamp: "time".to_string(),
+            predicate: PredicateSource::Constant("login".to_string()),
+            columns: None,
+            delimiter: ',',
+        };
+        let verdicts = monitor_typed_segments("login(u,n)", table_to_segments(&path, &format, None).unwrap());
+        assert_eq!(verdicts, vec![
+            (0, vec![Str("O'Brien".to_string()), Str("say \"hi\"".to_string())]),
+            (1, vec![Str("alice".to_string()), Int(-3)]),
+        ]);
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parser::csv_parser::{parser_extended_wrapper, ParserReturn};
+use parser::formula_syntax_tree::{predicate_arities, Formula};
+
+/// What happens to lines that fail validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    // drop the line
+    Skip,
+    // drop the line and report it on stderr
+    Warn,
+    // report the line and end the input
+    Fail,
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(ErrorPolicy::Skip),
+            "warn" => Ok(ErrorPolicy::Warn),
+            "fail" => Ok(ErrorPolicy::Fail),
+            _ => Err(format!("Invalid error policy {}, expected skip, warn or fail", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputError {
+    pub line: usize,
+    pub content: String,
+    pub reason: String,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {} ({})", self.line, self.reason, self.content)
+    }
+}
+
+/// Checks lines of the CSV event format: lines that do not parse, events whose arity differs from
+/// the use of their predicate in the policy, time points below the last watermark and timestamps
+/// that decrease along the time points. Lines may arrive out of time point order.
+pub struct InputValidator {
+    arities: BTreeMap<String, BTreeSet<usize>>,
+    tp_to_ts: BTreeMap<usize, usize>,
+    watermark: Option<i64>,
+    line: usize,
+}
+
+impl InputValidator {
+    pub fn new(policy: &Formula) -> Self {
+        InputValidator {
+            arities: predicate_arities(policy),
+            tp_to_ts: BTreeMap::new(),
+            watermark: None,
+            line: 0,
+        }
+    }
+
+    /// Checks the next line and returns the reason if it is invalid.
+    pub fn check(&mut self, line: &str) -> Result<(), String> {
+        self.line += 1;
+        match parser_extended_wrapper(line.to_string()) {
+            ParserReturn::Error(_) => Err("malformed event".to_string()),
+            ParserReturn::Watermark(wm) => {
+                match self.watermark {
+                    Some(last) if wm < last => return Err(format!("watermark {} is below the previous watermark {}", wm, last)),
+                    _ => self.watermark = Some(wm),
+                }
+                // only the latest time point below the watermark is needed to check later ones
+                let below = self.tp_to_ts.range(..wm.max(0) as usize).next_back().map(|(tp, ts)| (*tp, *ts));
+                self.tp_to_ts = self.tp_to_ts.split_off(&(wm.max(0) as usize));
+                if let Some((tp, ts)) = below {
+                    self.tp_to_ts.insert(tp, ts);
+                }
+                Ok(())
+            }
+            ParserReturn::Data(tp, ts, f) => {
+                if let Formula::CstFact(name, args) = &f {
+                    if let Some(arities) = self.arities.get(name) {
+                        if !arities.contains(&args.len()) {
+                            return Err(format!("{} has {} arguments, the policy uses it with {:?}", name, args.len(), arities));
+                        }
+                    }
+                }
+                if let Some(wm) = self.watermark {
+                    if (tp as i64) < wm {
+                        return Err(format!("time point {} is below the watermark {}", tp, wm));
+                    }
+                }
+                if let Some(prev_ts) = self.tp_to_ts.get(&tp) {
+                    if *prev_ts != ts {
+                        return Err(format!("time point {} has timestamp {}, earlier events of it have timestamp {}", tp, ts, prev_ts));
+                    }
+                    return Ok(());
+                }
+                if let Some((prev_tp, prev_ts)) = self.tp_to_ts.range(..tp).next_back() {
+                    if *prev_ts > ts {
+                        return Err(format!("timestamp {} of time point {} is smaller than timestamp {} of time point {}", ts, tp, prev_ts, prev_tp));
+                    }
+                }
+                if let Some((next_tp, next_ts)) = self.tp_to_ts.range(tp + 1..).next() {
+                    if *next_ts < ts {
+                        return Err(format!("timestamp {} of time point {} is larger than timestamp {} of time point {}", ts, tp, next_ts, next_tp));
+                    }
+                }
+                self.tp_to_ts.insert(tp, ts);
+                Ok(())
+            }
+        }
+    }
+
+    /// Number of the line checked last, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// Passes on the valid lines and handles invalid ones according to the error policy. After a
+/// failure the input ends and the failed flag is raised.
+pub struct ValidatedLines<I> {
+    lines: I,
+    validator: InputValidator,
+    policy: ErrorPolicy,
+    failed: Arc<AtomicBool>,
+}
+
+impl<I: Iterator<Item = String>> ValidatedLines<I> {
+    pub fn new(lines: I, validator: InputValidator, policy: ErrorPolicy, failed: Arc<AtomicBool>) -> Self {
+        ValidatedLines { lines, validator, policy, failed }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for ValidatedLines<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.failed.load(Ordering::SeqCst) {
+            return None;
+        }
+        for line in self.lines.by_ref() {
+            let reason = match self.validator.check(&line) {
+                Ok(()) => return Some(line),
+                Err(reason) => reason,
+            };
+            let error = InputError { line: self.validator.line(), content: line, reason };
+            match self.policy {
+                ErrorPolicy::Skip => {}
+                ErrorPolicy::Warn => eprintln!("{}", error),
+                ErrorPolicy::Fail => {
+                    eprintln!("{}", error);
+                    self.failed.store(true, Ordering::SeqCst);
+                    return None;
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Checks all lines and reports every invalid one.
+pub fn validate_lines<I: Iterator<Item = String>>(lines: I, policy: &Formula) -> Vec<InputError> {
+    let mut validator = InputValidator::new(policy);
+    let mut errors = Vec::new();
+    for line in lines {
+        if let Err(reason) = validator.check(&line) {
+            errors.push(InputError { line: validator.line(), content: line, reason });
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use parser::formula_parser::parse_formula;
+    use parser::validation::{validate_lines, ErrorPolicy, InputValidator, ValidatedLines};
+
+    fn lines(input: &str) -> Vec<String> {
+        input.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn validation_report() {
+        let policy = parse_formula("A(x,y) AND B(y)");
+        let input = lines("A, tp=0, ts=0, x0=1, x1=2\n\
+            A, tp=0, ts=1, x0=1, x1=3\n\
+            B, tp=1, ts=3, x0=2, x1=3\n\
+            A tp=2, ts=5, x0=1\n\
+            C, tp=2, ts=5, x0=1\n\
+            A, tp=4, ts=6, x0=1, x1=2\n\
+            >WATERMARK 3<\n\
+            B, tp=2, ts=5, x0=2\n\
+            A, tp=3, ts=2, x0=1, x1=2");
+
+        let errors = validate_lines(input.into_iter(), &policy);
+        let found: Vec<(usize, &str)> = errors.iter().map(|e| (e.line, e.reason.as_str())).collect();
+        assert_eq!(found, vec![
+            (2, "time point 0 has timestamp 1, earlier events of it have timestamp 0"),
+            (3, "B has 2 arguments, the policy uses it with {1}"),
+            (4, "malformed event"),
+            (8, "time point 2 is below the watermark 3"),
+            (9, "timestamp 2 of time point 3 is smaller than timestamp 5 of time point 2"),
+        ]);
+        assert_eq!(errors[2].content, "A tp=2, ts=5, x0=1");
+    }
+
+    #[test]
+    fn error_policies() {
+        let policy = parse_formula("A(x)");
+        let input = lines("A, tp=0, ts=0, x0=1\nA, tp=1 ts=1, x0=2\nA, tp=2, ts=2, x0=3");
+
+        let failed = Arc::new(AtomicBool::new(false));
+        let skipped: Vec<String> = ValidatedLines::new(input.clone().into_iter(), InputValidator::new(&policy), ErrorPolicy::Skip, failed.clone()).collect();
+        assert_eq!(skipped, vec![input[0].clone(), input[2].clone()]);
+        assert!(!failed.load(Ordering::SeqCst));
+
+        let mut validated = ValidatedLines::new(input.clone().into_iter(), InputValidator::new(&policy), ErrorPolicy::Fail, failed.clone());
+        assert_eq!(validated.next(), Some(input[0].clone()));
+        assert_eq!(validated.next(), None);
+        assert_eq!(validated.next(), None);
+        assert!(failed.load(Ordering::SeqCst));
+        assert_eq!("warn".parse(), Ok(ErrorPolicy::Warn));
+    }
+}
@@ -0,0 +1,269 @@
+use rand::{Rng, SeedableRng, StdRng};
+
+use parser::formula_syntax_tree::Constant::Int;
+use parser::formula_syntax_tree::*;
+use parser::signature::{ArgType, Signature, Signatures};
+use timeunits::TimeInterval;
+use timeunits::TS::FINITE;
+
+#[derive(Clone, Debug)]
+pub struct PolicyConfig {
+    /// Number of operators, where AND NOT and NOT ... SINCE/UNTIL count as two
+    pub operators: usize,
+    /// Number of free variables of the policy, named x0, x1, ...
+    pub free_variables: usize,
+    /// Largest lower bound of an interval
+    pub max_lb: usize,
+    /// Largest difference of the upper and the lower bound of an interval
+    pub max_interval: usize,
+    /// Probability that a temporal operator is a future operator
+    pub future_ratio: f64,
+    /// Constants of equalities are drawn from 0..domain
+    pub domain: i32,
+    pub seed: usize,
+}
+
+impl PolicyConfig {
+    pub fn new(operators: usize, free_variables: usize) -> PolicyConfig {
+        PolicyConfig {
+            operators,
+            free_variables,
+            max_lb: 5,
+            max_interval: 10,
+            future_ratio: 0.5,
+            domain: 10,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Not,
+    Conj,
+    AntiConj,
+    Equals,
+    Disj,
+    Exists,
+    Since,
+    Once,
+    Historically,
+    Prev,
+}
+
+struct Generator<'a> {
+    config: &'a PolicyConfig,
+    rng: StdRng,
+    predicates: Vec<Signature>,
+    bound_variables: usize,
+}
+
+/// Generates a random monitorable policy over integer predicates P0, P1, ... and their signatures.
+///
+/// Every operator keeps the policy monitorable by the dataflow: negations are closed or the right
+/// side of AND NOT, disjuncts have the same free variables, the left side of SINCE and UNTIL only
+/// uses free variables of the right side, equalities compare a variable bound by the other
+/// conjunct to a constant and future operators have bounded intervals.
+pub fn generate_policy(config: &PolicyConfig) -> (Formula, Signatures) {
+    let mut generator = Generator {
+        config,
+        rng: StdRng::from_seed(&[config.seed]),
+        predicates: Vec::new(),
+        bound_variables: 0,
+    };
+    let vars: Vec<String> = (0..config.free_variables).map(|i| format!("x{}", i)).collect();
+    let policy = generator.formula(config.operators, &vars, true);
+    (policy, Signatures::new(generator.predicates))
+}
+
+impl<'a> Generator<'a> {
+    // A formula with the given number of operators whose free variables are exactly vars
+    fn formula(&mut self, size: usize, vars: &[String], negatable: bool) -> Formula {
+        if size == 0 {
+            return self.fact(vars);
+        }
+
+        let mut operators = vec![Operator::Conj, Operator::Disj, Operator::Exists, Operator::Since, Operator::Once,
+                                 Operator::Historically, Operator::Prev];
+        if vars.is_empty() && negatable {
+            operators.push(Operator::Not);
+        }
+        if !vars.is_empty() {
+            operators.push(Operator::Equals);
+        }
+        if size >= 2 {
+            operators.push(Operator::AntiConj);
+        }
+        let future = self.rng.gen::<f64>() < self.config.future_ratio;
+
+        match *self.rng.choose(&operators).unwrap() {
+            Operator::Not => build_not(self.formula(size - 1, vars, false)),
+            Operator::Conj => {
+                let (left, right) = self.split(size - 1);
+                let (left_vars, right_vars) = self.cover(vars);
+                let lhs = self.formula(left, &left_vars, true);
+                // two closed negations are no conjunction the dataflow accepts
+                let negatable = !matches!(lhs, Formula::Not(_));
+                let rhs = self.formula(right, &right_vars, negatable);
+                build_conj(lhs, rhs)
+            }
+            Operator::AntiConj => {
+                let (left, right) = self.split(size - 2);
+                let right_vars = self.subset(vars);
+                let lhs = self.formula(left, vars, false);
+                let rhs = self.formula(right, &right_vars, false);
+                build_anticonj(lhs, rhs)
+            }
+            Operator::Equals => {
+                let var = self.rng.choose(vars).unwrap().clone();
+                let value = self.rng.gen_range(0, self.config.domain);
+                let lhs = self.formula(size - 1, vars, true);
+                build_conj(lhs, build_equals(&var, Arg::Cst(Int(value))))
+            }
+            Operator::Disj => {
+                let (left, right) = self.split(size - 1);
+                let lhs = self.formula(left, vars, true);
+                let rhs = self.formula(right, vars, true);
+                build_disj(lhs, rhs)
+            }
+            Operator::Exists => {
+                let var = format!("y{}", self.bound_variables);
+                self.bound_variables += 1;
+                let mut inner = vars.to_vec();
+                inner.push(var.clone());
+                build_exists(vec![&var], self.formula(size - 1, &inner, true))
+            }
+            Operator::Since => {
+                let (left, right) = self.split(size - 1);
+                let left_vars = self.subset(vars);
+                let rhs = self.formula(right, vars, true);
+                let interval = self.interval();
+                if left > 0 && self.rng.gen() {
+                    let lhs = self.formula(left - 1, &left_vars, false);
+                    if future { build_neg_until(lhs, rhs, interval) } else { build_neg_since(lhs, rhs, interval) }
+                } else {
+                    let lhs = self.formula(left, &left_vars, false);
+                    if future { build_until(lhs, rhs, interval) } else { build_since(lhs, rhs, interval) }
+                }
+            }
+            Operator::Once => {
+                let f = self.formula(size - 1, vars, true);
+                let interval = self.interval();
+                if future { build_eventually(f, interval) } else { build_once(f, interval) }
+            }
+            Operator::Historically => {
+                let f = self.formula(size - 1, vars, true);
+                let interval = self.interval();
+                if future { build_always(f, interval) } else { build_historically(f, interval) }
+            }
+            Operator::Prev => {
+                let f = self.formula(size - 1, vars, true);
+                let interval = self.interval();
+                if future { build_next(f, interval) } else { build_prev(f, interval) }
+            }
+        }
+    }
+
+    // A fact over vars, of a new predicate or of an earlier one with the same arity
+    fn fact(&mut self, vars: &[String]) -> Formula {
+        let earlier: Vec<String> = self.predicates.iter()
+            .filter(|p| p.args.len() == vars.len())
+            .map(|p| p.name.clone())
+            .collect();
+        let name = match self.rng.choose(&earlier) {
+            Some(name) if self.rng.gen() => name.clone(),
+            _ => {
+                let name = format!("P{}", self.predicates.len());
+                self.predicates.push(Signature { name: name.clone(), args: vec![ArgType::Int; vars.len()] });
+                name
+            }
+        };
+
+        let mut args: Vec<Arg> = vars.iter().map(|v| Arg::Var(v.clone())).collect();
+        self.rng.shuffle(&mut args);
+        build_fact_args(&name, args)
+    }
+
+    // A non-empty bounded interval
+    fn interval(&mut self) -> TimeInterval {
+        loop {
+            let start = self.rng.gen_range(0, self.config.max_lb + 1);
+            let end = start + self.rng.gen_range(0, self.config.max_interval + 1);
+            let interval = TimeInterval::with_bounds(FINITE(start), self.rng.gen(), FINITE(end), self.rng.gen());
+            if !interval.is_empty() {
+                return interval;
+            }
+        }
+    }
+
+    fn split(&mut self, size: usize) -> (usize, usize) {
+        let left = self.rng.gen_range(0, size + 1);
+        (left, size - left)
+    }
+
+    fn subset(&mut self, vars: &[String]) -> Vec<String> {
+        vars.iter().filter(|_| self.rng.gen()).cloned().collect()
+    }
+
+    // Two sets of variables whose union is vars
+    fn cover(&mut self, vars: &[String]) -> (Vec<String>, Vec<String>) {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for var in vars {
+            match self.rng.gen_range(0, 3) {
+                0 => left.push(var.clone()),
+                1 => right.push(var.clone()),
+                _ => {
+                    left.push(var.clone());
+                    right.push(var.clone());
+                }
+            }
+        }
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::formula_parser::parse_formula;
+    use parser::monpoly::{parse_monpoly_formula, to_native_policy};
+    use timeunits::TimeUnit;
+
+    fn operators(f: &Formula) -> usize {
+        match f {
+            Formula::Fact(_, _) | Formula::Equals(_, _) => 0,
+            Formula::Not(f) | Formula::Exists(_, f) | Formula::Once(f, _) | Formula::Eventually(f, _)
+            | Formula::Historically(f, _) | Formula::Always(f, _) | Formula::Prev(f, _) | Formula::Next(f, _) => 1 + operators(f),
+            Formula::Conj(l, r) | Formula::Disj(l, r) | Formula::Since(l, r, _) | Formula::Until(l, r, _) => 1 + operators(l) + operators(r),
+            Formula::AntiConj(l, r) | Formula::NegSince(l, r, _) | Formula::NegUntil(l, r, _) => 2 + operators(l) + operators(r),
+            f => panic!("Unexpected {}", f),
+        }
+    }
+
+    #[test]
+    fn generated_policies() {
+        for seed in 0..200 {
+            let mut config = PolicyConfig::new(seed % 12, seed % 4);
+            config.seed = seed;
+            let (policy, signatures) = generate_policy(&config);
+
+            assert_eq!(operators(&policy), config.operators, "policy {}", policy);
+            assert_eq!(free_variables(policy.clone()).iter().filter(|v| is_var(v)).count(), config.free_variables, "policy {}", policy);
+            assert_eq!(formula_error(&policy), None);
+            assert_eq!(signatures.check_policy(&policy), Ok(()));
+
+            // the policy can be written for the monitor and for MonPoly
+            let text = to_native_policy(&policy);
+            assert_eq!(parse_formula(&text), policy, "policy {}", text);
+            assert_eq!(parse_monpoly_formula(&text, TimeUnit::Second), Ok(policy), "policy {}", text);
+        }
+
+        let mut config = PolicyConfig::new(10, 2);
+        config.future_ratio = 0.0;
+        for seed in 0..20 {
+            config.seed = seed;
+            assert!(!has_future_operator(&generate_policy(&config).0));
+        }
+    }
+}
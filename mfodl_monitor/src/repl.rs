@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::{Context, Editor, Helper};
+use timely;
+use timely::dataflow::operators::{Inspect, Probe, UnorderedInput};
+
+use dataflow_constructor::operators::write_verdicts;
+use dataflow_constructor::types::TimeFlowValues::{Timestamp, EOS};
+use dataflow_constructor::types::{default_options, Record, TimeFlowValues};
+use evaluation_plan_generator::evaluation_plan_generator::optimize_evaluation_plan;
+use parser::csv_parser::{parser_extended_wrapper, ParserReturn};
+use parser::formula_syntax_tree::{formula_error, has_future_operator, predicate_arities, Formula};
+use parser::json_parser::find_timestamp;
+use {create_dataflow, generate_evaluation_plan, parse_formula};
+
+const HISTORY_FILE: &str = ".timelymon_history";
+
+// longest time the idle dataflow waits for its next command before it steps again
+const IDLE: Duration = Duration::from_millis(1);
+
+const COMMANDS: &[&str] = &[":flush", ":help", ":next", ":parse", ":policies", ":policy", ":quit", ":use"];
+
+const HELP: &str = "\
+:policy <formula>   monitor a new policy, starting at time point 0
+:parse [formula]    show the formula, its evaluation plan and the optimized plan
+:policies           list the policies, * marks the monitored one
+:use <n>            monitor the n-th policy again, starting at time point 0
+:next [tp]          complete the time points up to tp, or up to the latest event, and print their final verdicts
+:flush              close the input, print the remaining verdicts and start over
+:quit               leave the REPL
+Events are typed in the CSV (A, tp=0, ts=0, x0=1) or JSON ({\"timestamp\": 0, ...}) syntax, >WATERMARK n< completes the
+time points up to n.";
+
+enum Command {
+    // time point, timestamp if it is the first event of the time point, event
+    Event(usize, Option<usize>, String),
+    // all time points below are complete
+    Advance(usize),
+    // the stream ends at the time point
+    End(usize),
+}
+
+// A dataflow of one policy, driven by the first worker on its own thread. Every advance is
+// acknowledged with the output frontier, none once the dataflow is done. Without future operators,
+// the frontier passes the completed time points; future operators may hold them until later time
+// points are complete or the input is closed.
+struct Session {
+    commands: Sender<Command>,
+    acks: Receiver<Option<usize>>,
+    verdicts: Receiver<(usize, Vec<Record>)>,
+    no_free_vars: bool,
+}
+
+impl Session {
+    fn start(policy: &str, workers: usize) -> Result<Session, String> {
+        let (command_send, command_recv) = mpsc::channel();
+        let (ack_send, ack_recv) = mpsc::channel();
+        let (verdict_send, verdict_recv) = mpsc::channel();
+        let (attrs_send, attrs_recv) = mpsc::channel();
+        let command_recv = Arc::new(Mutex::new(Some(command_recv)));
+        let ack_send = Arc::new(Mutex::new(ack_send));
+        let verdict_send = Arc::new(Mutex::new(verdict_send));
+        let attrs_send = Arc::new(Mutex::new(attrs_send));
+        let policy = policy.to_string();
+        let future = has_future_operator(&parse_formula(&policy));
+
+        thread::spawn(move || {
+            let _ = timely::execute(timely::Config::process(workers), move |worker| {
+                let verdict_send = verdict_send.lock().unwrap().clone();
+                let (mut input, cap, mut time_input, time_cap, probe) = worker.dataflow::<usize, _, _>(|scope| {
+                    let ((time_input, time_cap), time_stream) = scope.new_unordered_input::<TimeFlowValues>();
+                    let ((input, input_cap), stream) = scope.new_unordered_input::<String>();
+                    let (attrs, output) = create_dataflow(parse_formula(&policy), stream, time_stream, default_options());
+                    let _ = attrs_send.lock().unwrap().send(attrs);
+                    let probe = output
+                        .inspect_batch(move |tp, records| {
+                            let _ = verdict_send.send((*tp, records.to_vec()));
+                        })
+                        .probe();
+                    (input, input_cap, time_input, time_cap, probe)
+                });
+
+                if worker.index() != 0 {
+                    return;
+                }
+                let commands = command_recv.lock().unwrap().take().unwrap();
+                let acks = ack_send.lock().unwrap().clone();
+                let (mut cap, mut time_cap) = (cap, time_cap);
+                let frontier = |probe: &timely::dataflow::ProbeHandle<usize>| probe.with_frontier(|f| f.iter().min().cloned());
+                // a closed REPL session drops the commands, which ends the stream
+                loop {
+                    let command = match commands.try_recv() {
+                        Ok(command) => command,
+                        // verdicts that wait for the input keep being computed between commands
+                        Err(TryRecvError::Empty) => {
+                            worker.step_or_park(Some(IDLE));
+                            continue;
+                        }
+                        Err(TryRecvError::Disconnected) => break,
+                    };
+                    match command {
+                        Command::Event(tp, ts, event) => {
+                            if let Some(ts) = ts {
+                                time_input.session(time_cap.delayed(&tp)).give(Timestamp(ts));
+                            }
+                            input.session(cap.delayed(&tp)).give(event);
+                        }
+                        Command::Advance(tp) => {
+                            cap.downgrade(&tp);
+                            time_cap.downgrade(&tp);
+                            if !future {
+                                worker.step_while(|| probe.less_than(&tp));
+                            }
+                            let _ = acks.send(frontier(&probe));
+                        }
+                        Command::End(tp) => {
+                            time_input.session(time_cap.delayed(&tp)).give(EOS);
+                            input.session(cap.delayed(&tp)).give("<eos>".to_string());
+                            break;
+                        }
+                    }
+                }
+                drop((cap, time_cap));
+                while !probe.done() {
+                    worker.step();
+                }
+                let _ = acks.send(None);
+            });
+        });
+
+        let attrs = attrs_recv.recv().map_err(|_| "The monitor stopped while building the dataflow".to_string())?;
+        Ok(Session { commands: command_send, acks: ack_recv, verdicts: verdict_recv, no_free_vars: attrs.is_empty() })
+    }
+
+    fn send(&self, command: Command) -> Result<(), String> {
+        self.commands.send(command).map_err(|_| "The monitor stopped".to_string())
+    }
+
+    // Sends a command that completes time points and waits for the output frontier
+    fn complete(&self, command: Command) -> Result<Option<usize>, String> {
+        self.send(command)?;
+        self.acks.recv().map_err(|_| "The monitor stopped".to_string())
+    }
+}
+
+/// Interactive monitoring: policies are switched with commands, events are typed in the CSV or
+/// JSON syntax and the verdicts of a time point are printed once they are final.
+pub struct Repl {
+    workers: usize,
+    policies: Vec<String>,
+    current: Option<usize>,
+    session: Option<Session>,
+    // lowest time point that is not complete yet
+    open: usize,
+    max_tp: Option<usize>,
+    timestamps: BTreeMap<usize, usize>,
+    // verdicts of the time points that are not final yet
+    records: BTreeMap<usize, Vec<Record>>,
+    predicates: BTreeSet<String>,
+}
+
+impl Repl {
+    pub fn new(workers: usize) -> Repl {
+        Repl {
+            workers: workers.max(1),
+            policies: Vec::new(),
+            current: None,
+            session: None,
+            open: 0,
+            max_tp: None,
+            timestamps: BTreeMap::new(),
+            records: BTreeMap::new(),
+            predicates: BTreeSet::new(),
+        }
+    }
+
+    /// Predicate names of the policies and of the events typed so far.
+    pub fn predicates(&self) -> &BTreeSet<String> {
+        &self.predicates
+    }
+
+    /// Lowest time point whose verdicts are not final yet.
+    pub fn time_point(&self) -> usize {
+        self.open
+    }
+
+    /// Evaluates a command or an event and returns the text to print.
+    pub fn eval(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        let (command, argument) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        match command {
+            ":help" => Ok(HELP.to_string()),
+            ":parse" if argument.is_empty() => match self.current {
+                Some(i) => describe(&self.policies[i]),
+                None => Err("No policy, expected :parse <formula>".to_string()),
+            },
+            ":parse" => describe(argument),
+            ":policy" => {
+                let description = describe(argument)?;
+                self.policies.push(argument.to_string());
+                let started = self.switch(self.policies.len() - 1)?;
+                Ok(format!("{}\n{}", description, started))
+            }
+            ":policies" => Ok(self.policies.iter().enumerate()
+                .map(|(i, policy)| format!("{} {}: {}", if Some(i) == self.current { "*" } else { " " }, i, policy))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ":use" => match argument.parse::<usize>() {
+                Ok(i) if i < self.policies.len() => self.switch(i),
+                _ => Err(format!("Expected the number of one of the {} policies", self.policies.len())),
+            },
+            ":next" if argument.is_empty() => {
+                let tp = match self.max_tp {
+                    Some(max_tp) if max_tp >= self.open => max_tp,
+                    _ => self.open,
+                };
+                self.advance(tp + 1)
+            }
+            ":next" => match argument.parse::<usize>() {
+                Ok(tp) if tp >= self.open => self.advance(tp + 1),
+                Ok(_) => Err(format!("The time points below {} are already complete", self.open)),
+                Err(_) => Err(format!("Invalid time point {}", argument)),
+            },
+            ":flush" => self.flush(),
+            _ if command.starts_with(':') => Err(format!("Unknown command {}, see :help", command)),
+            _ if line.starts_with('{') => {
+                let value: serde_json::Value = serde_json::from_str(line).map_err(|e| format!("Invalid JSON event: {}", e))?;
+                let ts = find_timestamp(&value).ok_or_else(|| "The JSON event has no timestamp".to_string())? as usize;
+                self.event(ts, ts, value.to_string())
+            }
+            _ => match parser_extended_wrapper(line.to_string()) {
+                ParserReturn::Data(tp, ts, event) => {
+                    if let Formula::CstFact(name, _) = &event {
+                        self.predicates.insert(name.clone());
+                    }
+                    self.event(tp, ts, event.to_string())
+                }
+                ParserReturn::Watermark(wm) if wm >= self.open as i64 => self.advance(wm as usize + 1),
+                ParserReturn::Watermark(_) => Ok(String::new()),
+                ParserReturn::Error(_) => Err(format!("Unable to parse {}, expected a command (see :help) or an event", line)),
+            },
+        }
+    }
+
+    // Monitors the i-th policy from time point 0
+    fn switch(&mut self, i: usize) -> Result<String, String> {
+        let session = Session::start(&self.policies[i], self.workers)?;
+        self.predicates.extend(predicate_arities(&parse_formula(&self.policies[i])).into_keys());
+        self.current = Some(i);
+        self.restart(session);
+        Ok(format!("Monitoring policy {}: {}", i, self.policies[i]))
+    }
+
+    fn restart(&mut self, session: Session) {
+        self.session = Some(session);
+        self.open = 0;
+        self.max_tp = None;
+        self.timestamps.clear();
+        self.records.clear();
+    }
+
+    fn session(&self) -> Result<&Session, String> {
+        self.session.as_ref().ok_or_else(|| "No policy, expected :policy <formula>".to_string())
+    }
+
+    fn event(&mut self, tp: usize, ts: usize, event: String) -> Result<String, String> {
+        if tp < self.open {
+            return Err(format!("Time point {} is already complete", tp));
+        }
+        let first = !self.timestamps.contains_key(&tp);
+        self.session()?.send(Command::Event(tp, if first { Some(ts) } else { None }, event))?;
+        self.timestamps.entry(tp).or_insert(ts);
+        self.max_tp = self.max_tp.max(Some(tp));
+        Ok(String::new())
+    }
+
+    // Completes the time points below `tp` and prints the verdicts that became final
+    fn advance(&mut self, tp: usize) -> Result<String, String> {
+        let frontier = self.session()?.complete(Command::Advance(tp))?;
+        self.open = tp;
+        Ok(self.final_verdicts(frontier))
+    }
+
+    // Closes the input, which makes the remaining verdicts final
+    fn flush(&mut self) -> Result<String, String> {
+        let end = self.max_tp.map_or(self.open, |max_tp| self.open.max(max_tp + 1));
+        let frontier = self.session()?.complete(Command::End(end))?;
+        let mut output = self.final_verdicts(frontier);
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        let i = self.current.unwrap();
+        output.push_str(&format!("End of the stream, monitoring policy {} from time point 0", i));
+        let session = Session::start(&self.policies[i], self.workers)?;
+        self.restart(session);
+        Ok(output)
+    }
+
+    // Verdicts of the time points below the output frontier, or of all if the dataflow is done
+    fn final_verdicts(&mut self, frontier: Option<usize>) -> String {
+        let session = self.session.as_ref().unwrap();
+        for (tp, records) in session.verdicts.try_iter() {
+            self.records.entry(tp).or_default().extend(records);
+        }
+        let complete: Vec<(usize, usize)> = self.timestamps.iter()
+            .take_while(|(tp, _)| frontier.is_none_or(|f| **tp < f))
+            .map(|(tp, ts)| (*tp, *ts))
+            .collect();
+        let mut output = Vec::new();
+        for (tp, ts) in complete {
+            self.timestamps.remove(&tp);
+            let records = self.records.remove(&tp).unwrap_or_default();
+            let _ = write_verdicts(&mut output, tp, ts, session.no_free_vars, records);
+        }
+        String::from_utf8_lossy(&output).trim_end().to_string()
+    }
+}
+
+/// The formula, its evaluation plan and the optimized plan, or why the formula is invalid.
+pub fn describe(policy: &str) -> Result<String, String> {
+    let formula = parse_formula(policy);
+    if let Some(e) = formula_error(&formula) {
+        return Err(format!("Invalid policy: {}", e));
+    }
+    let plan = generate_evaluation_plan(&formula);
+    Ok(format!(
+        "Formula: {}\nExpr: {:?}\nOptimized plan: {:?}",
+        formula,
+        plan,
+        optimize_evaluation_plan(plan.clone())
+    ))
+}
+
+/// Start of the word before the cursor and its completions: commands at the start of the line,
+/// predicate names otherwise.
+pub fn complete_word(line: &str, pos: usize, predicates: &BTreeSet<String>) -> (usize, Vec<String>) {
+    let start = line[..pos].rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).map_or(0, |i| i + 1);
+    let word = &line[start..pos];
+    let candidates = if start == 0 && word.starts_with(':') {
+        COMMANDS.iter().filter(|c| c.starts_with(word)).map(|c| c.to_string()).collect()
+    } else if word.is_empty() {
+        Vec::new()
+    } else {
+        predicates.iter().filter(|p| p.starts_with(word)).cloned().collect()
+    };
+    (start, candidates)
+}
+
+struct ReplHelper {
+    predicates: BTreeSet<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Result<(usize, Vec<String>), ReadlineError> {
+        Ok(complete_word(line, pos, &self.predicates))
+    }
+}
+
+impl Hinter for ReplHelper {}
+
+impl Highlighter for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+fn history_file() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+/// Reads commands and events until `:quit` or the end of the input, with the history kept in
+/// `~/.timelymon_history`.
+pub fn run(workers: usize) -> Result<(), String> {
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper { predicates: BTreeSet::new() }));
+    let history = history_file();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+
+    let mut repl = Repl::new(workers);
+    println!("Type :help for the commands");
+    loop {
+        match editor.readline(&format!("tp {}> ", repl.time_point())) {
+            Ok(line) => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(line.as_str());
+                if line.trim() == ":quit" {
+                    break;
+                }
+                match repl.eval(&line) {
+                    Ok(output) if output.is_empty() => {}
+                    Ok(output) => println!("{}", output),
+                    Err(e) => println!("{}", e),
+                }
+                editor.helper_mut().unwrap().predicates = repl.predicates().clone();
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(format!("Unable to read the input: {}", e)),
+        }
+    }
+
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdicts_of_completed_time_points() {
+        let mut repl = Repl::new(2);
+        assert!(repl.eval("A, tp=0, ts=0, x0=1").unwrap_err().starts_with("No policy"));
+        assert!(repl.eval(":policy (ONCE[0,2] A(x)) AND B(x)").unwrap().ends_with("Monitoring policy 0: (ONCE[0,2] A(x)) AND B(x)"));
+
+        assert_eq!(repl.eval("A, tp=0, ts=0, x0=1").unwrap(), "");
+        assert_eq!(repl.eval("B, tp=0, ts=0, x0=1").unwrap(), "");
+        assert_eq!(repl.eval("B, tp=1, ts=1, x0=2").unwrap(), "");
+        assert_eq!(repl.eval(":next 0").unwrap(), "@0 (time point 0): true (Int(1))");
+        assert_eq!(repl.time_point(), 1);
+        assert!(repl.eval("B, tp=0, ts=0, x0=3").is_err());
+
+        assert_eq!(repl.eval("A, tp=2, ts=2, x0=2").unwrap(), "");
+        assert_eq!(repl.eval("B, tp=3, ts=4, x0=2").unwrap(), "");
+        assert_eq!(repl.eval(":next").unwrap(), "@4 (time point 3): true (Int(2))");
+        assert_eq!(repl.time_point(), 4);
+        assert!(repl.eval(":flush").unwrap().starts_with("End of the stream"));
+        assert_eq!(repl.time_point(), 0);
+
+        // policies without free variables get one verdict per time point
+        repl.eval(":policy EVENTUALLY[0,1] B(1)").unwrap();
+        repl.eval("A, tp=0, ts=0, x0=1").unwrap();
+        repl.eval("B, tp=1, ts=1, x0=1").unwrap();
+        repl.eval("A, tp=2, ts=5, x0=1").unwrap();
+        // the verdicts that are final after :next depend on the progress of the dataflow, closing
+        // the input prints the others
+        let next = repl.eval(":next").unwrap();
+        let output = format!("{}\n{}", next, repl.eval(":flush").unwrap());
+        assert!(output.trim_start().starts_with("@0 (time point 0): true\n@1 (time point 1): true\n@5 (time point 2): false\n"), "{}", output);
+        assert!(repl.eval(":use 0").unwrap().starts_with("Monitoring policy 0"));
+        assert_eq!(repl.eval(":policies").unwrap(), "* 0: (ONCE[0,2] A(x)) AND B(x)\n  1: EVENTUALLY[0,1] B(1)");
+    }
+
+    #[test]
+    fn json_events_and_watermarks() {
+        let mut repl = Repl::new(1);
+        repl.eval(":policy A(x)").unwrap();
+        assert_eq!(repl.eval("A, tp=0, ts=3, x0=7").unwrap(), "");
+        assert_eq!(repl.eval(">WATERMARK 0<").unwrap(), "@3 (time point 0): true (Int(7))");
+        assert!(repl.eval("{\"user\": \"a\"}").unwrap_err().contains("no timestamp"));
+        assert_eq!(repl.eval("{\"timestamp\": 5, \"user\": \"a\"}").unwrap(), "");
+        assert!(repl.eval(":next").is_ok());
+        assert_eq!(repl.time_point(), 6);
+        assert!(repl.eval("A(1").is_err());
+        assert!(repl.eval(":use 3").is_err());
+        assert!(repl.eval(":bogus").unwrap_err().starts_with("Unknown command"));
+    }
+
+    #[test]
+    fn plans_and_completion() {
+        let description = describe("ONCE[0,2] A(x)").unwrap();
+        assert!(description.starts_with("Formula: "));
+        assert!(description.contains("\nExpr: Once("));
+        assert!(description.contains("\nOptimized plan: Once("));
+        assert!(describe("A(x").is_err());
+
+        let predicates: BTreeSet<String> = vec!["Alpha".to_string(), "Avg".to_string(), "Beta".to_string()].into_iter().collect();
+        assert_eq!(complete_word(":po", 3, &predicates), (0, vec![":policies".to_string(), ":policy".to_string()]));
+        assert_eq!(complete_word("ONCE[0,2] A", 11, &predicates), (10, vec!["Alpha".to_string(), "Avg".to_string()]));
+        assert_eq!(complete_word(":policy B", 9, &predicates), (8, vec!["Beta".to_string()]));
+        assert_eq!(complete_word("A, tp=0", 7, &predicates).1, Vec::<String>::new());
+
+        let mut repl = Repl::new(1);
+        repl.eval(":policy A(x) AND NOT Beta(x)").unwrap();
+        repl.eval("Gamma, tp=0, ts=0, x0=1").unwrap();
+        assert_eq!(repl.predicates().iter().cloned().collect::<Vec<_>>(), vec!["A", "Beta", "Gamma"]);
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use timely::communication::Allocate;
+use timely::dataflow::ProbeHandle;
+use timely::worker::Worker;
+
+// how long stepping may leave the output probe where it is before the input is read on
+const STALL: Duration = Duration::from_millis(10);
+
+/// Stops the ingestion while `limit` ingested time points are incomplete, i.e. the output probe has
+/// not passed them, so the output operators only buffer the records of that many time points.
+///
+/// Some time points only complete with more input: a time point above the last watermark, or one
+/// that waits for the events of a bounded future interval. The ingestion therefore only waits as long
+/// as stepping completes time points, and reads on once the probe stalled. It waits again when the
+/// probe has moved since.
+#[derive(Clone, Debug)]
+pub struct Backpressure {
+    limit: usize,
+    incomplete: BTreeSet<usize>,
+    // probe frontier at which the last wait stalled
+    stalled_at: Option<Vec<usize>>,
+}
+
+impl Backpressure {
+    pub fn new(limit: usize) -> Backpressure {
+        Backpressure { limit: limit.max(1), incomplete: BTreeSet::new(), stalled_at: None }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Records that an event of the time point was ingested.
+    pub fn ingest(&mut self, tp: usize) {
+        self.incomplete.insert(tp);
+    }
+
+    /// Forgets the time points in order as long as `passed` holds for them and returns the number
+    /// of incomplete time points.
+    pub fn complete<F: Fn(usize) -> bool>(&mut self, passed: F) -> usize {
+        while let Some(&tp) = self.incomplete.iter().next() {
+            if !passed(tp) {
+                break;
+            }
+            self.incomplete.remove(&tp);
+        }
+        self.incomplete.len()
+    }
+
+    pub fn incomplete(&self) -> usize {
+        self.incomplete.len()
+    }
+
+    /// Steps the worker until fewer than `limit` ingested time points are incomplete or the probe
+    /// stalls, and calls `stepped` after each step.
+    pub fn wait<A: Allocate, F: FnMut()>(&mut self, worker: &mut Worker<A>, probe: &ProbeHandle<usize>, mut stepped: F) {
+        let passed = |tp: usize| !probe.less_equal(&tp);
+        if self.complete(passed) < self.limit {
+            return;
+        }
+        if self.stalled_at.is_some() {
+            worker.step();
+            stepped();
+        }
+        let mut frontier = probe.with_frontier(|f| f.to_vec());
+        if self.stalled_at.as_ref() == Some(&frontier) {
+            return;
+        }
+        let mut moved = Instant::now();
+        while self.complete(passed) >= self.limit {
+            worker.step_or_park(Some(Duration::from_millis(1)));
+            stepped();
+            let current = probe.with_frontier(|f| f.to_vec());
+            if current != frontier {
+                frontier = current;
+                moved = Instant::now();
+            } else if moved.elapsed() >= STALL {
+                self.stalled_at = Some(frontier);
+                return;
+            }
+        }
+        self.stalled_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use timely::dataflow::operators::{Input, Probe};
+    use timely::dataflow::InputHandle;
+
+    #[test]
+    fn complete_in_time_point_order() {
+        let mut backpressure = Backpressure::new(2);
+        backpressure.ingest(3);
+        backpressure.ingest(1);
+        backpressure.ingest(1);
+        assert_eq!(backpressure.incomplete(), 2);
+        // 3 is only forgotten after 1 passed
+        assert_eq!(backpressure.complete(|tp| tp == 3), 2);
+        assert_eq!(backpressure.complete(|tp| tp < 2), 1);
+        assert_eq!(backpressure.complete(|_| true), 0);
+    }
+
+    #[test]
+    fn ingestion_waits_for_the_output() {
+        timely::execute_directly(|worker| {
+            let mut input = InputHandle::new();
+            let mut probe = ProbeHandle::new();
+            worker.dataflow::<usize, _, _>(|scope| {
+                scope.input_from(&mut input).probe_with(&mut probe);
+            });
+
+            let mut backpressure = Backpressure::new(3);
+            for tp in 0..20 {
+                input.send(tp);
+                backpressure.ingest(tp);
+                input.advance_to(tp + 1);
+                backpressure.wait(worker, &probe, || ());
+                assert!(backpressure.incomplete() < 3, "{} incomplete after time point {}", backpressure.incomplete(), tp);
+            }
+        });
+    }
+
+    #[test]
+    fn ingestion_reads_on_once_the_output_stalls() {
+        timely::execute_directly(|worker| {
+            let mut input = InputHandle::new();
+            let mut probe = ProbeHandle::new();
+            worker.dataflow::<usize, _, _>(|scope| {
+                scope.input_from(&mut input).probe_with(&mut probe);
+            });
+
+            // the time points only complete once the input advances, after the loop
+            let mut backpressure = Backpressure::new(3);
+            for tp in 0..20 {
+                input.send(tp);
+                backpressure.ingest(tp);
+                backpressure.wait(worker, &probe, || ());
+            }
+            assert_eq!(backpressure.incomplete(), 20);
+
+            input.advance_to(10);
+            while probe.less_than(&10) {
+                worker.step();
+            }
+            backpressure.wait(worker, &probe, || ());
+            assert_eq!(backpressure.incomplete(), 10);
+        });
+    }
+}
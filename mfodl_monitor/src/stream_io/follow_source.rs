@@ -0,0 +1,255 @@
+use std::fs::{File, Metadata};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stream_io::tcp_source::SHUTDOWN_COMMAND;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// number of leading bytes that identify the content of a followed file
+const HEAD_LEN: usize = 256;
+
+/// Reads the lines of a file that is still being appended to, like `tail -F`. If the file is
+/// truncated or copied away and truncated, reading restarts at its beginning. If it is rotated,
+/// i.e. the path refers to a new file, the remaining lines of the old file are read before
+/// switching to the new one. A file counts as replaced if its inode changes or its first bytes
+/// change. An incomplete last line is kept until the rest of it arrives, also across truncation
+/// and rotation. The stream ends at the shutdown command or once no new line arrived within the
+/// idle timeout.
+pub struct FollowLines {
+    path: PathBuf,
+    reader: BufReader<File>,
+    // first bytes of the followed file, up to HEAD_LEN
+    head: Vec<u8>,
+    // the path refers to a new file, which is read once the old one is read to the end
+    rotated: bool,
+    // bytes of the current line that is not yet complete
+    partial: String,
+    idle_timeout: Option<Duration>,
+    last_line: Instant,
+    done: bool,
+}
+
+impl FollowLines {
+    pub fn open(path: PathBuf, idle_timeout: Option<Duration>) -> io::Result<FollowLines> {
+        let mut file = File::open(&path)?;
+        let head = read_head(&mut file)?;
+        Ok(FollowLines {
+            path,
+            reader: BufReader::new(file),
+            head,
+            rotated: false,
+            partial: String::new(),
+            idle_timeout,
+            last_line: Instant::now(),
+            done: false,
+        })
+    }
+
+    // a complete line if the file currently contains one
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        if self.reader.read_line(&mut self.partial)? == 0 || !self.partial.ends_with('\n') {
+            return Ok(None);
+        }
+        let mut line = std::mem::take(&mut self.partial);
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+
+    // notices a rotation and switches to the beginning of the file after a truncation. Only called
+    // once the followed file is read to the end.
+    fn check_file(&mut self) -> io::Result<()> {
+        if self.rotated {
+            // the old file is read to the end, including lines appended after the rotation
+            if let Ok(mut file) = File::open(&self.path) {
+                self.head = read_head(&mut file)?;
+                self.reader = BufReader::new(file);
+                self.rotated = false;
+            }
+            return Ok(());
+        }
+
+        let current = self.reader.get_ref().metadata()?;
+        let position = self.reader.stream_position()?;
+        match std::fs::metadata(&self.path) {
+            Ok(latest) if !same_file(&current, &latest) => self.rotated = true,
+            Ok(latest) => {
+                let head = read_head(&mut File::open(&self.path)?)?;
+                if latest.len() < position || !head.starts_with(&self.head) {
+                    // truncated, possibly after being copied away, and maybe rewritten since
+                    self.reader.seek(SeekFrom::Start(0))?;
+                }
+                self.head = head;
+            }
+            // the path is missing between the rotation and the creation of the new file
+            Err(_) => {}
+        }
+        Ok(())
+    }
+}
+
+// the first bytes of a file that is opened for it, its position is back at the start afterwards
+fn read_head(file: &mut File) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(HEAD_LEN);
+    file.by_ref().take(HEAD_LEN as u64).read_to_end(&mut head)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(head)
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Metadata, _b: &Metadata) -> bool {
+    true
+}
+
+impl Iterator for FollowLines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while !self.done {
+            match self.read_line() {
+                Ok(Some(line)) if line.trim() == SHUTDOWN_COMMAND => self.done = true,
+                Ok(Some(line)) => {
+                    self.last_line = Instant::now();
+                    return Some(line);
+                }
+                Ok(None) => {
+                    if self.idle_timeout.is_some_and(|timeout| self.last_line.elapsed() >= timeout) {
+                        self.done = true;
+                    } else {
+                        // checked right before reading again, so that new content of a replaced
+                        // file is not read from the old position
+                        thread::sleep(POLL_INTERVAL);
+                        if let Err(e) = self.check_file() {
+                            eprintln!("Follow Error: {}", e);
+                            self.done = true;
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Follow Error: {}", e);
+                    self.done = true;
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File, OpenOptions};
+    use std::io::Write;
+    use std::time::Duration;
+    use stream_io::follow_source::FollowLines;
+    use stream_io::tcp_source::SHUTDOWN_COMMAND;
+
+    fn append(path: &std::path::Path, text: &str) {
+        OpenOptions::new().append(true).open(path).unwrap().write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn follow_growing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.csv");
+        File::create(&path).unwrap().write_all(b"A, tp=0, ts=0, x=1\n").unwrap();
+
+        let mut lines = FollowLines::open(path.clone(), Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+
+        // a line is only returned once it is complete
+        append(&path, "A, tp=1, ");
+        append(&path, "ts=1, x=2\n");
+        assert_eq!(lines.next(), Some("A, tp=1, ts=1, x=2".to_string()));
+
+        append(&path, &format!("{}\nA, tp=2, ts=2, x=3\n", SHUTDOWN_COMMAND));
+        assert_eq!(lines.next(), None);
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn follow_truncation_and_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.csv");
+        File::create(&path).unwrap().write_all(b"A, tp=0, ts=0, x=1\nA, tp=1, ts=1, x=2\n").unwrap();
+
+        let mut lines = FollowLines::open(path.clone(), Some(Duration::from_millis(500))).unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+        assert_eq!(lines.next(), Some("A, tp=1, ts=1, x=2".to_string()));
+
+        File::create(&path).unwrap().write_all(b"A, tp=2, ts=2, x=3\n").unwrap();
+        assert_eq!(lines.next(), Some("A, tp=2, ts=2, x=3".to_string()));
+
+        fs::rename(&path, dir.path().join("log.csv.1")).unwrap();
+        File::create(&path).unwrap().write_all(b"A, tp=3, ts=3, x=4\n").unwrap();
+        assert_eq!(lines.next(), Some("A, tp=3, ts=3, x=4".to_string()));
+
+        // nothing is appended within the idle timeout
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn follow_copy_truncation_with_longer_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.csv");
+        File::create(&path).unwrap().write_all(b"A, tp=0, ts=0, x=1\n").unwrap();
+
+        let mut lines = FollowLines::open(path.clone(), Some(Duration::from_millis(500))).unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+
+        // copied away and truncated, and the new content outgrows the old one before the next poll
+        let reader = std::thread::spawn(move || (lines.next(), lines));
+        std::thread::sleep(Duration::from_millis(150));
+        fs::copy(&path, dir.path().join("log.csv.1")).unwrap();
+        File::create(&path).unwrap().write_all(b"B, tp=1, ts=1, x=2\nB, tp=2, ts=2, x=3\n").unwrap();
+        let (line, mut lines) = reader.join().unwrap();
+        assert_eq!(line, Some("B, tp=1, ts=1, x=2".to_string()));
+        assert_eq!(lines.next(), Some("B, tp=2, ts=2, x=3".to_string()));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn follow_rotation_reads_the_old_file_to_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.csv");
+        let rotated = dir.path().join("log.csv.1");
+        File::create(&path).unwrap().write_all(b"A, tp=0, ts=0, x=1\nA, tp=1, ").unwrap();
+
+        let mut lines = FollowLines::open(path.clone(), Some(Duration::from_millis(500))).unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+
+        // the writer still holds the old file after the rename and completes its last line there
+        fs::rename(&path, &rotated).unwrap();
+        File::create(&path).unwrap().write_all(b"A, tp=3, ts=3, x=4\n").unwrap();
+        append(&rotated, "ts=1, x=2\nA, tp=2, ts=2, x=3\n");
+        assert_eq!(lines.next(), Some("A, tp=1, ts=1, x=2".to_string()));
+        assert_eq!(lines.next(), Some("A, tp=2, ts=2, x=3".to_string()));
+        assert_eq!(lines.next(), Some("A, tp=3, ts=3, x=4".to_string()));
+    }
+
+    #[test]
+    fn follow_keeps_an_incomplete_line_across_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.csv");
+        File::create(&path).unwrap().write_all(b"A, tp=0, ts=0, x=1\nA, tp=1, ").unwrap();
+
+        let mut lines = FollowLines::open(path.clone(), Some(Duration::from_millis(500))).unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+
+        // copytruncate between the two writes of a line
+        let reader = std::thread::spawn(move || lines.next());
+        std::thread::sleep(Duration::from_millis(150));
+        File::create(&path).unwrap().write_all(b"ts=1, x=2\n").unwrap();
+        assert_eq!(reader.join().unwrap(), Some("A, tp=1, ts=1, x=2".to_string()));
+    }
+}
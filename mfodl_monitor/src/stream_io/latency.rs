@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use timely::dataflow::ProbeHandle;
+
+// smallest bucket of the histogram, every further bucket doubles the bound
+const FIRST_BUCKET: Duration = Duration::from_micros(100);
+
+/// When the last event of a time point was ingested and when the output probe passed it, i.e.
+/// its verdicts were final.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimePointLatency {
+    pub tp: usize,
+    pub ingested: Instant,
+    pub complete: Instant,
+}
+
+impl TimePointLatency {
+    pub fn latency(&self) -> Duration {
+        self.complete.saturating_duration_since(self.ingested)
+    }
+}
+
+/// Records the ingestion of the time points fed to a dataflow and the latency of their verdicts.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyTracker {
+    // latest ingestion of the time points the probe has not passed yet
+    pending: BTreeMap<usize, Instant>,
+    complete: Vec<TimePointLatency>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> LatencyTracker {
+        LatencyTracker::default()
+    }
+
+    /// Records that an event of the time point was ingested now.
+    pub fn ingest(&mut self, tp: usize) {
+        self.ingest_at(tp, Instant::now());
+    }
+
+    pub fn ingest_at(&mut self, tp: usize, at: Instant) {
+        self.pending.insert(tp, at);
+    }
+
+    /// Completes the time points the output probe has passed.
+    pub fn complete(&mut self, probe: &ProbeHandle<usize>) {
+        self.complete_at(|tp| !probe.less_equal(&tp), Instant::now());
+    }
+
+    /// Completes the time points in order as long as `passed` holds for them.
+    pub fn complete_at<F: Fn(usize) -> bool>(&mut self, passed: F, at: Instant) {
+        while let Some((&tp, &ingested)) = self.pending.iter().next() {
+            if !passed(tp) {
+                break;
+            }
+            self.pending.remove(&tp);
+            self.complete.push(TimePointLatency { tp, ingested, complete: at });
+        }
+    }
+
+    /// Time points whose verdicts are not final yet.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn time_points(&self) -> &[TimePointLatency] {
+        &self.complete
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        LatencyReport::new(self.complete.iter().map(TimePointLatency::latency).collect())
+    }
+}
+
+/// Distribution of the latencies of time points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencyReport {
+    // sorted
+    latencies: Vec<Duration>,
+}
+
+impl LatencyReport {
+    pub fn new(mut latencies: Vec<Duration>) -> LatencyReport {
+        latencies.sort();
+        LatencyReport { latencies }
+    }
+
+    pub fn len(&self) -> usize {
+        self.latencies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latencies.is_empty()
+    }
+
+    /// Latency that `percentile` percent of the time points do not exceed (nearest rank), none
+    /// without time points.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let rank = ((percentile / 100.0) * self.latencies.len() as f64).ceil() as usize;
+        Some(self.latencies[rank.max(1).min(self.latencies.len()) - 1])
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.latencies.last().cloned()
+    }
+
+    /// Number of time points per bucket, as the exclusive upper bound of the bucket and its count.
+    /// The bounds double from 100µs up to the first one above the largest latency.
+    pub fn histogram(&self) -> Vec<(Duration, usize)> {
+        let mut buckets = Vec::new();
+        let mut bound = FIRST_BUCKET;
+        let mut rest = &self.latencies[..];
+        while !rest.is_empty() {
+            let count = rest.iter().take_while(|l| **l < bound).count();
+            buckets.push((bound, count));
+            rest = &rest[count..];
+            bound *= 2;
+        }
+        buckets
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl fmt::Display for LatencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Latency of {} time points", self.len())?;
+        if self.is_empty() {
+            return Ok(());
+        }
+        for &p in &[50.0, 90.0, 99.0] {
+            write!(f, ", p{} {:.3}ms", p, millis(self.percentile(p).unwrap()))?;
+        }
+        write!(f, ", max {:.3}ms", millis(self.max().unwrap()))?;
+        for (bound, count) in self.histogram() {
+            write!(f, "\n  < {:>10.3}ms {:>8}", millis(bound), count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_from_the_last_event() {
+        let start = Instant::now();
+        let mut tracker = LatencyTracker::new();
+        tracker.ingest_at(2, start);
+        tracker.ingest_at(1, start);
+        tracker.ingest_at(2, start + Duration::from_millis(4));
+        tracker.complete_at(|tp| tp < 2, start + Duration::from_millis(5));
+        assert_eq!(tracker.pending(), 1);
+        tracker.complete_at(|_| true, start + Duration::from_millis(10));
+        assert_eq!(tracker.pending(), 0);
+
+        let latencies: Vec<(usize, Duration)> = tracker.time_points().iter().map(|t| (t.tp, t.latency())).collect();
+        assert_eq!(latencies, vec![(1, Duration::from_millis(5)), (2, Duration::from_millis(6))]);
+        assert_eq!(tracker.report().percentile(50.0), Some(Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn percentiles_and_histogram() {
+        let report = LatencyReport::new((1..=10).rev().map(Duration::from_millis).collect());
+        assert_eq!(report.percentile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(report.percentile(50.0), Some(Duration::from_millis(5)));
+        assert_eq!(report.percentile(90.0), Some(Duration::from_millis(9)));
+        assert_eq!(report.percentile(99.0), Some(Duration::from_millis(10)));
+        assert_eq!(report.max(), Some(Duration::from_millis(10)));
+
+        let histogram = report.histogram();
+        assert_eq!(histogram.iter().map(|(_, count)| count).sum::<usize>(), 10);
+        // 1ms falls into the bucket below 1.6ms, 10ms into the one below 12.8ms
+        assert_eq!(histogram.len(), 8);
+        assert_eq!(histogram[4], (Duration::from_micros(1600), 1));
+        assert_eq!(histogram[7], (Duration::from_micros(12800), 4));
+        assert!(report.to_string().starts_with("Latency of 10 time points, p50 5.000ms, p90 9.000ms, p99 10.000ms, max 10.000ms\n"));
+
+        let empty = LatencyReport::new(vec![]);
+        assert_eq!(empty.percentile(50.0), None);
+        assert!(empty.histogram().is_empty());
+        assert_eq!(empty.to_string(), "Latency of 0 time points");
+    }
+}
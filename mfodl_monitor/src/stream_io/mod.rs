@@ -0,0 +1,8 @@
+pub mod tcp_source;
+pub mod socket_sink;
+pub mod follow_source;
+pub mod replayer;
+pub mod reorder;
+pub mod latency;
+pub mod backpressure;
+pub mod polled_source;
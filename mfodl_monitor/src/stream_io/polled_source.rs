@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Result of polling a source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Poll<T> {
+    Item(T),
+    /// No item arrived within the timeout, the source is still open.
+    Pending,
+    /// The source ended.
+    Ended,
+}
+
+struct SourceState<T> {
+    queue: VecDeque<T>,
+    capacity: usize,
+    ended: bool,
+    // the PolledSource was dropped
+    closed: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<SourceState<T>>,
+    changed: Condvar,
+}
+
+/// Items of a blocking source (stdin, a socket, a followed file), read on a thread of its own so that
+/// the ingestion can keep stepping the worker and notice that it should stop while no item arrives.
+/// At most `capacity` items are read ahead. The queued items are taken all at once, so the threads
+/// do not hand over every single item. Once the PolledSource is dropped, the thread stops at the
+/// next item, a source that stays open keeps it blocked until the process exits.
+pub struct PolledSource<T> {
+    shared: Arc<Shared<T>>,
+    taken: VecDeque<T>,
+}
+
+impl<T: Send + 'static> PolledSource<T> {
+    pub fn spawn<I>(items: I, capacity: usize) -> PolledSource<T>
+    where
+        I: IntoIterator<Item = T> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SourceState { queue: VecDeque::new(), capacity: capacity.max(1), ended: false, closed: false }),
+            changed: Condvar::new(),
+        });
+        let reader_shared = shared.clone();
+        thread::spawn(move || read(items, reader_shared));
+        PolledSource { shared, taken: VecDeque::new() }
+    }
+
+    /// Waits up to `timeout` for the next item.
+    pub fn poll(&mut self, timeout: Duration) -> Poll<T> {
+        if self.taken.is_empty() {
+            let mut state = self.shared.state.lock().unwrap();
+            if state.queue.is_empty() && !state.ended {
+                state = self.shared.changed.wait_timeout(state, timeout).unwrap().0;
+            }
+            if state.queue.is_empty() {
+                return if state.ended { Poll::Ended } else { Poll::Pending };
+            }
+            mem::swap(&mut self.taken, &mut state.queue);
+            self.shared.changed.notify_all();
+        }
+        Poll::Item(self.taken.pop_front().unwrap())
+    }
+}
+
+impl<T> Drop for PolledSource<T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+// Runs on its own thread and queues the items until the source ends or the PolledSource is dropped.
+// The consumer is only woken when the queue was empty, as it does not wait otherwise.
+fn read<T, I: IntoIterator<Item = T>>(items: I, shared: Arc<Shared<T>>) {
+    for item in items {
+        let mut state = shared.state.lock().unwrap();
+        while state.queue.len() >= state.capacity && !state.closed {
+            state = shared.changed.wait(state).unwrap();
+        }
+        if state.closed {
+            return;
+        }
+        state.queue.push_back(item);
+        if state.queue.len() == 1 {
+            shared.changed.notify_all();
+        }
+    }
+    shared.state.lock().unwrap().ended = true;
+    shared.changed.notify_all();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn items_then_end() {
+        let mut source = PolledSource::spawn(vec![1, 2], 1);
+        assert_eq!(source.poll(Duration::from_secs(5)), Poll::Item(1));
+        assert_eq!(source.poll(Duration::from_secs(5)), Poll::Item(2));
+        assert_eq!(source.poll(Duration::from_secs(5)), Poll::Ended);
+    }
+
+    #[test]
+    fn open_source_is_pending() {
+        let (send, recv) = channel();
+        let mut source = PolledSource::spawn(recv, 1);
+        assert_eq!(source.poll(TIMEOUT), Poll::Pending);
+        send.send("A, tp=0, ts=0").unwrap();
+        assert_eq!(source.poll(Duration::from_secs(5)), Poll::Item("A, tp=0, ts=0"));
+        assert_eq!(source.poll(TIMEOUT), Poll::Pending);
+        drop(send);
+        assert_eq!(source.poll(Duration::from_secs(5)), Poll::Ended);
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+
+use rand::distributions::{IndependentSample, Normal};
+use rand::{Rng, SeedableRng, StdRng};
+
+use parser::binary_log::LogRecord;
+use trace_generator::Event;
+
+// draws of a delay before falling back to the mean, when the distribution hardly overlaps the range
+const MAX_DRAWS: usize = 100;
+
+/// Delay of an event in time points: normally distributed, redrawn until it is between 0 and the
+/// largest delay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DelayDistribution {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub max_delay: usize,
+}
+
+/// The events of a CSV log in the order of the log, its watermarks are dropped.
+pub fn log_events(records: Vec<LogRecord>) -> io::Result<Vec<Event>> {
+    let mut events = Vec::with_capacity(records.len());
+    for record in records {
+        match record {
+            LogRecord::Event(tp, ts, predicate, args) => events.push(Event { tp, ts, predicate, args }),
+            LogRecord::Watermark(_) => {}
+            LogRecord::Json(_, text) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unexpected JSON event {}", text)))
+            }
+        }
+    }
+    Ok(events)
+}
+
+// The events grouped by time point in time point order, and the time points in epochs of `epoch`
+// time points, a single epoch otherwise
+fn epochs(mut events: Vec<Event>, epoch: Option<usize>) -> Vec<Vec<Vec<Event>>> {
+    events.sort_by_key(|e| e.tp);
+    let mut epochs: Vec<Vec<Vec<Event>>> = Vec::new();
+    let key = |tp: usize| epoch.map_or(0, |n| tp / n.max(1));
+    for event in events {
+        let new_epoch = epochs.last().and_then(|e| e.last()).is_none_or(|tp| key(tp[0].tp) != key(event.tp));
+        if new_epoch {
+            epochs.push(Vec::new());
+        }
+        let time_points = epochs.last_mut().unwrap();
+        if time_points.last().is_none_or(|tp| tp[0].tp != event.tp) {
+            time_points.push(Vec::new());
+        }
+        time_points.last_mut().unwrap().push(event);
+    }
+    epochs
+}
+
+/// Reverses the events within each epoch of `epoch` time points, or the whole trace.
+pub fn reverse(events: Vec<Event>, epoch: Option<usize>) -> Vec<Event> {
+    epochs(events, epoch).into_iter()
+        .flat_map(|time_points| time_points.into_iter().flatten().rev())
+        .collect()
+}
+
+/// Shuffles the time points within each epoch of `epoch` time points, or of the whole trace. The
+/// events of a time point stay together and in order.
+pub fn shuffle_epochs(events: Vec<Event>, epoch: Option<usize>, seed: usize) -> Vec<Event> {
+    let mut rng = StdRng::from_seed(&[seed]);
+    epochs(events, epoch).into_iter()
+        .flat_map(|mut time_points| {
+            rng.shuffle(&mut time_points);
+            time_points.into_iter().flatten()
+        })
+        .collect()
+}
+
+/// Shuffles the events within each epoch of `epoch` time points, or of the whole trace. Unlike
+/// `shuffle_epochs`, the events of a time point are spread over their epoch.
+pub fn shuffle_events(events: Vec<Event>, epoch: Option<usize>, seed: usize) -> Vec<Event> {
+    let mut rng = StdRng::from_seed(&[seed]);
+    epochs(events, epoch).into_iter()
+        .flat_map(|time_points| {
+            let mut events: Vec<Event> = time_points.into_iter().flatten().collect();
+            rng.shuffle(&mut events);
+            events
+        })
+        .collect()
+}
+
+/// Delays every event by a random number of time points. Events with the same delayed position
+/// keep their order.
+pub fn delay(mut events: Vec<Event>, distribution: DelayDistribution, seed: usize) -> Vec<Event> {
+    events.sort_by_key(|e| e.tp);
+    let mut reorder = Reorder::new(distribution, None, seed);
+    let mut delayed = Vec::with_capacity(events.len());
+    for event in events {
+        let (tp, label) = (event.tp, event.tp as i64);
+        delayed.extend(reorder.push(tp, label, event).expect("events are sorted by time point"));
+    }
+    delayed.extend(reorder.finish());
+    delayed.into_iter()
+        .filter_map(|item| match item {
+            Delayed::Item(_, event) => Some(event),
+            Delayed::Watermark(_, _) => None,
+        })
+        .collect()
+}
+
+impl DelayDistribution {
+    /// A delay between 0 and the largest delay, the mean if the draws keep missing the range.
+    pub fn sample<R: Rng>(&self, normal: &Normal, rng: &mut R) -> usize {
+        if self.max_delay == 0 {
+            return 0;
+        }
+        let max_delay = self.max_delay as f64;
+        let d = (0..MAX_DRAWS).map(|_| normal.ind_sample(rng).round())
+            .find(|d| *d >= 0.0 && *d <= max_delay)
+            .unwrap_or_else(|| self.mean.round().max(0.0).min(max_delay));
+        d as usize
+    }
+}
+
+/// An item of a delayed stream with the position it is emitted at, or a watermark with the largest
+/// label all of whose items were emitted before it, -1 if there is none.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Delayed<T> {
+    Item(usize, T),
+    Watermark(usize, i64),
+}
+
+/// Delays items that arrive in the order of their positions, e.g. time points or timestamps, by
+/// the delay distribution and interleaves watermarks every watermark period of positions. Labels,
+/// e.g. the time points of the items, grow with the positions. Only the items that can still be
+/// overtaken by later ones are buffered, i.e. those of the last `max_delay` positions.
+pub struct Reorder<T> {
+    distribution: DelayDistribution,
+    normal: Normal,
+    rng: StdRng,
+    watermark_period: Option<usize>,
+    // delayed positions and arrival of the buffered items
+    buffer: BTreeMap<(usize, usize), T>,
+    arrivals: usize,
+    // positions and labels of the items that a watermark does not cover yet
+    uncovered: VecDeque<(usize, i64)>,
+    covered: i64,
+    next_watermark: Option<usize>,
+    last_position: usize,
+}
+
+impl<T> Reorder<T> {
+    pub fn new(distribution: DelayDistribution, watermark_period: Option<usize>, seed: usize) -> Reorder<T> {
+        Reorder {
+            distribution,
+            normal: Normal::new(distribution.mean, distribution.std_dev.max(f64::MIN_POSITIVE)),
+            rng: StdRng::from_seed(&[seed]),
+            watermark_period,
+            buffer: BTreeMap::new(),
+            arrivals: 0,
+            uncovered: VecDeque::new(),
+            covered: -1,
+            next_watermark: None,
+            last_position: 0,
+        }
+    }
+
+    /// Adds an item and returns the items and watermarks that no later item can precede. Fails if
+    /// the position is smaller than the one of the previous item.
+    pub fn push(&mut self, position: usize, label: i64, item: T) -> Result<Vec<Delayed<T>>, String> {
+        if position < self.last_position {
+            return Err(format!("Position {} follows the larger position {}", position, self.last_position));
+        }
+        self.last_position = position;
+        // later items are delayed to this position or beyond
+        let mut due = Vec::new();
+        while let Some(entry) = self.buffer.first_entry() {
+            if entry.key().0 >= position {
+                break;
+            }
+            let ((key, _), item) = entry.remove_entry();
+            self.emit(key, item, &mut due);
+        }
+
+        let delay = self.distribution.sample(&self.normal, &mut self.rng);
+        self.buffer.insert((position + delay, self.arrivals), item);
+        self.arrivals += 1;
+        self.uncovered.push_back((position, label));
+        Ok(due)
+    }
+
+    /// The remaining items after the last one.
+    pub fn finish(mut self) -> Vec<Delayed<T>> {
+        let mut due = Vec::new();
+        for ((key, _), item) in std::mem::take(&mut self.buffer) {
+            self.emit(key, item, &mut due);
+        }
+        due
+    }
+
+    fn emit(&mut self, key: usize, item: T, due: &mut Vec<Delayed<T>>) {
+        if let Some(period) = self.watermark_period {
+            let mut next = self.next_watermark.unwrap_or(key);
+            while next <= key {
+                // the items of smaller positions were emitted before the position next
+                while let Some(&(position, label)) = self.uncovered.front() {
+                    if position + self.distribution.max_delay >= next {
+                        break;
+                    }
+                    self.covered = label;
+                    self.uncovered.pop_front();
+                }
+                due.push(Delayed::Watermark(next, self.covered));
+                next += period.max(1);
+            }
+            self.next_watermark = Some(next);
+        } else {
+            self.uncovered.clear();
+        }
+        due.push(Delayed::Item(key, item));
+    }
+}
+
+/// Writes the events as CSV lines in the given order. After an event, a watermark `>WATERMARK n<`
+/// is written once the largest time point n all of whose events were written advanced by at least
+/// `watermark_period` time points since the last watermark. The last one follows the last event.
+/// Hence, every event after a watermark n has a larger time point than n.
+pub fn with_watermarks(events: &[Event], watermark_period: Option<usize>) -> io::Result<Vec<String>> {
+    // events of each time point that are still to be written
+    let mut pending: BTreeMap<usize, usize> = BTreeMap::new();
+    for event in events {
+        *pending.entry(event.tp).or_insert(0) += 1;
+    }
+
+    let mut lines = Vec::with_capacity(events.len());
+    let mut complete: Option<usize> = None;
+    let mut watermark: Option<usize> = None;
+    for (i, event) in events.iter().enumerate() {
+        lines.push(event.to_csv()?);
+        *pending.get_mut(&event.tp).unwrap() -= 1;
+        while let Some((&tp, &0)) = pending.iter().next() {
+            pending.remove(&tp);
+            complete = Some(tp);
+        }
+
+        if let (Some(period), Some(tp)) = (watermark_period, complete) {
+            let last = i + 1 == events.len();
+            let advanced = watermark.is_none_or(|wm| tp >= wm + period.max(1) || (last && tp > wm));
+            if advanced {
+                lines.push(format!(">WATERMARK {}<", tp));
+                watermark = Some(tp);
+            }
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::csv_parser::parse_lines_to_segments;
+    use parser::csv_parser::Segment::{Epoch, Seg};
+    use parser::formula_syntax_tree::Constant::{Int, Str};
+
+    fn trace() -> Vec<Event> {
+        (0..30).flat_map(|tp| (0..3).map(move |i| Event {
+            tp,
+            ts: tp * 2,
+            predicate: if i == 2 { "B".to_string() } else { "A".to_string() },
+            args: vec![Int(i), Str(format!("v{}", tp))],
+        })).collect()
+    }
+
+    // Checks that the segments of the lines contain every event and that no event follows a
+    // watermark that covers its time point
+    fn check_segments(lines: &[String], events: &[Event]) {
+        let mut watermark = -1;
+        let mut parsed = Vec::new();
+        for segment in parse_lines_to_segments(lines.iter().cloned()) {
+            match segment {
+                Epoch(wm) => {
+                    assert!(wm >= watermark);
+                    watermark = wm;
+                }
+                Seg(tp, ts, facts) => {
+                    assert!(!facts.is_empty());
+                    assert!(tp as i64 > watermark, "time point {} after watermark {}", tp, watermark);
+                    parsed.extend(facts.into_iter().map(|f| (tp, ts, f)));
+                }
+            }
+        }
+        assert_eq!(watermark, events.iter().map(|e| e.tp as i64).max().unwrap());
+
+        let mut expected: Vec<(usize, usize, String)> = events.iter()
+            .map(|e| (e.tp, e.ts, format!("{}({})", e.predicate, e.args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", "))))
+            .collect();
+        expected.sort();
+        parsed.sort();
+        assert_eq!(parsed.len(), expected.len());
+        assert_eq!(parsed.iter().map(|(tp, ts, _)| (*tp, *ts)).collect::<Vec<_>>(),
+                   expected.iter().map(|(tp, ts, _)| (*tp, *ts)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reverse_and_shuffle() {
+        let reversed = reverse(trace(), None);
+        assert_eq!(reversed[0].tp, 29);
+        assert_eq!(reversed[89].tp, 0);
+        check_segments(&with_watermarks(&reversed, Some(1)).unwrap(), &trace());
+        // the only watermark follows the last event
+        assert_eq!(with_watermarks(&reversed, Some(1)).unwrap().iter().filter(|l| l.contains("WATERMARK")).count(), 1);
+
+        let reversed = reverse(trace(), Some(4));
+        assert_eq!(reversed.iter().take(12).map(|e| e.tp).collect::<Vec<_>>(), vec![3, 3, 3, 2, 2, 2, 1, 1, 1, 0, 0, 0]);
+        let lines = with_watermarks(&reversed, Some(1)).unwrap();
+        assert_eq!(lines[12], ">WATERMARK 3<");
+        check_segments(&lines, &trace());
+
+        let shuffled = shuffle_epochs(trace(), Some(5), 7);
+        assert_ne!(shuffled, trace());
+        for (i, epoch) in shuffled.chunks(15).enumerate() {
+            assert!(epoch.iter().all(|e| e.tp / 5 == i));
+            // the events of a time point stay together
+            assert!(epoch.chunks(3).all(|tp| tp.iter().all(|e| e.tp == tp[0].tp)));
+        }
+        check_segments(&with_watermarks(&shuffled, Some(2)).unwrap(), &trace());
+        assert_eq!(shuffle_epochs(trace(), None, 7).len(), 90);
+
+        let shuffled = shuffle_events(trace(), Some(5), 7);
+        for (i, epoch) in shuffled.chunks(15).enumerate() {
+            assert!(epoch.iter().all(|e| e.tp / 5 == i));
+        }
+        assert!(shuffled.chunks(3).any(|tp| tp.iter().any(|e| e.tp != tp[0].tp)));
+        check_segments(&with_watermarks(&shuffled, Some(1)).unwrap(), &trace());
+    }
+
+    #[test]
+    fn delays_are_bounded() {
+        let distribution = DelayDistribution { mean: 1.0, std_dev: 2.0, max_delay: 3 };
+        let delayed = delay(trace(), distribution, 3);
+        assert_ne!(delayed, trace());
+        let mut max_tp = 0;
+        for event in &delayed {
+            assert!(event.tp + 3 >= max_tp);
+            max_tp = max_tp.max(event.tp);
+        }
+        check_segments(&with_watermarks(&delayed, Some(1)).unwrap(), &trace());
+        check_segments(&with_watermarks(&delayed, Some(4)).unwrap(), &trace());
+
+        let none = DelayDistribution { mean: 0.0, std_dev: 0.0, max_delay: 0 };
+        assert_eq!(delay(trace(), none, 3), trace());
+        assert!(with_watermarks(&trace(), None).unwrap().iter().all(|l| !l.contains("WATERMARK")));
+    }
+
+    #[test]
+    fn reorder_covers_emitted_items() {
+        let distribution = DelayDistribution { mean: 1.0, std_dev: 2.0, max_delay: 3 };
+        let mut reorder = Reorder::new(distribution, Some(2), 5);
+        let mut delayed = Vec::new();
+        for tp in 0..30 {
+            delayed.extend(reorder.push(tp * 2, tp as i64, tp).unwrap());
+        }
+        assert!(reorder.push(10, 5, 5).is_err());
+        delayed.extend(reorder.finish());
+
+        let mut emitted = Vec::new();
+        let mut last = 0;
+        for item in delayed {
+            match item {
+                Delayed::Item(emission, tp) => {
+                    assert!(emission >= last && emission >= tp * 2 && emission <= tp * 2 + 3);
+                    last = emission;
+                    emitted.push(tp);
+                }
+                Delayed::Watermark(position, wm) => {
+                    assert!(position >= last);
+                    // every covered item was emitted, the next one can still arrive
+                    assert!((0..=wm).all(|tp| emitted.contains(&(tp as usize))));
+                    assert!(wm + 1 >= 30 || (wm + 1) as usize * 2 + 3 >= position);
+                }
+            }
+        }
+        emitted.sort();
+        assert_eq!(emitted, (0..30).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn watermarks_of_a_log() {
+        let records = vec![
+            LogRecord::Event(0, 0, "A".to_string(), vec![Int(1)]),
+            LogRecord::Watermark(5),
+            LogRecord::Event(2, 3, "A".to_string(), vec![Int(2)]),
+            LogRecord::Event(1, 2, "B".to_string(), vec![]),
+        ];
+        let events = log_events(records).unwrap();
+        assert_eq!(with_watermarks(&events, Some(1)).unwrap(), vec![
+            "A, tp=0, ts=0, x0=1",
+            ">WATERMARK 0<",
+            "A, tp=2, ts=3, x0=2",
+            "B, tp=1, ts=2",
+            ">WATERMARK 2<",
+        ]);
+        assert!(log_events(vec![LogRecord::Json(0, "{}".to_string())]).is_err());
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Map, Value};
+
+use dataflow_constructor::types::TypedEvent;
+use parser::binary_log::{csv_log_records, json_log_records, BinaryLogReader, LogRecord};
+use parser::csv_parser::Segment;
+use parser::formula_syntax_tree::Constant;
+use parser::formula_syntax_tree::Constant::{Int, Str};
+use parser::monpoly::MonpolySegments;
+use stream_io::reorder::{Delayed, DelayDistribution, Reorder};
+use timeunits::TimeUnit;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Csv,
+    Json,
+    MonPoly,
+    Binary,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(LogFormat::Csv),
+            "json" => Ok(LogFormat::Json),
+            "monpoly" => Ok(LogFormat::MonPoly),
+            "binary" => Ok(LogFormat::Binary),
+            _ => Err(format!("Unknown log format {}, expected csv, json, monpoly or binary", s)),
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads a log in any supported format, the records are parsed as they are read.
+pub fn read_log(path: &Path, format: LogFormat) -> io::Result<Box<dyn Iterator<Item = io::Result<LogRecord>>>> {
+    Ok(match format {
+        LogFormat::Csv => Box::new(csv_log_records(path)?),
+        LogFormat::Json => Box::new(json_log_records(path)?),
+        LogFormat::Binary => Box::new(BinaryLogReader::open(path)?),
+        LogFormat::MonPoly => {
+            let lines = BufReader::new(File::open(path)?).lines();
+            Box::new(MonpolySegments::new(lines, None).flat_map(|segment| {
+                let records: Vec<io::Result<LogRecord>> = match segment {
+                    Ok(Segment::Seg(tp, ts, events)) => events.into_iter().map(|event| match event {
+                        TypedEvent::Fact(name, args) => Ok(LogRecord::Event(tp, ts, name, args)),
+                        e => Err(invalid(format!("Unexpected event {:?}", e))),
+                    }).collect(),
+                    Ok(Segment::Epoch(wm)) => vec![Ok(LogRecord::Watermark(wm))],
+                    Err(e) => vec![Err(invalid(e))],
+                };
+                records
+            }))
+        }
+    })
+}
+
+/// An event in the CSV event format, e.g. `A, tp=0, ts=5, x0=1, x1='a'`. Strings are quoted with
+/// the quote character they do not contain, the format has no way to write a string with both.
+pub fn csv_event(tp: usize, ts: usize, name: &str, args: &[Constant]) -> io::Result<String> {
+    let mut line = format!("{}, tp={}, ts={}", name, tp, ts);
+    for (i, arg) in args.iter().enumerate() {
+        match arg {
+            Str(s) if s.contains('\'') && s.contains('"') => {
+                return Err(invalid(format!("Argument {} of {} contains both quote characters", s, name)))
+            }
+            Str(s) if s.contains('\'') => line.push_str(&format!(", x{}=\"{}\"", i, s)),
+            Str(s) => line.push_str(&format!(", x{}='{}'", i, s)),
+            c => line.push_str(&format!(", x{}={}", i, c)),
+        }
+    }
+    Ok(line)
+}
+
+/// An event as a JSON object with the fields timestamp, predicate and x0, x1, ... for the
+/// arguments. The JSON reader uses the timestamp as time point.
+pub fn json_event(ts: usize, name: &str, args: &[Constant]) -> String {
+    let mut object = Map::new();
+    object.insert("timestamp".to_string(), Value::from(ts));
+    object.insert("predicate".to_string(), Value::from(name));
+    for (i, arg) in args.iter().enumerate() {
+        let value = match arg {
+            Int(v) => Value::from(*v),
+            Str(s) => Value::from(s.clone()),
+            Constant::JSONValue(v) => v.clone(),
+        };
+        object.insert(format!("x{}", i), value);
+    }
+    Value::Object(object).to_string()
+}
+
+// The predicate and arguments of a JSON event written by `json_event`, None for other objects,
+// e.g. with an argument that is no int or string
+fn json_to_event(json: &str) -> Option<(String, Vec<Constant>)> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let name = value.get("predicate")?.as_str()?.to_string();
+    let mut args = Vec::new();
+    while let Some(arg) = value.get(format!("x{}", args.len())) {
+        args.push(match arg {
+            Value::String(s) => Str(s.clone()),
+            v => Int(i32::try_from(v.as_i64()?).ok()?),
+        });
+    }
+    Some((name, args))
+}
+
+/// How a log is written and replayed.
+#[derive(Clone, Debug)]
+pub struct ReplayOptions {
+    pub format: LogFormat,
+    /// Events per second, as fast as possible otherwise
+    pub rate: Option<f64>,
+    /// Replays with the speed of the timestamps, accelerated by this factor
+    pub acceleration: Option<f64>,
+    /// Unit of the timestamps, for the acceleration
+    pub ts_unit: TimeUnit,
+    /// Time points (timestamps for JSON) between two injected watermarks
+    pub watermark_period: Option<usize>,
+    /// Largest number of time points (timestamps for JSON) an event is delayed by
+    pub reorder: usize,
+    pub seed: usize,
+}
+
+impl ReplayOptions {
+    pub fn new(format: LogFormat) -> ReplayOptions {
+        ReplayOptions { format, rate: None, acceleration: None, ts_unit: TimeUnit::Second, watermark_period: None, reorder: 0, seed: 0 }
+    }
+
+    // delays centred in 0..=reorder
+    fn delay_distribution(&self) -> DelayDistribution {
+        let half = self.reorder as f64 / 2.0;
+        DelayDistribution { mean: half, std_dev: half, max_delay: self.reorder }
+    }
+}
+
+/// A line of the replayed log with the timestamp it is due at, None for watermarks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayLine {
+    pub ts: Option<usize>,
+    pub text: String,
+}
+
+// An event of the log with its time point and timestamp. JSON objects that are no events are
+// kept as they are and can only be written as JSON.
+enum Entry {
+    Event(usize, usize, String, Vec<Constant>),
+    Raw(usize, usize, String),
+}
+
+impl Entry {
+    fn tp(&self) -> usize {
+        match self {
+            Entry::Event(tp, _, _, _) | Entry::Raw(tp, _, _) => *tp,
+        }
+    }
+
+    fn ts(&self) -> usize {
+        match self {
+            Entry::Event(_, ts, _, _) | Entry::Raw(_, ts, _) => *ts,
+        }
+    }
+
+    fn to_line(&self, format: LogFormat) -> io::Result<ReplayLine> {
+        let text = match (self, format) {
+            (Entry::Event(_, ts, name, args), LogFormat::Json) => json_event(*ts, name, args),
+            (Entry::Event(tp, ts, name, args), _) => csv_event(*tp, *ts, name, args)?,
+            (Entry::Raw(_, _, text), LogFormat::Json) => text.clone(),
+            (Entry::Raw(_, _, text), _) => return Err(invalid(format!("Unable to convert the JSON event {}", text))),
+        };
+        Ok(ReplayLine { ts: Some(self.ts()), text })
+    }
+}
+
+fn watermark_line(wm: i64) -> ReplayLine {
+    ReplayLine { ts: None, text: format!(">WATERMARK {}<", wm) }
+}
+
+/// Converts records into lines of the target format while they are read. Watermarks of the log
+/// are kept, unless watermarks are injected or the events are reordered. Then the events, which
+/// have to be in time point (timestamp for JSON) order, are delayed by at most `reorder` time
+/// points, and every watermark period a watermark with the largest time point all of whose events
+/// were written follows. MonPoly lines are written once the next time point starts.
+pub struct ReplayLines<I> {
+    records: I,
+    format: LogFormat,
+    reorder: Option<Reorder<Entry>>,
+    // JSON events are numbered by their timestamps
+    json_tps: HashMap<usize, usize>,
+    // the MonPoly time point that is written next: time point, timestamp and the tuples of each predicate
+    time_point: Option<(usize, usize, BTreeMap<String, String>)>,
+    ready: VecDeque<ReplayLine>,
+    done: bool,
+}
+
+pub fn replay_lines<I: IntoIterator<Item = io::Result<LogRecord>>>(records: I, options: &ReplayOptions) -> io::Result<ReplayLines<I::IntoIter>> {
+    let regenerate = options.watermark_period.is_some() || options.reorder > 0;
+    if options.format == LogFormat::MonPoly && regenerate {
+        return Err(invalid("MonPoly logs cannot contain watermarks or reordered events".to_string()));
+    }
+    Ok(ReplayLines {
+        records: records.into_iter(),
+        format: options.format,
+        reorder: if regenerate { Some(Reorder::new(options.delay_distribution(), options.watermark_period, options.seed)) } else { None },
+        json_tps: HashMap::new(),
+        time_point: None,
+        ready: VecDeque::new(),
+        done: false,
+    })
+}
+
+impl<I: Iterator<Item = io::Result<LogRecord>>> ReplayLines<I> {
+    fn add(&mut self, record: LogRecord) -> io::Result<()> {
+        let entry = match record {
+            LogRecord::Event(tp, ts, name, args) => Entry::Event(tp, ts, name, args),
+            LogRecord::Json(ts, text) => {
+                let next = self.json_tps.len();
+                let tp = *self.json_tps.entry(ts).or_insert(next);
+                match json_to_event(&text) {
+                    Some((name, args)) => Entry::Event(tp, ts, name, args),
+                    None => Entry::Raw(tp, ts, text),
+                }
+            }
+            LogRecord::Watermark(wm) => {
+                if self.reorder.is_none() && self.format != LogFormat::MonPoly {
+                    self.ready.push_back(watermark_line(wm));
+                }
+                return Ok(());
+            }
+        };
+
+        if self.format == LogFormat::MonPoly {
+            return self.add_monpoly(entry);
+        }
+        let format = self.format;
+        match self.reorder.as_mut() {
+            Some(reorder) => {
+                // the position of an event is its time point in the target format
+                let position = if format == LogFormat::Json { entry.ts() } else { entry.tp() };
+                let delayed = reorder.push(position, position as i64, entry).map_err(invalid)?;
+                self.add_delayed(delayed)
+            }
+            None => {
+                self.ready.push_back(entry.to_line(format)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn add_delayed(&mut self, delayed: Vec<Delayed<Entry>>) -> io::Result<()> {
+        for item in delayed {
+            match item {
+                Delayed::Item(_, entry) => self.ready.push_back(entry.to_line(self.format)?),
+                Delayed::Watermark(_, wm) => self.ready.push_back(watermark_line(wm)),
+            }
+        }
+        Ok(())
+    }
+
+    // One line per time point, e.g. `@5 A(1,2)(3,4) B();`, strings in double quotes
+    fn add_monpoly(&mut self, entry: Entry) -> io::Result<()> {
+        let (tp, ts, name, args) = match entry {
+            Entry::Event(tp, ts, name, args) => (tp, ts, name, args),
+            Entry::Raw(_, _, text) => return Err(invalid(format!("Unable to convert the JSON event {}", text))),
+        };
+        match &self.time_point {
+            Some((current, _, _)) if tp < *current => {
+                return Err(invalid(format!("Time point {} follows time point {}, MonPoly logs are ordered", tp, current)))
+            }
+            Some((current, _, _)) if tp == *current => {}
+            _ => {
+                self.finish_time_point();
+                self.time_point = Some((tp, ts, BTreeMap::new()));
+            }
+        }
+        let args: Vec<String> = args.iter().map(|arg| match arg {
+            Str(s) => format!("\"{}\"", s),
+            c => format!("{}", c),
+        }).collect();
+        let predicates = &mut self.time_point.as_mut().unwrap().2;
+        predicates.entry(name).or_default().push_str(&format!("({})", args.join(",")));
+        Ok(())
+    }
+
+    fn finish_time_point(&mut self) {
+        if let Some((_, ts, predicates)) = self.time_point.take() {
+            let mut text = format!("@{}", ts);
+            for (name, tuples) in predicates {
+                text.push_str(&format!(" {}{}", name, tuples));
+            }
+            text.push(';');
+            self.ready.push_back(ReplayLine { ts: Some(ts), text });
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.finish_time_point();
+        match self.reorder.take() {
+            Some(reorder) => self.add_delayed(reorder.finish()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I: Iterator<Item = io::Result<LogRecord>>> Iterator for ReplayLines<I> {
+    type Item = io::Result<ReplayLine>;
+
+    fn next(&mut self) -> Option<io::Result<ReplayLine>> {
+        loop {
+            if let Some(line) = self.ready.pop_front() {
+                return Some(Ok(line));
+            }
+            if self.done {
+                return None;
+            }
+            let added = match self.records.next() {
+                Some(record) => record.and_then(|record| self.add(record)),
+                None => {
+                    self.done = true;
+                    self.finish()
+                }
+            };
+            if let Err(e) = added {
+                self.done = true;
+                self.ready.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Writes the lines, pacing them with the rate or the speed of the timestamps. Returns the
+/// number of lines.
+pub fn replay<I: IntoIterator<Item = io::Result<ReplayLine>>, W: Write>(lines: I, options: &ReplayOptions, out: &mut W) -> io::Result<usize> {
+    let start = Instant::now();
+    let mut first_ts = None;
+    let millis_per_ts = options.ts_unit.millis() as f64;
+    let mut events = 0;
+    let mut written = 0;
+
+    for line in lines {
+        let line = line?;
+        if first_ts.is_none() {
+            first_ts = line.ts;
+        }
+        let due = match (options.rate, options.acceleration, line.ts) {
+            (Some(rate), _, Some(_)) if rate > 0.0 => Some(Duration::from_secs_f64(events as f64 / rate)),
+            (None, Some(acceleration), Some(ts)) if acceleration > 0.0 => Some(Duration::from_secs_f64(
+                ts.saturating_sub(first_ts.unwrap_or(ts)) as f64 * millis_per_ts / 1000.0 / acceleration,
+            )),
+            _ => None,
+        };
+        if let Some(due) = due {
+            let elapsed = start.elapsed();
+            if due > elapsed {
+                out.flush()?;
+                thread::sleep(due - elapsed);
+            }
+        }
+
+        writeln!(out, "{}", line.text)?;
+        written += 1;
+        if line.ts.is_some() {
+            events += 1;
+        }
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::csv_parser::{parser_extended_wrapper, ParserReturn};
+    use parser::monpoly::parse_monpoly_log;
+
+    fn event(tp: usize, ts: usize, name: &str, args: Vec<Constant>) -> LogRecord {
+        LogRecord::Event(tp, ts, name.to_string(), args)
+    }
+
+    fn log() -> Vec<LogRecord> {
+        vec![
+            event(0, 10, "A", vec![Int(1), Str("a".to_string())]),
+            event(0, 10, "A", vec![Int(2), Str("b".to_string())]),
+            event(1, 12, "B", vec![]),
+            LogRecord::Watermark(0),
+            event(2, 15, "A", vec![Int(3), Str("c".to_string())]),
+        ]
+    }
+
+    fn convert(records: Vec<LogRecord>, options: &ReplayOptions) -> io::Result<Vec<ReplayLine>> {
+        replay_lines(records.into_iter().map(Ok), options)?.collect()
+    }
+
+    fn texts(lines: &[ReplayLine]) -> Vec<&str> {
+        lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn convert_logs() {
+        let csv = convert(log(), &ReplayOptions::new(LogFormat::Csv)).unwrap();
+        assert_eq!(texts(&csv), vec![
+            "A, tp=0, ts=10, x0=1, x1='a'",
+            "A, tp=0, ts=10, x0=2, x1='b'",
+            "B, tp=1, ts=12",
+            ">WATERMARK 0<",
+            "A, tp=2, ts=15, x0=3, x1='c'",
+        ]);
+
+        let monpoly = convert(log(), &ReplayOptions::new(LogFormat::MonPoly)).unwrap();
+        assert_eq!(texts(&monpoly), vec!["@10 A(1,\"a\")(2,\"b\");", "@12 B();", "@15 A(3,\"c\");"]);
+        let parsed = parse_monpoly_log(&texts(&monpoly).join("\n"), None).unwrap();
+        assert_eq!(parsed.len(), 3);
+
+        // JSON and back
+        let json = convert(log(), &ReplayOptions::new(LogFormat::Json)).unwrap();
+        assert_eq!(json[0].text, r#"{"predicate":"A","timestamp":10,"x0":1,"x1":"a"}"#);
+        let records = json.iter().map(|l| match parser_extended_wrapper(l.text.clone()) {
+            ParserReturn::Watermark(wm) => LogRecord::Watermark(wm),
+            _ => LogRecord::Json(l.ts.unwrap(), l.text.clone()),
+        }).collect();
+        assert_eq!(texts(&convert(records, &ReplayOptions::new(LogFormat::Csv)).unwrap()), texts(&csv));
+    }
+
+    #[test]
+    fn reorder_with_watermarks() {
+        let records: Vec<LogRecord> = (0..50).flat_map(|tp| vec![
+            event(tp, tp * 2, "A", vec![Int(tp as i32)]),
+            event(tp, tp * 2, "B", vec![Int(tp as i32)]),
+        ]).collect();
+        let mut options = ReplayOptions::new(LogFormat::Csv);
+        options.reorder = 3;
+        options.watermark_period = Some(5);
+        let lines = convert(records, &options).unwrap();
+
+        assert_eq!(lines.iter().filter(|l| l.ts.is_some()).count(), 100);
+        let mut watermark = -1;
+        let mut max_tp = 0;
+        let mut reordered = false;
+        for line in &lines {
+            match parser_extended_wrapper(line.text.clone()) {
+                ParserReturn::Watermark(wm) => {
+                    assert!(wm >= watermark);
+                    watermark = wm;
+                }
+                ParserReturn::Data(tp, _, _) => {
+                    assert!(tp as i64 > watermark);
+                    assert!(tp + 3 >= max_tp);
+                    reordered |= tp < max_tp;
+                    max_tp = max_tp.max(tp);
+                }
+                ParserReturn::Error(e) => panic!("{}", e),
+            }
+        }
+        assert!(reordered);
+        assert!(convert(vec![], &ReplayOptions { reorder: 1, ..ReplayOptions::new(LogFormat::MonPoly) }).is_err());
+    }
+
+    #[test]
+    fn paced_replay() {
+        let mut options = ReplayOptions::new(LogFormat::Csv);
+        options.rate = Some(100.0);
+        let lines = convert(log(), &options).unwrap();
+        let start = Instant::now();
+        let mut out = Vec::new();
+        assert_eq!(replay(lines.into_iter().map(Ok), &options, &mut out).unwrap(), 5);
+        // the fourth event is due after 30ms
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 5);
+    }
+
+    #[test]
+    fn quotes_and_large_ints() {
+        let quoted = event(0, 1, "A", vec![Str("it's".to_string()), Str("say \"hi\"".to_string())]);
+        let lines = convert(vec![quoted], &ReplayOptions::new(LogFormat::Csv)).unwrap();
+        assert_eq!(texts(&lines), vec!["A, tp=0, ts=1, x0=\"it's\", x1='say \"hi\"'"]);
+        match parser_extended_wrapper(lines[0].text.clone()) {
+            ParserReturn::Data(0, 1, f) => assert_eq!(f.to_string(), "A('it's','say \"hi\"')"),
+            _ => panic!("Unable to parse {}", lines[0].text),
+        }
+        let both = event(0, 1, "A", vec![Str("it's \"both\"".to_string())]);
+        assert!(convert(vec![both], &ReplayOptions::new(LogFormat::Csv)).is_err());
+
+        // ints that do not fit an argument are no events, and only JSON can keep them
+        let large = r#"{"predicate":"A","timestamp":3,"x0":4294967296}"#.to_string();
+        assert!(convert(vec![LogRecord::Json(3, large.clone())], &ReplayOptions::new(LogFormat::Csv)).is_err());
+        assert_eq!(texts(&convert(vec![LogRecord::Json(3, large.clone())], &ReplayOptions::new(LogFormat::Json)).unwrap()), vec![large.as_str()]);
+    }
+
+    #[test]
+    fn lines_follow_the_records() {
+        // lines are produced before the log ends, an unreadable record ends them
+        let records = vec![Ok(event(0, 1, "A", vec![])), Err(invalid("unreadable".to_string())), Ok(event(1, 2, "A", vec![]))];
+        let mut lines = replay_lines(records, &ReplayOptions::new(LogFormat::Csv)).unwrap();
+        assert_eq!(lines.next().unwrap().unwrap().text, "A, tp=0, ts=1");
+        assert!(lines.next().unwrap().is_err());
+        assert!(lines.next().is_none());
+
+        let unordered = vec![event(1, 2, "A", vec![]), event(0, 1, "A", vec![])];
+        assert!(convert(unordered.clone(), &ReplayOptions::new(LogFormat::MonPoly)).is_err());
+        assert!(convert(unordered.clone(), &ReplayOptions { watermark_period: Some(1), ..ReplayOptions::new(LogFormat::Csv) }).is_err());
+        assert_eq!(convert(unordered, &ReplayOptions::new(LogFormat::Csv)).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Consumer of the verdicts, given as `tcp:<host>:<port>` or `unix:<path>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SinkAddress {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl FromStr for SinkAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("tcp:") {
+            Ok(SinkAddress::Tcp(addr.to_string()))
+        } else if let Some(path) = s.strip_prefix("unix:") {
+            Ok(SinkAddress::Unix(PathBuf::from(path)))
+        } else {
+            Err(format!("Invalid sink {}, expected tcp:<host>:<port> or unix:<path>", s))
+        }
+    }
+}
+
+impl fmt::Display for SinkAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkAddress::Tcp(addr) => write!(f, "tcp:{}", addr),
+            SinkAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+fn connect(address: &SinkAddress) -> io::Result<Box<dyn Write + Send>> {
+    match address {
+        SinkAddress::Tcp(addr) => {
+            let mut last_err = io::Error::new(io::ErrorKind::NotFound, format!("Unable to resolve {}", addr));
+            for socket_addr in addr.to_socket_addrs()? {
+                match TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT) {
+                    Ok(stream) => return Ok(Box::new(stream)),
+                    Err(e) => last_err = e,
+                }
+            }
+            Err(last_err)
+        }
+        #[cfg(unix)]
+        SinkAddress::Unix(path) => Ok(Box::new(UnixStream::connect(path)?)),
+        #[cfg(not(unix))]
+        SinkAddress::Unix(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "Unix-domain sockets are not supported")),
+    }
+}
+
+struct SinkState {
+    // complete lines that are not yet handed to the writer thread, at most capacity
+    queue: VecDeque<Vec<u8>>,
+    capacity: usize,
+    dropped: usize,
+    // wait for room in the queue instead of dropping the oldest line
+    blocking: bool,
+    // the writer thread is in the middle of a line
+    busy: bool,
+    // deliver the queued lines and stop
+    closing: bool,
+    // stop even if lines are left
+    stopped: bool,
+}
+
+impl SinkState {
+    fn push(&mut self, line: Vec<u8>) {
+        if self.queue.len() >= self.capacity {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(line);
+    }
+
+    fn is_full(&self) -> bool {
+        self.blocking && !self.stopped && self.queue.len() >= self.capacity
+    }
+
+    fn undelivered(&self) -> usize {
+        self.queue.len() + self.busy as usize
+    }
+}
+
+struct Shared {
+    state: Mutex<SinkState>,
+    changed: Condvar,
+}
+
+// Writes the whole line. A line that is cut off by a broken connection is sent again in full on
+// the next connection, as the consumer cannot continue a line that it received on another one.
+fn send_line(connection: &mut dyn Write, line: &[u8]) -> io::Result<()> {
+    connection.write_all(line)?;
+    connection.flush()
+}
+
+// Runs on its own thread and delivers the queued lines one by one, (re)connecting at most once
+// per backoff period, until the sink is closed.
+fn deliver(address: SinkAddress, shared: Arc<Shared>) {
+    let mut connection: Option<Box<dyn Write + Send>> = None;
+    let mut backoff = MIN_BACKOFF;
+    // the line that is being written
+    let mut current: Option<Vec<u8>> = None;
+
+    loop {
+        {
+            let mut state = shared.state.lock().unwrap();
+            while current.is_none() && state.queue.is_empty() && !state.closing && !state.stopped {
+                state = shared.changed.wait(state).unwrap();
+            }
+            if state.stopped || (current.is_none() && state.queue.is_empty()) {
+                return;
+            }
+        }
+
+        if connection.is_none() {
+            match connect(&address) {
+                Ok(new_connection) => {
+                    connection = Some(new_connection);
+                    backoff = MIN_BACKOFF;
+                    let mut state = shared.state.lock().unwrap();
+                    if state.dropped > 0 {
+                        eprintln!("Sink {}: dropped {} verdict lines while unavailable", address, state.dropped);
+                        state.dropped = 0;
+                    }
+                }
+                Err(_) => {
+                    let deadline = Instant::now() + backoff;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    let mut state = shared.state.lock().unwrap();
+                    while !state.stopped && Instant::now() < deadline {
+                        state = shared.changed.wait_timeout(state, deadline.saturating_duration_since(Instant::now())).unwrap().0;
+                    }
+                    continue;
+                }
+            }
+        }
+
+        if current.is_none() {
+            let mut state = shared.state.lock().unwrap();
+            current = state.queue.pop_front();
+            state.busy = current.is_some();
+        }
+        if let (Some(line), Some(conn)) = (current.as_ref(), connection.as_mut()) {
+            if send_line(conn.as_mut(), line).is_err() {
+                // resend the line after reconnecting
+                connection = None;
+                continue;
+            }
+            current = None;
+            let mut state = shared.state.lock().unwrap();
+            state.busy = false;
+            shared.changed.notify_all();
+        }
+    }
+}
+
+/// Pushes verdict lines to a TCP or Unix-domain socket. The lines are written by a separate thread,
+/// so writing to the sink never waits for the consumer. If the consumer is slow or unavailable,
+/// the most recent `capacity` lines are buffered and delivered once it accepts connections again.
+/// A blocking sink keeps all lines and makes writes wait while `capacity` lines are buffered.
+/// Clones share the buffer and only hand complete lines to it, so that the lines of different
+/// workers do not interleave.
+pub struct SocketSink {
+    shared: Arc<Shared>,
+    // bytes of the current line that is not yet complete
+    pending: Vec<u8>,
+    address: SinkAddress,
+}
+
+impl SocketSink {
+    pub fn new(address: SinkAddress, capacity: usize) -> Self {
+        SocketSink::with_mode(address, capacity, false)
+    }
+
+    /// A sink that applies backpressure instead of dropping lines.
+    pub fn blocking(address: SinkAddress, capacity: usize) -> Self {
+        SocketSink::with_mode(address, capacity, true)
+    }
+
+    fn with_mode(address: SinkAddress, capacity: usize, blocking: bool) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SinkState {
+                queue: VecDeque::new(),
+                capacity: capacity.max(1),
+                dropped: 0,
+                blocking,
+                busy: false,
+                closing: false,
+                stopped: false,
+            }),
+            changed: Condvar::new(),
+        });
+        let writer_shared = shared.clone();
+        let writer_address = address.clone();
+        thread::spawn(move || deliver(writer_address, writer_shared));
+        SocketSink { shared, pending: Vec::new(), address }
+    }
+
+    /// Waits at most `timeout` for the buffered lines to be delivered and stops the writer thread.
+    pub fn close(&mut self, timeout: Duration) -> io::Result<()> {
+        self.queue_complete_lines();
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.state.lock().unwrap();
+        state.closing = true;
+        self.shared.changed.notify_all();
+        while state.undelivered() > 0 && Instant::now() < deadline {
+            state = self.shared.changed.wait_timeout(state, deadline.saturating_duration_since(Instant::now())).unwrap().0;
+        }
+        state.stopped = true;
+        self.shared.changed.notify_all();
+        match state.undelivered() {
+            0 => Ok(()),
+            n => Err(io::Error::new(io::ErrorKind::TimedOut, format!("Sink {}: {} verdict lines not delivered", self.address, n))),
+        }
+    }
+
+    fn queue_complete_lines(&mut self) {
+        if let Some(end) = self.pending.iter().rposition(|b| *b == b'\n') {
+            let rest = self.pending.split_off(end + 1);
+            let complete = std::mem::replace(&mut self.pending, rest);
+            let mut state = self.shared.state.lock().unwrap();
+            for line in complete.split_inclusive(|b| *b == b'\n') {
+                while state.is_full() {
+                    self.shared.changed.notify_all();
+                    state = self.shared.changed.wait(state).unwrap();
+                }
+                state.push(line.to_vec());
+            }
+            self.shared.changed.notify_all();
+        }
+    }
+}
+
+impl Clone for SocketSink {
+    fn clone(&self) -> Self {
+        SocketSink { shared: self.shared.clone(), pending: Vec::new(), address: self.address.clone() }
+    }
+}
+
+impl fmt::Debug for SocketSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SocketSink({})", self.address)
+    }
+}
+
+impl Write for SocketSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if buf.contains(&b'\n') {
+            self.queue_complete_lines();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.queue_complete_lines();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use stream_io::socket_sink::{send_line, SinkAddress, SocketSink};
+
+    fn read_lines<R: std::io::Read>(stream: R) -> Vec<String> {
+        BufReader::new(stream).lines().map(|line| line.unwrap()).collect()
+    }
+
+    #[test]
+    fn sink_address() {
+        assert_eq!("tcp:127.0.0.1:4000".parse(), Ok(SinkAddress::Tcp("127.0.0.1:4000".to_string())));
+        assert_eq!("unix:/tmp/verdicts.sock".parse(), Ok(SinkAddress::Unix("/tmp/verdicts.sock".into())));
+        assert!("127.0.0.1:4000".parse::<SinkAddress>().is_err());
+    }
+
+    #[test]
+    fn tcp_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let consumer = thread::spawn(move || read_lines(listener.accept().unwrap().0));
+
+        let mut sink = SocketSink::new(SinkAddress::Tcp(addr.to_string()), 10);
+        let mut other_worker = sink.clone();
+        write!(sink, "@0 (time point 0): ").unwrap();
+        writeln!(other_worker, "@1 (time point 1): (Int(2))").unwrap();
+        writeln!(sink, "(Int(1))").unwrap();
+        sink.flush().unwrap();
+        other_worker.flush().unwrap();
+        sink.close(Duration::from_secs(5)).unwrap();
+
+        let mut lines = consumer.join().unwrap();
+        lines.sort();
+        assert_eq!(lines, vec!["@0 (time point 0): (Int(1))", "@1 (time point 1): (Int(2))"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_sink_reconnect() {
+        // no consumer is listening yet
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verdicts.sock");
+        let mut sink = SocketSink::new(SinkAddress::Unix(path.clone()), 2);
+        for tp in 0..3 {
+            writeln!(sink, "@{tp} (time point {tp}): true").unwrap();
+            sink.flush().unwrap();
+        }
+
+        // the consumer starts, only the most recent lines are kept
+        let listener = UnixListener::bind(&path).unwrap();
+        let consumer = thread::spawn(move || read_lines(listener.accept().unwrap().0));
+        sink.close(Duration::from_secs(5)).unwrap();
+
+        assert_eq!(consumer.join().unwrap(), vec!["@1 (time point 1): true", "@2 (time point 2): true"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn blocking_sink_keeps_every_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verdicts.sock");
+        let sink = SocketSink::blocking(SinkAddress::Unix(path.clone()), 2);
+        let mut writer = sink.clone();
+        let producer = thread::spawn(move || {
+            for tp in 0..5 {
+                writeln!(writer, "@{tp} (time point {tp}): true").unwrap();
+                writer.flush().unwrap();
+            }
+        });
+
+        // without a consumer, the writes wait once the buffer is full
+        thread::sleep(Duration::from_millis(200));
+        assert!(!producer.is_finished());
+
+        let listener = UnixListener::bind(&path).unwrap();
+        let consumer = thread::spawn(move || read_lines(listener.accept().unwrap().0));
+        producer.join().unwrap();
+        let mut sink = sink;
+        sink.close(Duration::from_secs(5)).unwrap();
+        assert_eq!(consumer.join().unwrap().len(), 5);
+    }
+
+    // accepts limit bytes and fails afterwards, like a connection that breaks
+    struct BreakingConnection {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for BreakingConnection {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.written.len() >= self.limit {
+                return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "broken"));
+            }
+            let n = buf.len().min(self.limit - self.written.len());
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn partially_written_line_is_resent() {
+        let line = b"@0 (time point 0): true\n";
+        let mut first = BreakingConnection { written: Vec::new(), limit: 5 };
+        assert!(send_line(&mut first, line).is_err());
+        assert_eq!(first.written, line[..5].to_vec());
+
+        let mut second = BreakingConnection { written: Vec::new(), limit: 100 };
+        send_line(&mut second, line).unwrap();
+        assert_eq!(second.written, line.to_vec());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_do_not_wait_for_a_stalled_consumer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verdicts.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let mut sink = SocketSink::new(SinkAddress::Unix(path), 1_000_000);
+        // the consumer accepts the connection but never reads
+        writeln!(sink, "@0 (time point 0): true").unwrap();
+        let _connection = listener.accept();
+
+        let start = Instant::now();
+        for tp in 1..100_000 {
+            writeln!(sink, "@{tp} (time point {tp}): true").unwrap();
+        }
+        sink.flush().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(sink.close(Duration::from_millis(100)).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verdicts.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let consumer = thread::spawn(move || read_lines(listener.accept().unwrap().0));
+
+        let mut sink = SocketSink::new(SinkAddress::Unix(path), 10);
+        writeln!(sink, "@0 (time point 0): false").unwrap();
+        sink.close(Duration::from_secs(5)).unwrap();
+
+        assert_eq!(consumer.join().unwrap(), vec!["@0 (time point 0): false"]);
+    }
+}
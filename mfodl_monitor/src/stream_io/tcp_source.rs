@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use parser::csv_parser::{parser_extended_wrapper, ParserReturn};
+
+// A line with this command ends the input of all connections
+pub const SHUTDOWN_COMMAND: &str = ">SHUTDOWN<";
+
+enum Event {
+    Connected(usize),
+    Line(usize, String),
+    Closed(usize),
+    Shutdown,
+}
+
+/// Merges the lines of all TCP connections accepted on a listener into a single stream of events.
+/// Watermarks of the connections are not forwarded as they are, instead a merged watermark is
+/// emitted whenever the minimum watermark over all open connections increases. The stream ends
+/// once every accepted connection is closed or a connection sends the shutdown command.
+pub struct TcpLines {
+    local_addr: SocketAddr,
+    events: Receiver<Event>,
+    // last watermark of every open connection, None until the connection sends one
+    watermarks: HashMap<usize, Option<i64>>,
+    merged_watermark: Option<i64>,
+    connected: bool,
+}
+
+impl TcpLines {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<TcpLines> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (send, events) = channel();
+        thread::spawn(move || accept_connections(listener, send));
+
+        Ok(TcpLines {
+            local_addr,
+            events,
+            watermarks: HashMap::new(),
+            merged_watermark: None,
+            connected: false,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    // the minimum watermark over all open connections if it increased
+    fn advance_watermark(&mut self) -> Option<String> {
+        let mut min_watermark = None;
+        for watermark in self.watermarks.values() {
+            match (watermark, min_watermark) {
+                (None, _) => return None,
+                (Some(wm), Some(min_wm)) if *wm >= min_wm => {}
+                (Some(wm), _) => min_watermark = Some(*wm),
+            }
+        }
+
+        match (min_watermark, self.merged_watermark) {
+            (Some(wm), Some(merged)) if wm <= merged => None,
+            (Some(wm), _) => {
+                self.merged_watermark = Some(wm);
+                Some(format!(">WATERMARK {}<", wm))
+            }
+            (None, _) => None,
+        }
+    }
+}
+
+impl Iterator for TcpLines {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.connected && self.watermarks.is_empty() {
+            return None;
+        }
+
+        while let Ok(event) = self.events.recv() {
+            match event {
+                Event::Connected(id) => {
+                    self.connected = true;
+                    self.watermarks.insert(id, None);
+                }
+                Event::Line(id, line) => {
+                    if let ParserReturn::Watermark(wm) = parser_extended_wrapper(line.clone()) {
+                        self.watermarks.insert(id, Some(wm));
+                        if let Some(merged) = self.advance_watermark() {
+                            return Some(merged);
+                        }
+                    } else {
+                        return Some(line);
+                    }
+                }
+                Event::Closed(id) => {
+                    self.watermarks.remove(&id);
+                    if self.watermarks.is_empty() {
+                        return None;
+                    }
+                    if let Some(merged) = self.advance_watermark() {
+                        return Some(merged);
+                    }
+                }
+                Event::Shutdown => {
+                    self.connected = true;
+                    self.watermarks.clear();
+                    return None;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn accept_connections(listener: TcpListener, send: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        match stream {
+            Ok(stream) => {
+                // registered before any of its lines can arrive
+                if send.send(Event::Connected(id)).is_err() {
+                    return;
+                }
+                let send = send.clone();
+                thread::spawn(move || read_connection(id, stream, send));
+            }
+            Err(e) => eprintln!("Connection Error: {}", e),
+        }
+    }
+}
+
+fn read_connection(id: usize, stream: TcpStream, send: Sender<Event>) {
+    for line in BufReader::new(stream).lines() {
+        let event = match line {
+            Ok(line) if line.trim() == SHUTDOWN_COMMAND => Event::Shutdown,
+            Ok(line) => Event::Line(id, line),
+            Err(e) => {
+                eprintln!("Connection Error: {}", e);
+                break;
+            }
+        };
+        if send.send(event).is_err() {
+            return;
+        }
+    }
+    let _ = send.send(Event::Closed(id));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpStream;
+    use stream_io::tcp_source::{TcpLines, SHUTDOWN_COMMAND};
+
+    #[test]
+    fn merge_connections() {
+        let mut lines = TcpLines::bind("127.0.0.1:0").unwrap();
+        let addr = lines.local_addr();
+
+        let mut a = TcpStream::connect(addr).unwrap();
+        a.write_all(b"A, tp=0, ts=0, x=1\n").unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+
+        let mut b = TcpStream::connect(addr).unwrap();
+        b.write_all(b"B, tp=0, ts=0, x=2\n").unwrap();
+        assert_eq!(lines.next(), Some("B, tp=0, ts=0, x=2".to_string()));
+
+        // the merged watermark is the minimum over all open connections
+        a.write_all(b">WATERMARK 2<\n").unwrap();
+        b.write_all(b">WATERMARK 1<\n").unwrap();
+        assert_eq!(lines.next(), Some(">WATERMARK 1<".to_string()));
+
+        drop(b);
+        assert_eq!(lines.next(), Some(">WATERMARK 2<".to_string()));
+
+        drop(a);
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn shutdown_command() {
+        let mut lines = TcpLines::bind("127.0.0.1:0").unwrap();
+        let addr = lines.local_addr();
+
+        let mut a = TcpStream::connect(addr).unwrap();
+        let mut b = TcpStream::connect(addr).unwrap();
+        a.write_all(b"A, tp=0, ts=0, x=1\n").unwrap();
+        assert_eq!(lines.next(), Some("A, tp=0, ts=0, x=1".to_string()));
+
+        b.write_all(format!("{}\n", SHUTDOWN_COMMAND).as_bytes()).unwrap();
+        assert_eq!(lines.next(), None);
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -1,4 +1,7 @@
+use std::cmp;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 use std::ops::Add;
 use std::ops::AddAssign;
 use std::ops::Sub;
@@ -6,73 +9,212 @@ use std::ops::SubAssign;
 use timeunits::TP::*;
 use timeunits::TS::*;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Abomonation)]
+/// Interval of timestamp distances. Each finite bound is either closed (included) or open
+/// (excluded), the flag of an infinite end is not used. Timestamps are integers, so intervals that
+/// contain the same distances, e.g. (1,5) and [2,4], are equal.
+#[derive(Debug, Copy, Clone, Abomonation)]
 pub struct TimeInterval {
     pub start: TS,
     pub end: TS,
+    pub start_open: bool,
+    pub end_open: bool,
+    /// Units the bounds were written in, a bound without one is a number of timestamps.
+    pub start_unit: Option<TimeUnit>,
+    pub end_unit: Option<TimeUnit>,
+    /// Unit of a timestamp once `in_resolution` converted the bounds with a unit, None before.
+    pub resolution: Option<TimeUnit>,
 }
 
 impl TimeInterval {
     pub fn new(start: TS, end: TS) -> TimeInterval {
-        TimeInterval { start, end }
+        TimeInterval::with_bounds(start, false, end, false)
+    }
+
+    pub fn with_bounds(start: TS, start_open: bool, end: TS, end_open: bool) -> TimeInterval {
+        TimeInterval { start, end, start_open, end_open, start_unit: None, end_unit: None, resolution: None }
+    }
+
+    /// Converts the bounds written with a unit to timestamps of the given resolution, or returns the
+    /// first bound that is not a whole number of timestamps, e.g. 1500ms for seconds.
+    pub fn in_resolution(self, resolution: TimeUnit) -> Result<TimeInterval, String> {
+        if self.resolution.is_some() {
+            return Ok(self);
+        }
+        let convert = |bound, unit: Option<TimeUnit>| match (bound, unit) {
+            (FINITE(n), Some(unit)) => convert_to_timestamps(n, unit, resolution)
+                .map(FINITE)
+                .ok_or_else(|| format!("{}{}", n, unit.suffix())),
+            (bound, _) => Ok(bound),
+        };
+        Ok(TimeInterval {
+            start: convert(self.start, self.start_unit)?,
+            end: convert(self.end, self.end_unit)?,
+            resolution: Some(resolution),
+            ..self
+        })
     }
 
+    /// The start as written, it is excluded if `start_open` is set.
     pub fn get_start(self) -> Option<usize> {
-        match self {
-            TimeInterval { start, end: _ } => match start {
-                FINITE(x) => Some(x),
-                INFINITY => None,
-                _ => None,
-            },
+        match self.start {
+            FINITE(x) => Some(x),
+            _ => None,
         }
     }
 
+    /// The end as written, it is excluded if `end_open` is set. None if the interval is unbounded.
     pub fn get_end(self) -> Option<usize> {
-        match self {
-            TimeInterval { start: _, end } => match end {
-                FINITE(x) => Some(x),
-                INFINITY => None,
-                _ => None,
-            },
+        match self.end {
+            FINITE(x) => Some(x),
+            _ => None,
         }
     }
 
     pub fn get_raw_start(self) -> usize {
-        match self {
-            TimeInterval { start, end: _ } => match start {
-                FINITE(x) => x,
-                INFINITY => 0,
-                _ => 0,
-            },
-        }
+        self.get_start().unwrap_or(0)
     }
 
     pub fn get_raw_end(self) -> usize {
-        match self {
-            TimeInterval { start: _, end } => match end {
-                FINITE(x) => x,
-                INFINITY => 0,
-                _ => 0,
-            },
-        }
+        self.get_end().unwrap_or(0)
     }
 
     pub fn is_infinite(self) -> bool {
         match self {
-            TimeInterval { start: _, end } => match end {
+            TimeInterval { end, .. } => match end {
                 FINITE(_) => false,
                 INFINITY => true,
                 _ => false,
             }
         }
     }
+
+    /// Whether the distance is not below the start, honouring an open start.
+    pub fn above_start(self, distance: usize) -> bool {
+        match self.start {
+            FINITE(a) if self.start_open => distance > a,
+            FINITE(a) => distance >= a,
+            _ => false,
+        }
+    }
+
+    /// Whether the distance is not beyond the end, honouring an open end.
+    pub fn below_end(self, distance: usize) -> bool {
+        match self.end {
+            FINITE(b) if self.end_open => distance < b,
+            FINITE(b) => distance <= b,
+            INFINITY => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the distance lies within the bounds, honouring open bounds.
+    pub fn contains(self, distance: usize) -> bool {
+        self.above_start(distance) && self.below_end(distance)
+    }
+
+    /// Whether no distance lies within the bounds, e.g. for (3,4) or [5,2].
+    pub fn is_empty(self) -> bool {
+        match (self.start, self.end) {
+            (FINITE(a), FINITE(b)) => b < a || (a == b && (self.start_open || self.end_open)) || (b == a + 1 && self.start_open && self.end_open),
+            (FINITE(_), INFINITY) => false,
+            _ => true,
+        }
+    }
+
+    /// The window of a past operator at `ts`: the timestamps whose distance to `ts` lies in the
+    /// interval. The end of the interval becomes the start of the window and vice versa, together
+    /// with their flags. None if the window lies entirely before timestamp 0.
+    pub fn past_window(self, ts: usize) -> Option<TimeInterval> {
+        let end = match self.start {
+            FINITE(a) if a < ts || (a == ts && !self.start_open) => FINITE(ts - a),
+            _ => return None,
+        };
+        let (start, start_open) = match self.end {
+            FINITE(b) if b <= ts => (FINITE(ts - b), self.end_open),
+            _ => (FINITE(0), false),
+        };
+        Some(TimeInterval::with_bounds(start, start_open, end, self.start_open))
+    }
+
+    /// The window of a future operator at `ts`: the timestamps whose distance from `ts` lies in the
+    /// interval.
+    pub fn future_window(self, ts: usize) -> TimeInterval {
+        self + ts
+    }
+
+    /// The latest timestamp whose future window ends before `ts`, None if there is none or the
+    /// interval is unbounded.
+    pub fn last_complete_ts(self, ts: usize) -> Option<usize> {
+        match self.end {
+            FINITE(b) if self.end_open => ts.checked_sub(b),
+            FINITE(b) => ts.checked_sub(b + 1),
+            _ => None,
+        }
+    }
+
+    /// The earliest timestamp of a window.
+    pub fn first_ts(self) -> usize {
+        match self.start {
+            FINITE(a) if self.start_open => a + 1,
+            FINITE(a) => a,
+            _ => 0,
+        }
+    }
+
+    /// The latest timestamp of a window, None if it is unbounded or lies before timestamp 0.
+    pub fn last_ts(self) -> Option<usize> {
+        match self.end {
+            FINITE(b) if self.end_open => b.checked_sub(1),
+            FINITE(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    // The first distance in the interval and the first one after it
+    fn key(self) -> (TS, TS) {
+        let end = match self.end {
+            FINITE(b) if !self.end_open => FINITE(b + 1),
+            end => end,
+        };
+        let start = match self.start {
+            FINITE(_) => FINITE(self.first_ts()),
+            start => start,
+        };
+        (start, end)
+    }
+}
+
+impl PartialEq for TimeInterval {
+    fn eq(&self, other: &TimeInterval) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for TimeInterval {}
+
+impl Hash for TimeInterval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl PartialOrd for TimeInterval {
+    fn partial_cmp(&self, other: &TimeInterval) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TimeInterval {
+    fn cmp(&self, other: &TimeInterval) -> cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
 }
 
 impl Add<usize> for TimeInterval {
     type Output = TimeInterval;
 
     fn add(self, other: usize) -> TimeInterval {
-        TimeInterval::new(self.start + other, self.end + other)
+        TimeInterval { start: self.start + other, end: self.end + other, ..self }
     }
 }
 
@@ -86,7 +228,7 @@ impl Sub<usize> for TimeInterval {
     type Output = TimeInterval;
 
     fn sub(self, other: usize) -> TimeInterval {
-        TimeInterval::new(self.start - other, self.end - other)
+        TimeInterval { start: self.start - other, end: self.end - other, ..self }
     }
 }
 
@@ -96,6 +238,98 @@ impl SubAssign<usize> for TimeInterval {
     }
 }
 
+// Written as in the formula with a bracket for each bound, bounds with a unit are written in the
+// largest unit that divides them
+impl fmt::Display for TimeInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound = |ts: TS, unit: Option<TimeUnit>| match (ts, unit) {
+            (FINITE(n), Some(unit)) => match self.resolution {
+                Some(resolution) => format_timestamps(n, resolution),
+                None => format!("{}{}", n, unit.suffix()),
+            },
+            (FINITE(n), None) => n.to_string(),
+            (INFINITY, _) => "*".to_string(),
+            (ts, _) => ts.to_string(),
+        };
+        let open = if self.start_open { "(" } else { "[" };
+        let close = if self.end_open || self.end == INFINITY { ")" } else { "]" };
+        write!(f, "{}{},{}{}", open, bound(self.start, self.start_unit), bound(self.end, self.end_unit), close)
+    }
+}
+
+/// Units of interval bounds and of the timestamps of the log.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Abomonation)]
+pub enum TimeUnit {
+    Millisecond,
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl TimeUnit {
+    const ALL: [TimeUnit; 5] = [TimeUnit::Millisecond, TimeUnit::Second, TimeUnit::Minute, TimeUnit::Hour, TimeUnit::Day];
+
+    pub fn millis(self) -> usize {
+        match self {
+            TimeUnit::Millisecond => 1,
+            TimeUnit::Second => 1000,
+            TimeUnit::Minute => 60 * 1000,
+            TimeUnit::Hour => 60 * 60 * 1000,
+            TimeUnit::Day => 24 * 60 * 60 * 1000,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeUnit::Millisecond => "ms",
+            TimeUnit::Second => "s",
+            TimeUnit::Minute => "m",
+            TimeUnit::Hour => "h",
+            TimeUnit::Day => "d",
+        }
+    }
+}
+
+impl FromStr for TimeUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TimeUnit::ALL
+            .iter()
+            .find(|unit| unit.suffix() == s)
+            .cloned()
+            .ok_or_else(|| format!("Invalid time unit {}, expected ms, s, m, h or d", s))
+    }
+}
+
+/// Converts a bound given in `unit` to timestamps of the given resolution, or None if it is not a
+/// whole number of timestamps.
+pub fn convert_to_timestamps(value: usize, unit: TimeUnit, resolution: TimeUnit) -> Option<usize> {
+    let millis = value.checked_mul(unit.millis())?;
+    if millis.is_multiple_of(resolution.millis()) {
+        Some(millis / resolution.millis())
+    } else {
+        None
+    }
+}
+
+/// Writes timestamps of the given resolution in the largest unit that divides them, e.g. 300
+/// timestamps in seconds as 5m.
+pub fn format_timestamps(ts: usize, resolution: TimeUnit) -> String {
+    if ts == 0 {
+        return "0".to_string();
+    }
+    let millis = ts * resolution.millis();
+    let unit = TimeUnit::ALL
+        .iter()
+        .rev()
+        .find(|unit| **unit >= resolution && millis.is_multiple_of(unit.millis()))
+        .cloned()
+        .unwrap_or(resolution);
+    format!("{}{}", millis / unit.millis(), unit.suffix())
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Abomonation)]
 pub enum TP {
     CURR,
@@ -238,6 +472,68 @@ impl SubAssign<usize> for TS {
 mod tests {
     use super::*;
 
+    #[test]
+    fn time_unit_conversion() {
+        assert_eq!(convert_to_timestamps(5, TimeUnit::Minute, TimeUnit::Second), Some(300));
+        assert_eq!(convert_to_timestamps(2, TimeUnit::Day, TimeUnit::Millisecond), Some(172_800_000));
+        assert_eq!(convert_to_timestamps(1500, TimeUnit::Millisecond, TimeUnit::Second), None);
+        assert_eq!("ms".parse(), Ok(TimeUnit::Millisecond));
+        assert!("y".parse::<TimeUnit>().is_err());
+    }
+
+    #[test]
+    fn time_unit_formatting() {
+        assert_eq!(format_timestamps(300, TimeUnit::Second), "5m");
+        assert_eq!(format_timestamps(90, TimeUnit::Second), "90s");
+        assert_eq!(format_timestamps(0, TimeUnit::Millisecond), "0");
+        assert_eq!(format_timestamps(7_200_000, TimeUnit::Millisecond), "2h");
+
+        let written = TimeInterval { start_unit: Some(TimeUnit::Minute), ..TimeInterval::new(FINITE(1), FINITE(1500)) };
+        assert_eq!(TimeInterval { end_open: true, ..written }.to_string(), "[1m,1500)");
+        let converted = written.in_resolution(TimeUnit::Millisecond).unwrap();
+        assert_eq!(converted, TimeInterval::new(FINITE(60_000), FINITE(1500)));
+        assert_eq!(converted.to_string(), "[1m,1500]");
+        assert_eq!(written.in_resolution(TimeUnit::Hour), Err("1m".to_string()));
+    }
+
+    #[test]
+    fn open_interval_bounds() {
+        let half_open = TimeInterval::with_bounds(FINITE(2), true, FINITE(5), false);
+        assert!(!half_open.contains(2));
+        assert!(half_open.contains(3));
+        assert!(half_open.contains(5));
+        assert_eq!((half_open.get_start(), half_open.get_end()), (Some(2), Some(5)));
+        assert_eq!(half_open, TimeInterval::new(FINITE(3), FINITE(5)));
+        assert_eq!(TimeInterval::with_bounds(FINITE(0), false, FINITE(1), true), TimeInterval::new(FINITE(0), FINITE(0)));
+        assert_ne!(half_open, TimeInterval::with_bounds(FINITE(2), false, FINITE(5), false));
+
+        let open = TimeInterval::with_bounds(FINITE(2), true, INFINITY, false);
+        assert!(!open.contains(2));
+        assert!(open.contains(1000));
+
+        assert!(TimeInterval::with_bounds(FINITE(3), true, FINITE(4), true).is_empty());
+        assert!(TimeInterval::with_bounds(FINITE(0), false, FINITE(0), true).is_empty());
+        assert!(TimeInterval::new(FINITE(5), FINITE(2)).is_empty());
+        assert!(!TimeInterval::new(FINITE(0), FINITE(0)).is_empty());
+    }
+
+    #[test]
+    fn windows_keep_the_flags() {
+        let interval = TimeInterval::with_bounds(FINITE(2), true, FINITE(5), false);
+        let past = interval.past_window(10).unwrap();
+        assert_eq!((past.start, past.start_open, past.end, past.end_open), (FINITE(5), false, FINITE(8), true));
+        assert_eq!((past.first_ts(), past.last_ts()), (5, Some(7)));
+        assert_eq!(interval.past_window(3).unwrap(), TimeInterval::new(FINITE(0), FINITE(0)));
+        assert_eq!(interval.past_window(2), None);
+
+        let future = interval.future_window(10);
+        assert_eq!((future.first_ts(), future.last_ts()), (13, Some(15)));
+        assert!(TimeInterval::with_bounds(FINITE(0), false, FINITE(0), true).last_ts().is_none());
+
+        let unbounded = TimeInterval::with_bounds(FINITE(0), false, INFINITY, false).past_window(4).unwrap();
+        assert_eq!((unbounded.first_ts(), unbounded.last_ts()), (0, Some(4)));
+    }
+
     #[test]
     fn timestamp_add() {
         let mut ts1 = FINITE(1);
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use parser::formula_syntax_tree::Constant;
+use parser::formula_syntax_tree::Constant::{Int, Str};
+use parser::signature::{ArgType, Signatures};
+use stream_io::reorder::{Delayed, DelayDistribution, Reorder};
+use stream_io::replayer::{csv_event, json_event};
+
+/// Join patterns over the predicates A, B and C:
+/// star A(w,x) B(w,y) C(w,z), linear A(w,x) B(x,y) C(y,z) and triangle A(x,y) B(y,z) C(z,x).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    Star,
+    Linear,
+    Triangle,
+}
+
+impl Pattern {
+    // Variables of A, B and C
+    fn variables(self) -> [[&'static str; 2]; 3] {
+        match self {
+            Pattern::Star => [["w", "x"], ["w", "y"], ["w", "z"]],
+            Pattern::Linear => [["w", "x"], ["x", "y"], ["y", "z"]],
+            Pattern::Triangle => [["x", "y"], ["y", "z"], ["z", "x"]],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EventSource {
+    Pattern(Pattern),
+    /// Random events of the declared predicates, with arguments named x0, x1, ...
+    Signature(Signatures),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    /// Uniform over 0..domain
+    Uniform,
+    /// Zipf distributed rank in 1..=domain with the exponent, shifted by the offset
+    Zipf(f64, i32),
+}
+
+/// Delays the emission of the events and interleaves watermarks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutOfOrder {
+    /// Largest delay of an event, in timestamps
+    pub max_delay: usize,
+    /// Standard deviation of the (truncated normal) delay
+    pub std_dev: f64,
+    /// Emission time between two watermarks
+    pub watermark_period: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceConfig {
+    pub source: EventSource,
+    /// Number of time points
+    pub length: usize,
+    /// Events per time point
+    pub event_rate: usize,
+    /// Timestamp difference of consecutive time points
+    pub ts_spacing: usize,
+    /// Values are drawn from 0..domain
+    pub domain: i32,
+    /// Distribution of each variable (pattern) or argument name (signature), uniform otherwise
+    pub distributions: HashMap<String, Distribution>,
+    /// Relative frequencies of A and B events, C takes the rest
+    pub ratio_a: f64,
+    pub ratio_b: f64,
+    /// Probability that a B or C event joins with an earlier event of the pattern
+    pub match_ratio: f64,
+    /// Largest timestamp distance of joining events
+    pub window: usize,
+    pub out_of_order: Option<OutOfOrder>,
+    pub seed: usize,
+}
+
+impl TraceConfig {
+    pub fn new(source: EventSource, length: usize) -> TraceConfig {
+        TraceConfig {
+            source,
+            length,
+            event_rate: 10,
+            ts_spacing: 1,
+            domain: 1_000_000,
+            distributions: HashMap::new(),
+            ratio_a: 1.0 / 3.0,
+            ratio_b: 1.0 / 3.0,
+            match_ratio: 0.5,
+            window: 10,
+            out_of_order: None,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub tp: usize,
+    pub ts: usize,
+    pub predicate: String,
+    pub args: Vec<Constant>,
+}
+
+impl Event {
+    pub fn to_csv(&self) -> io::Result<String> {
+        csv_event(self.tp, self.ts, &self.predicate, &self.args)
+    }
+
+    pub fn to_json(&self) -> String {
+        json_event(self.ts, &self.predicate, &self.args)
+    }
+}
+
+// Samples the rank of a Zipf distribution by inverting its cumulative distribution
+struct Zipf {
+    cumulative: Vec<f64>,
+}
+
+impl Zipf {
+    fn new(domain: usize, exponent: f64) -> Zipf {
+        let mut cumulative = Vec::with_capacity(domain);
+        let mut sum = 0.0;
+        for rank in 1..domain + 1 {
+            sum += 1.0 / (rank as f64).powf(exponent);
+            cumulative.push(sum);
+        }
+        Zipf { cumulative }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let total = *self.cumulative.last().unwrap_or(&0.0);
+        let target = rng.gen::<f64>() * total;
+        match self.cumulative.binary_search_by(|c| c.partial_cmp(&target).unwrap()) {
+            Ok(i) | Err(i) => i.min(self.cumulative.len().saturating_sub(1)) + 1,
+        }
+    }
+}
+
+struct Values {
+    domain: i32,
+    zipf: HashMap<String, (Zipf, i32)>,
+}
+
+impl Values {
+    fn new(config: &TraceConfig) -> Values {
+        let mut zipf = HashMap::new();
+        for (name, distribution) in &config.distributions {
+            if let Distribution::Zipf(exponent, offset) = distribution {
+                zipf.insert(name.clone(), (Zipf::new(config.domain.max(1) as usize, *exponent), *offset));
+            }
+        }
+        Values { domain: config.domain.max(1), zipf }
+    }
+
+    fn int<R: Rng>(&self, name: &str, rng: &mut R) -> i32 {
+        match self.zipf.get(name) {
+            Some((zipf, offset)) => zipf.sample(rng) as i32 + offset,
+            None => rng.gen_range(0, self.domain),
+        }
+    }
+
+    fn value<R: Rng>(&self, name: &str, arg_type: ArgType, rng: &mut R) -> Constant {
+        match arg_type {
+            ArgType::Int => Int(self.int(name, rng)),
+            ArgType::Str => Str(format!("s{}", self.int(name, rng))),
+        }
+    }
+}
+
+/// Generates the events of the trace in time point order.
+pub fn generate_events(config: &TraceConfig) -> Vec<Event> {
+    let mut rng = StdRng::from_seed(&[config.seed]);
+    let values = Values::new(config);
+    let mut events = Vec::with_capacity(config.length * config.event_rate);
+
+    // earlier A events and joined A-B pairs within the window: (ts, valuation)
+    let mut recent_a: Vec<(usize, HashMap<&str, i32>)> = Vec::new();
+    let mut recent_ab: Vec<(usize, HashMap<&str, i32>)> = Vec::new();
+    let declared = match &config.source {
+        EventSource::Signature(signatures) => signatures.signatures(),
+        EventSource::Pattern(_) => vec![],
+    };
+
+    for tp in 0..config.length {
+        let ts = tp * config.ts_spacing;
+        recent_a.retain(|(t, _)| ts - t <= config.window);
+        recent_ab.retain(|(t, _)| ts - t <= config.window);
+
+        for _ in 0..config.event_rate {
+            match &config.source {
+                EventSource::Signature(_) => {
+                    if declared.is_empty() {
+                        break;
+                    }
+                    let signature = &declared[rng.gen_range(0, declared.len())];
+                    let args = signature.args.iter().enumerate()
+                        .map(|(i, arg_type)| values.value(&format!("x{}", i), *arg_type, &mut rng))
+                        .collect();
+                    events.push(Event { tp, ts, predicate: signature.name.clone(), args });
+                }
+                EventSource::Pattern(pattern) => {
+                    let vars = pattern.variables();
+                    let choice = rng.gen::<f64>();
+                    let joins = rng.gen::<f64>() < config.match_ratio;
+                    let (index, earlier) = if choice < config.ratio_a {
+                        (0, None)
+                    } else if choice < config.ratio_a + config.ratio_b {
+                        (1, if joins { rng.choose(&recent_a).cloned() } else { None })
+                    } else {
+                        (2, if joins { rng.choose(&recent_ab).cloned() } else { None })
+                    };
+
+                    let mut valuation = earlier.clone().map(|(_, v)| v).unwrap_or_default();
+                    for var in vars[index].iter() {
+                        if !valuation.contains_key(var) {
+                            let value = values.int(var, &mut rng);
+                            valuation.insert(var, value);
+                        }
+                    }
+                    match (index, earlier) {
+                        (0, _) => recent_a.push((ts, valuation.clone())),
+                        (1, Some(_)) => recent_ab.push((ts, valuation.clone())),
+                        _ => {}
+                    }
+
+                    let args = vars[index].iter().map(|var| Int(valuation[var])).collect();
+                    events.push(Event { tp, ts, predicate: ["A", "B", "C"][index].to_string(), args });
+                }
+            }
+        }
+    }
+
+    events
+}
+
+/// Writes the trace as CSV or (with `json`) JSON events, one per line. Out-of-order traces are
+/// written in emission order: CSV lines are prefixed with their emission time (e.g. `3'A, tp=1, ...`),
+/// and a watermark `>WATERMARK n<` is written every watermark period with the largest time point
+/// (timestamp for JSON) all of whose events were emitted, -1 if there is none.
+pub fn write_trace<W: Write>(config: &TraceConfig, json: bool, out: &mut W) -> io::Result<()> {
+    let events = generate_events(config);
+    let format = |e: &Event| if json { Ok(e.to_json()) } else { e.to_csv() };
+
+    let ooo = match config.out_of_order {
+        Some(ooo) => ooo,
+        None => {
+            for event in &events {
+                writeln!(out, "{}", format(event)?)?;
+            }
+            return Ok(());
+        }
+    };
+
+    let distribution = DelayDistribution { mean: 0.0, std_dev: ooo.std_dev, max_delay: ooo.max_delay };
+    // the delays are drawn independently of the values
+    let mut reorder = Reorder::new(distribution, Some(ooo.watermark_period), config.seed.wrapping_add(1));
+    let mut delayed = Vec::new();
+    for event in &events {
+        let label = if json { event.ts as i64 } else { event.tp as i64 };
+        delayed.extend(reorder.push(event.ts, label, event).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+        write_delayed(delayed.drain(..), json, out)?;
+    }
+    write_delayed(reorder.finish(), json, out)
+}
+
+fn write_delayed<'a, I, W>(delayed: I, json: bool, out: &mut W) -> io::Result<()>
+where
+    I: IntoIterator<Item = Delayed<&'a Event>>,
+    W: Write,
+{
+    for item in delayed {
+        match item {
+            Delayed::Item(_, event) if json => writeln!(out, "{}", event.to_json())?,
+            Delayed::Item(emission, event) => writeln!(out, "{}'{}", emission, event.to_csv()?)?,
+            Delayed::Watermark(_, watermark) if json => writeln!(out, ">WATERMARK {}<", watermark)?,
+            Delayed::Watermark(emission, watermark) => writeln!(out, "{}'>WATERMARK {}<", emission, watermark)?,
+        }
+    }
+    Ok(())
+}
+
+/// Parses per-variable distributions such as `x=1.5+3,y=2`: Zipf with exponent 1.5 and offset 3
+/// for x, and exponent 2 for y.
+pub fn parse_distributions(input: &str) -> Result<HashMap<String, Distribution>, String> {
+    let mut distributions = HashMap::new();
+    for entry in input.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, spec) = entry.split_once('=').ok_or_else(|| format!("Expected var=exponent, found {}", entry))?;
+        let (exponent, offset) = match spec.split_once('+') {
+            Some((e, o)) => (e, o.trim().parse::<i32>().map_err(|_| format!("Invalid offset in {}", entry))?),
+            None => (spec, 0),
+        };
+        let exponent = exponent.trim().parse::<f64>().map_err(|_| format!("Invalid exponent in {}", entry))?;
+        distributions.insert(name.trim().to_string(), Distribution::Zipf(exponent, offset));
+    }
+    Ok(distributions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::csv_parser::{parser_extended_wrapper, ParserReturn};
+    use serde_json::Value;
+
+    #[test]
+    fn pattern_trace() {
+        let mut config = TraceConfig::new(EventSource::Pattern(Pattern::Linear), 20);
+        config.ts_spacing = 2;
+        config.domain = 50;
+        config.match_ratio = 1.0;
+        config.distributions = parse_distributions("w=1.5+3").unwrap();
+        let events = generate_events(&config);
+
+        assert_eq!(events.len(), 200);
+        assert_eq!(events, generate_events(&config));
+        assert!(events.iter().all(|e| e.ts == 2 * e.tp && e.args.len() == 2));
+        assert!(events.iter().filter(|e| e.predicate == "A").all(|e| e.args[0] >= Int(4)));
+        // every joining B shares x with an A within the window
+        let joined = events.iter().filter(|b| b.predicate == "B").filter(|b| {
+            events.iter().any(|a| a.predicate == "A" && a.args[1] == b.args[0] && a.ts <= b.ts && b.ts - a.ts <= config.window)
+        }).count();
+        assert!(joined > 0);
+
+        for event in &events {
+            match parser_extended_wrapper(event.to_csv().unwrap()) {
+                ParserReturn::Data(tp, ts, _) => assert_eq!((tp, ts), (event.tp, event.ts)),
+                _ => panic!("Unable to parse {:?}", event.to_csv()),
+            }
+        }
+    }
+
+    #[test]
+    fn signature_and_out_of_order_trace() {
+        let signatures = Signatures::parse("P(int,string) Q()").unwrap();
+        let mut config = TraceConfig::new(EventSource::Signature(signatures.clone()), 10);
+        config.out_of_order = Some(OutOfOrder { max_delay: 2, std_dev: 5.0, watermark_period: 2 });
+
+        let mut out = Vec::new();
+        write_trace(&config, false, &mut out).unwrap();
+        let lines: Vec<String> = String::from_utf8(out).unwrap().lines().map(|l| l.to_string()).collect();
+        assert_eq!(lines.iter().filter(|l| !l.contains("WATERMARK")).count(), 100);
+
+        let mut watermark = -1;
+        for line in &lines {
+            let (emission, rest) = line.split_once('\'').unwrap();
+            match parser_extended_wrapper(rest.to_string()) {
+                ParserReturn::Watermark(wm) => {
+                    assert_eq!(wm, (emission.parse::<i64>().unwrap() - 3).max(-1));
+                    watermark = wm;
+                }
+                ParserReturn::Data(tp, ts, _) => {
+                    assert!(tp as i64 > watermark);
+                    assert!(emission.parse::<usize>().unwrap() <= ts + 2);
+                    assert!(signatures.normalize_event(rest).is_ok());
+                }
+                _ => panic!("Unable to parse {}", line),
+            }
+        }
+
+        let mut out = Vec::new();
+        config.out_of_order = None;
+        write_trace(&config, true, &mut out).unwrap();
+        let first = String::from_utf8(out).unwrap().lines().next().unwrap().to_string();
+        let value: Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(value["timestamp"], Value::from(0));
+    }
+
+    #[test]
+    fn wide_delays_stay_bounded() {
+        let mut config = TraceConfig::new(EventSource::Pattern(Pattern::Star), 20);
+        config.out_of_order = Some(OutOfOrder { max_delay: 1, std_dev: 1e12, watermark_period: 1 });
+        let mut out = Vec::new();
+        write_trace(&config, false, &mut out).unwrap();
+        for line in String::from_utf8(out).unwrap().lines() {
+            let (emission, rest) = line.split_once('\'').unwrap();
+            if let ParserReturn::Data(_, ts, _) = parser_extended_wrapper(rest.to_string()) {
+                assert!(emission.parse::<usize>().unwrap() <= ts + 1);
+            }
+        }
+    }
+}